@@ -2,15 +2,22 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path;
 
-use bindgen::config::{Config, Language};
+use bindgen::cdecl;
+use bindgen::config::{Config, ItemType, Language};
+use bindgen::dependencies::Reference;
+use bindgen::explain::{self, Explainer, Referrer};
+use bindgen::graph::{DependencyEdge, DependencyGraph, DependencyNode};
 use bindgen::ir::{
-    Constant, Function, ItemContainer, ItemMap, Path as BindgenPath, Static, Struct,
+    AnnotationSet, Cfg, ConditionWrite, Constant, Documentation, Function, Item, ItemContainer,
+    ItemMap, MappedType, Method, Path as BindgenPath, Static, Struct, ToCondition, Type,
 };
+use bindgen::reference::{ApiReference, ReferenceDefinition, ReferenceMember};
 use bindgen::writer::{Source, SourceWriter};
 
 /// A bindings header that can be written.
@@ -19,31 +26,189 @@ pub struct Bindings {
     /// The map from path to struct, used to lookup whether a given type is a
     /// transparent struct. This is needed to generate code for constants.
     struct_map: ItemMap<Struct>,
+    /// The types from `[export.type_map]`, used to spell them out.
+    mapped_types: ItemMap<MappedType>,
     globals: Vec<Static>,
     constants: Vec<Constant>,
     items: Vec<ItemContainer>,
     functions: Vec<Function>,
+    source_files: Vec<path::PathBuf>,
+    missing_files: Vec<path::PathBuf>,
+    explainer: Explainer,
+    /// The references between items found while gathering the items.
+    references: Vec<Reference>,
+    /// The names the items are exported as, by their name in rust, to resolve
+    /// links in their documentation.
+    doc_links: HashMap<String, String>,
+    /// The namespaces of the modules the items are in, by their name in rust,
+    /// with `[module_namespaces]`.
+    module_namespaces: HashMap<String, Vec<String>>,
+    /// The namespaces declared for modules, and those they're nested in.
+    declared_namespaces: HashSet<Vec<String>>,
+    /// The C++ member functions of structs and opaque items, by their name in
+    /// rust.
+    methods: HashMap<String, Vec<Method>>,
 }
 
 impl Bindings {
     pub(crate) fn new(
         config: Config,
         struct_map: ItemMap<Struct>,
+        mapped_types: ItemMap<MappedType>,
         constants: Vec<Constant>,
         globals: Vec<Static>,
         items: Vec<ItemContainer>,
         functions: Vec<Function>,
+        methods: HashMap<String, Vec<Method>>,
+        source_files: Vec<path::PathBuf>,
+        missing_files: Vec<path::PathBuf>,
+        explainer: Explainer,
+        references: Vec<Reference>,
     ) -> Bindings {
+        let mut doc_links = HashMap::new();
+        for item in &items {
+            let item = item.deref();
+            doc_links.insert(item.name().to_owned(), item.export_name().to_owned());
+        }
+        for constant in constants.iter().filter(|x| x.associated_to.is_none()) {
+            doc_links.insert(
+                constant.path.name().to_owned(),
+                constant.export_name().to_owned(),
+            );
+        }
+        for global in &globals {
+            doc_links.insert(
+                global.path.name().to_owned(),
+                global.export_name().to_owned(),
+            );
+        }
+        for function in &functions {
+            doc_links.insert(
+                function.path.name().to_owned(),
+                function.export_name.clone(),
+            );
+        }
+
+        let mut module_namespaces = HashMap::new();
+        let mut declared_namespaces = HashSet::new();
+        if config.language == Language::Cxx && config.module_namespaces.is_enabled() {
+            for name in doc_links.keys() {
+                let note = explainer
+                    .notes(name)
+                    .into_iter()
+                    .find(|x| x.item_type().is_some());
+                if let Some(note) = note {
+                    let namespace = config.module_namespaces.namespace(
+                        &note.crate_name,
+                        note.binding_crate,
+                        &note.module,
+                    );
+                    for len in 1..namespace.len() + 1 {
+                        declared_namespaces.insert(namespace[..len].to_vec());
+                    }
+                    module_namespaces.insert(name.clone(), namespace);
+                }
+            }
+        }
+
         Bindings {
             config,
             struct_map,
+            mapped_types,
             globals,
             constants,
             items,
             functions,
+            source_files,
+            missing_files,
+            explainer,
+            references,
+            doc_links,
+            module_namespaces,
+            declared_namespaces,
+            methods,
+        }
+    }
+
+    /// The C++ member functions of the struct or opaque item at `path`.
+    pub fn methods(&self, path: &BindgenPath) -> &[Method] {
+        self.methods.get(path.name()).map(|x| &x[..]).unwrap_or(&[])
+    }
+
+    /// The namespace of the module of the item named `name` in rust, within
+    /// the root namespaces.
+    pub fn module_namespace(&self, name: &str) -> &[String] {
+        self.module_namespaces
+            .get(name)
+            .map(|x| &x[..])
+            .unwrap_or(&[])
+    }
+
+    fn constant_namespace(&self, constant: &Constant) -> &[String] {
+        match constant.associated_to {
+            Some(ref path) => self.module_namespace(path.name()),
+            None => self.module_namespace(constant.path.name()),
         }
     }
 
+    /// The name that refers to the item at `path`, exported as `name`, in the
+    /// namespace `from`.
+    pub fn qualified_name(&self, path: &BindgenPath, name: &str, from: &[String]) -> String {
+        let namespace = self.module_namespace(path.name());
+        if from.starts_with(namespace) {
+            return name.to_owned();
+        }
+
+        // A name is looked up in the namespace it's used in first, and then in
+        // those around it, so qualifying it from the namespace both are in is
+        // only right if no namespace in between has one of the same name.
+        let common = from
+            .iter()
+            .zip(namespace)
+            .take_while(|&(x, y)| x == y)
+            .count();
+        let shadowed = (common + 1..from.len() + 1).any(|len| {
+            let mut shadow = from[..len].to_vec();
+            shadow.push(namespace[common].clone());
+            self.declared_namespaces.contains(&shadow)
+        });
+
+        if shadowed {
+            return self.absolute_name(namespace, name);
+        }
+        let mut segments = namespace[common..].to_vec();
+        segments.push(name.to_owned());
+        segments.join("::")
+    }
+
+    /// The fully qualified name of `name` in the module namespace `namespace`.
+    fn absolute_name(&self, namespace: &[String], name: &str) -> String {
+        let mut segments = vec![String::new()];
+        segments.extend(self.config.namespace.iter().cloned());
+        if let Some(ref namespaces) = self.config.namespaces {
+            segments.extend(namespaces.iter().cloned());
+        }
+        segments.extend(namespace.iter().cloned());
+        segments.push(name.to_owned());
+        segments.join("::")
+    }
+
+    /// The names the items are exported as, by their name in rust.
+    pub fn doc_links(&self) -> &HashMap<String, String> {
+        &self.doc_links
+    }
+
+    /// The rust source files the bindings were generated from.
+    pub fn source_files(&self) -> &[path::PathBuf] {
+        &self.source_files
+    }
+
+    /// The files that modules which weren't found could be in, so that
+    /// creating one of them changes the bindings.
+    pub fn missing_files(&self) -> &[path::PathBuf] {
+        &self.missing_files
+    }
+
     // FIXME(emilio): What to do when the configuration doesn't match?
     pub fn struct_is_transparent(&self, path: &BindgenPath) -> bool {
         let mut any = false;
@@ -57,6 +222,518 @@ impl Bindings {
         any
     }
 
+    pub fn mapped_type(&self, path: &BindgenPath) -> Option<MappedType> {
+        let mut mapped_type = None;
+        self.mapped_types
+            .for_items(path, |x| mapped_type = Some(x.clone()));
+        mapped_type
+    }
+
+    /// The includes needed by the mapped types that are used.
+    fn mapped_type_includes(&self) -> Vec<&str> {
+        let mut includes = Vec::new();
+        for item in &self.items {
+            if let ItemContainer::MappedType(ref x) = *item {
+                if let Some(ref include) = x.mapping.include {
+                    if !includes.contains(&include.as_str()) {
+                        includes.push(include.as_str());
+                    }
+                }
+            }
+        }
+        includes
+    }
+
+    /// The declaration of each item, keyed by the kind and name of the item,
+    /// for telling what changed between two sets of bindings.
+    pub fn declarations(&self) -> BTreeMap<String, String> {
+        let mut declarations = BTreeMap::new();
+
+        for constant in &self.constants {
+            declarations.insert(
+                format!("const {}", constant.export_name()),
+                self.declaration(|out| constant.write(&self.config, out, None)),
+            );
+        }
+
+        for item in &self.items {
+            let kind = match *item {
+                ItemContainer::Constant(..) | ItemContainer::Static(..) => unreachable!(),
+                ItemContainer::MappedType(ref x) if x.mapping.declaration.is_none() => continue,
+                ItemContainer::Enum(..) => "enum",
+                ItemContainer::Struct(..) => "struct",
+                ItemContainer::Union(..) => "union",
+                ItemContainer::OpaqueItem(..) => "opaque",
+                ItemContainer::Typedef(..) => "typedef",
+                ItemContainer::MappedType(..) => "mapped type",
+            };
+            let declaration = self.declaration(|out| match *item {
+                ItemContainer::Enum(ref x) => x.write(&self.config, out),
+                ItemContainer::Struct(ref x) => x.write(&self.config, out),
+                ItemContainer::Union(ref x) => x.write(&self.config, out),
+                ItemContainer::OpaqueItem(ref x) => x.write(&self.config, out),
+                ItemContainer::Typedef(ref x) => x.write(&self.config, out),
+                ItemContainer::MappedType(ref x) => x.write(&self.config, out),
+                ItemContainer::Constant(..) | ItemContainer::Static(..) => unreachable!(),
+            });
+            declarations.insert(
+                format!("{} {}", kind, item.deref().export_name()),
+                declaration,
+            );
+        }
+
+        for global in &self.globals {
+            declarations.insert(
+                format!("static {}", global.export_name()),
+                self.declaration(|out| global.write(&self.config, out)),
+            );
+        }
+
+        for function in &self.functions {
+            declarations.insert(
+                format!("fn {}", function.export_name),
+                self.declaration(|out| function.write(&self.config, out)),
+            );
+        }
+
+        declarations
+    }
+
+    /// Describes what became of the rust items named `name`: where they were
+    /// parsed and under which `#[cfg]`, which settings left them out or
+    /// renamed them, and which exported item pulled them in.
+    pub fn explain(&self, name: &str) -> String {
+        let mut lines = Vec::new();
+        let notes = self.explainer.notes(name);
+        let path = BindgenPath::new(name);
+        if let Some(origin) = self.explainer.origin(&path) {
+            lines.push(origin.to_owned());
+        } else if notes.is_empty() {
+            lines.push(
+                "No item with this name was parsed. It may be in a crate that isn't parsed, \
+                 see `[parse]`, or come from a macro that isn't expanded."
+                    .to_owned(),
+            );
+        }
+        for note in &notes {
+            lines.push(format!("Parsed {}.", note));
+        }
+
+        let export = &self.config.export;
+        let mut written = Vec::new();
+        for function in self.functions.iter().filter(|x| x.path == path) {
+            written.push(("fn", ItemType::Functions, function.export_name.as_str()));
+        }
+        for constant in self.constants.iter().filter(|x| x.path == path) {
+            written.push(("const", ItemType::Constants, constant.export_name()));
+        }
+        for global in self.globals.iter().filter(|x| x.path == path) {
+            written.push(("static", ItemType::Globals, global.export_name()));
+        }
+        for item in self.items.iter().filter(|x| *x.deref().path() == path) {
+            let (kind, item_type) = match *item {
+                ItemContainer::Constant(..) | ItemContainer::Static(..) => unreachable!(),
+                ItemContainer::Enum(..) => ("enum", ItemType::Enums),
+                ItemContainer::Struct(..) => ("struct", ItemType::Structs),
+                ItemContainer::Union(..) => ("union", ItemType::Unions),
+                ItemContainer::OpaqueItem(..) => ("opaque struct", ItemType::OpaqueItems),
+                ItemContainer::Typedef(..) => ("typedef", ItemType::Typedefs),
+                ItemContainer::MappedType(ref x) => {
+                    lines.push(format!(
+                        "Written as `{}`, from `[export.type_map]`.",
+                        x.mapping.name
+                    ));
+                    continue;
+                }
+            };
+            written.push((kind, item_type, item.deref().export_name()));
+        }
+
+        for &(kind, ref item_type, export_name) in &written {
+            let reasons = if export_name == name {
+                Vec::new()
+            } else {
+                export.rename_item_explained(Some(item_type), &mut name.to_owned())
+            };
+            let renamed = if reasons.is_empty() {
+                String::new()
+            } else {
+                format!(", {}", reasons.join(" and "))
+            };
+            lines.push(format!("Written as {} `{}`{}.", kind, export_name, renamed));
+        }
+        if let Some(chain) = self.explainer.chain(&path) {
+            lines.push(format!("Included through {}.", chain));
+        }
+
+        let parsed_types: Vec<_> = notes.iter().filter_map(|x| x.item_type()).collect();
+        let mangled_prefix = format!("{}_", name);
+        let instantiations: Vec<_> = self
+            .items
+            .iter()
+            .map(|x| x.deref())
+            .filter(|x| x.path().name().starts_with(&mangled_prefix))
+            .map(|x| format!("`{}`", x.export_name()))
+            .collect();
+        if written.is_empty() && !parsed_types.is_empty() {
+            if let Some(rule) = self.explainer.excluded_by(name) {
+                if rule.name() == Some(name) {
+                    lines.push("Not written, as it's in `[export] exclude`.".to_owned());
+                } else {
+                    lines.push(format!(
+                        "Not written, as it matches `{}` in `[export] exclude`.",
+                        rule
+                    ));
+                }
+            } else if let Some(item_type) = parsed_types
+                .iter()
+                .find(|x| !export.should_generate((*x).clone()))
+            {
+                lines.push(format!(
+                    "Not written, as `{}` isn't in `[export] item_types`.",
+                    explain::item_type_name(item_type)
+                ));
+            } else if !instantiations.is_empty() {
+                lines.push(format!(
+                    "Not written itself, as generic types are written once for each \
+                     instantiation in C: {}.",
+                    instantiations.join(", ")
+                ));
+            } else {
+                lines.push(
+                    "Not written, as no exported function, static or constant uses it, \
+                     and it isn't in `[export] include`."
+                        .to_owned(),
+                );
+            }
+        }
+
+        let mut explanation = format!("{}:\n", name);
+        for line in lines {
+            explanation.push_str("  ");
+            explanation.push_str(&line);
+            explanation.push('\n');
+        }
+        explanation
+    }
+
+    /// The graph of the items in the bindings and the items they refer to.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        let mut nodes = Vec::new();
+        // The `#[cfg]`s of each node, of which there are several when an item
+        // is defined differently under different `#[cfg]`s.
+        let mut cfgs: Vec<Vec<Option<Cfg>>> = Vec::new();
+        let mut type_ids = HashMap::new();
+
+        {
+            let mut add_node = |kind: &str,
+                                path: &BindgenPath,
+                                export_name: &str,
+                                cfg: Option<&Cfg>,
+                                opaque: bool| {
+                let id = format!("{} {}", kind, path);
+                let cfg = cfg.cloned();
+                if let Some(index) = nodes.iter().position(|x: &DependencyNode| x.id == id) {
+                    cfgs[index].push(cfg);
+                    return id;
+                }
+                nodes.push(DependencyNode {
+                    id: id.clone(),
+                    kind: kind.to_owned(),
+                    name: path.name().to_owned(),
+                    export_name: export_name.to_owned(),
+                    cfg: None,
+                    opaque,
+                    included: self.explainer.included_by(path.name()).is_some(),
+                });
+                cfgs.push(vec![cfg]);
+                id
+            };
+
+            for constant in &self.constants {
+                add_node(
+                    "const",
+                    &constant.path,
+                    constant.export_name(),
+                    constant.cfg(),
+                    false,
+                );
+            }
+            for item in &self.items {
+                let (kind, opaque) = match *item {
+                    ItemContainer::Constant(..) | ItemContainer::Static(..) => unreachable!(),
+                    ItemContainer::Enum(..) => ("enum", false),
+                    ItemContainer::Struct(..) => ("struct", false),
+                    ItemContainer::Union(..) => ("union", false),
+                    ItemContainer::OpaqueItem(..) => ("opaque", true),
+                    ItemContainer::Typedef(..) => ("typedef", false),
+                    ItemContainer::MappedType(..) => ("mapped type", false),
+                };
+                let item = item.deref();
+                let id = add_node(kind, item.path(), item.export_name(), item.cfg(), opaque);
+                type_ids.insert(item.path().clone(), id);
+            }
+            for global in &self.globals {
+                add_node(
+                    "static",
+                    &global.path,
+                    global.export_name(),
+                    global.cfg(),
+                    false,
+                );
+            }
+            for function in &self.functions {
+                add_node(
+                    "fn",
+                    &function.path,
+                    &function.export_name,
+                    function.cfg.as_ref(),
+                    false,
+                );
+            }
+        }
+
+        for (node, cfgs) in nodes.iter_mut().zip(cfgs) {
+            // An item is only conditional if each of its definitions is.
+            if cfgs.iter().all(|x| x.is_some()) {
+                let mut cfgs: Vec<_> = cfgs.into_iter().map(|x| x.unwrap()).collect();
+                let cfg = if cfgs.len() == 1 {
+                    cfgs.pop().unwrap()
+                } else {
+                    Cfg::Any(cfgs)
+                };
+                node.cfg = Some(cfg.to_string());
+            }
+        }
+
+        let node_ids: HashSet<_> = nodes.iter().map(|x| x.id.clone()).collect();
+        let mut edges = Vec::new();
+        let mut seen = HashSet::new();
+        for reference in &self.references {
+            let from = match reference.from {
+                Referrer::Function(ref path) => format!("fn {}", path),
+                Referrer::Static(ref path) => format!("static {}", path),
+                Referrer::Constant(ref path) => format!("const {}", path),
+                Referrer::Item(ref path) => match type_ids.get(path) {
+                    Some(id) => id.clone(),
+                    None => continue,
+                },
+                Referrer::Include => continue,
+            };
+            let to = match type_ids.get(&reference.to) {
+                Some(id) => id.clone(),
+                None => continue,
+            };
+            if node_ids.contains(&from) && seen.insert((from.clone(), to.clone())) {
+                edges.push(DependencyEdge { from, to });
+            }
+        }
+
+        DependencyGraph { nodes, edges }
+    }
+
+    /// A reference of the items in the bindings, with their declarations,
+    /// documentation, members, `#[cfg]`s and deprecation.
+    pub fn api_reference(&self) -> ApiReference {
+        let mut reference = ApiReference::new(&self.config.language, self.doc_links.clone());
+        // The declarations are written without their documentation and
+        // condition, which are part of the reference already.
+        let mut config = self.config.clone();
+        config.documentation = false;
+
+        let definition = |kind: &str,
+                          declaration: String,
+                          cfg: Option<&Cfg>,
+                          annotations: &AnnotationSet,
+                          documentation: &Documentation,
+                          members: Vec<ReferenceMember>| {
+            let condition = cfg
+                .and_then(|x| x.to_condition(&self.config))
+                .map(|x| self.declaration(|out| Some(x.clone()).write_before(&self.config, out)));
+            ReferenceDefinition {
+                kind: kind.to_owned(),
+                declaration: declaration.trim().to_owned(),
+                cfg: cfg.map(|x| x.to_string()),
+                condition: condition.map(|x| x.trim().to_owned()),
+                deprecated: annotations.deprecated.clone(),
+                documentation: documentation.doc_comment.clone(),
+                members,
+            }
+        };
+        let field = |&(ref name, ref ty, ref documentation): &(String, Type, Documentation)| {
+            ReferenceMember {
+                name: name.clone(),
+                value: Some(self.declaration(|out| cdecl::write_type(out, ty))),
+                documentation: documentation.doc_comment.clone(),
+            }
+        };
+
+        for constant in &self.constants {
+            let mut declared = constant.clone();
+            declared.cfg = None;
+            reference.add(
+                &self.constant_export_name(constant),
+                definition(
+                    "const",
+                    self.declaration(|out| declared.write(&config, out, None)),
+                    constant.cfg.as_ref(),
+                    &constant.annotations,
+                    &constant.documentation,
+                    vec![],
+                ),
+            );
+        }
+
+        for item in &self.items {
+            if item
+                .deref()
+                .annotations()
+                .bool("no-export")
+                .unwrap_or(false)
+            {
+                continue;
+            }
+
+            let (kind, declaration, documentation, members) = match *item {
+                ItemContainer::Constant(..) | ItemContainer::Static(..) => unreachable!(),
+                ItemContainer::MappedType(..) => continue,
+                ItemContainer::Enum(ref x) => {
+                    let mut declared = x.clone();
+                    declared.cfg = None;
+                    let variants = x
+                        .variants
+                        .iter()
+                        .map(|variant| ReferenceMember {
+                            name: variant.export_name.clone(),
+                            value: variant.discriminant.as_ref().map(|x| x.to_string()),
+                            documentation: variant.documentation.doc_comment.clone(),
+                        })
+                        .collect();
+                    (
+                        "enum",
+                        self.declaration(|out| declared.write(&config, out)),
+                        &x.documentation,
+                        variants,
+                    )
+                }
+                ItemContainer::Struct(ref x) => {
+                    let mut declared = x.clone();
+                    declared.cfg = None;
+                    (
+                        "struct",
+                        self.declaration(|out| declared.write(&config, out)),
+                        &x.documentation,
+                        x.fields.iter().map(&field).collect(),
+                    )
+                }
+                ItemContainer::Union(ref x) => {
+                    let mut declared = x.clone();
+                    declared.cfg = None;
+                    (
+                        "union",
+                        self.declaration(|out| declared.write(&config, out)),
+                        &x.documentation,
+                        x.fields.iter().map(&field).collect(),
+                    )
+                }
+                ItemContainer::OpaqueItem(ref x) => {
+                    let mut declared = x.clone();
+                    declared.cfg = None;
+                    (
+                        "opaque",
+                        self.declaration(|out| declared.write(&config, out)),
+                        &x.documentation,
+                        vec![],
+                    )
+                }
+                ItemContainer::Typedef(ref x) => {
+                    let mut declared = x.clone();
+                    declared.cfg = None;
+                    (
+                        "typedef",
+                        self.declaration(|out| declared.write(&config, out)),
+                        &x.documentation,
+                        vec![],
+                    )
+                }
+            };
+            let item = item.deref();
+            reference.add(
+                item.export_name(),
+                definition(
+                    kind,
+                    declaration,
+                    item.cfg(),
+                    item.annotations(),
+                    documentation,
+                    members,
+                ),
+            );
+        }
+
+        for global in &self.globals {
+            let mut declared = global.clone();
+            declared.cfg = None;
+            reference.add(
+                global.export_name(),
+                definition(
+                    "static",
+                    self.declaration(|out| declared.write(&config, out)),
+                    global.cfg.as_ref(),
+                    &global.annotations,
+                    &global.documentation,
+                    vec![],
+                ),
+            );
+        }
+
+        for function in &self.functions {
+            let mut declared = function.clone();
+            declared.cfg = None;
+            let parameters = function
+                .args
+                .iter()
+                .map(|&(ref name, ref ty)| ReferenceMember {
+                    name: name.clone(),
+                    value: Some(self.declaration(|out| cdecl::write_type(out, ty))),
+                    documentation: vec![],
+                })
+                .collect();
+            reference.add(
+                &function.export_name,
+                definition(
+                    "fn",
+                    self.declaration(|out| declared.write(&config, out)),
+                    function.cfg.as_ref(),
+                    &function.annotations,
+                    &function.documentation,
+                    parameters,
+                ),
+            );
+        }
+
+        reference
+    }
+
+    /// The name a constant is written as, which starts with the name of the
+    /// struct it's associated to, if any.
+    fn constant_export_name(&self, constant: &Constant) -> String {
+        match constant.associated_to {
+            Some(ref path) => {
+                let mut name = path.name().to_owned();
+                self.config.export.rename(&mut name);
+                format!("{}_{}", name, constant.export_name())
+            }
+            None => constant.export_name().to_owned(),
+        }
+    }
+
+    fn declaration<W: Fn(&mut SourceWriter<&mut Vec<u8>>)>(&self, write: W) -> String {
+        let mut declaration = Vec::new();
+        write(&mut SourceWriter::new(&mut declaration, self));
+        String::from_utf8(declaration).unwrap()
+    }
+
     pub fn write_to_file<P: AsRef<path::Path>>(&self, path: P) -> bool {
         // Don't compare files if we've never written this file before
         if !path.as_ref().is_file() {
@@ -150,6 +827,15 @@ impl Bindings {
             write!(out, "#include \"{}\"", include);
             out.new_line();
         }
+
+        for include in self.mapped_type_includes() {
+            if include.starts_with('<') || include.starts_with('"') {
+                write!(out, "#include {}", include);
+            } else {
+                write!(out, "#include \"{}\"", include);
+            }
+            out.new_line();
+        }
     }
 
     pub fn write<F: Write>(&self, file: F) {
@@ -158,6 +844,7 @@ impl Bindings {
         if !self.config.no_includes
             || !self.config.includes.is_empty()
             || !self.config.sys_includes.is_empty()
+            || !self.mapped_type_includes().is_empty()
         {
             self.write_headers(&mut out);
         }
@@ -168,9 +855,10 @@ impl Bindings {
 
         for constant in &self.constants {
             if constant.ty.is_primitive_or_ptr_primitive() {
-                out.new_line_if_not_start();
-                constant.write(&self.config, &mut out, None);
-                out.new_line();
+                self.enter_module_namespace(&mut out, self.constant_namespace(constant));
+                write_with_text(&mut out, &constant.annotations, |out| {
+                    constant.write(&self.config, out, None)
+                });
             }
         }
 
@@ -184,52 +872,107 @@ impl Bindings {
                 continue;
             }
 
-            out.new_line_if_not_start();
             match *item {
+                ItemContainer::Struct(ref x) if x.is_forward_declared => {
+                    self.enter_module_namespace(&mut out, self.module_namespace(x.path.name()));
+                    out.new_line_if_not_start();
+                    x.write_forward_declaration(&self.config, &mut out);
+                    out.new_line();
+                }
+                ItemContainer::Union(ref x) if x.is_forward_declared => {
+                    self.enter_module_namespace(&mut out, self.module_namespace(x.path.name()));
+                    out.new_line_if_not_start();
+                    x.write_forward_declaration(&self.config, &mut out);
+                    out.new_line();
+                }
+                _ => {}
+            }
+        }
+
+        for item in &self.items {
+            if item
+                .deref()
+                .annotations()
+                .bool("no-export")
+                .unwrap_or(false)
+            {
+                continue;
+            }
+
+            if let ItemContainer::MappedType(ref x) = *item {
+                if x.mapping.declaration.is_none() {
+                    continue;
+                }
+            }
+
+            self.enter_module_namespace(&mut out, self.module_namespace(item.deref().name()));
+            write_with_text(&mut out, item.deref().annotations(), |out| match *item {
                 ItemContainer::Constant(..) => unreachable!(),
                 ItemContainer::Static(..) => unreachable!(),
-                ItemContainer::Enum(ref x) => x.write(&self.config, &mut out),
-                ItemContainer::Struct(ref x) => x.write(&self.config, &mut out),
-                ItemContainer::Union(ref x) => x.write(&self.config, &mut out),
-                ItemContainer::OpaqueItem(ref x) => x.write(&self.config, &mut out),
-                ItemContainer::Typedef(ref x) => x.write(&self.config, &mut out),
-            }
-            out.new_line();
+                ItemContainer::Enum(ref x) => x.write(&self.config, out),
+                ItemContainer::Struct(ref x) => x.write(&self.config, out),
+                ItemContainer::Union(ref x) => x.write(&self.config, out),
+                ItemContainer::OpaqueItem(ref x) => x.write(&self.config, out),
+                ItemContainer::Typedef(ref x) => x.write(&self.config, out),
+                ItemContainer::MappedType(ref x) => x.write(&self.config, out),
+            });
         }
 
         for constant in &self.constants {
             if !constant.ty.is_primitive_or_ptr_primitive() {
-                out.new_line_if_not_start();
-                constant.write(&self.config, &mut out, None);
-                out.new_line();
+                self.enter_module_namespace(&mut out, self.constant_namespace(constant));
+                write_with_text(&mut out, &constant.annotations, |out| {
+                    constant.write(&self.config, out, None)
+                });
             }
         }
 
-        if !self.functions.is_empty() || !self.globals.is_empty() {
-            if self.config.language == Language::Cxx {
-                out.new_line_if_not_start();
-                out.write("extern \"C\" {");
-                out.new_line();
+        if self.config.module_namespaces.functions {
+            // Each namespace gets an `extern "C"` block of its own, in the
+            // order the namespaces first come up in.
+            let mut namespaces: Vec<&[String]> = Vec::new();
+            let names = self
+                .globals
+                .iter()
+                .map(|x| x.path.name())
+                .chain(self.functions.iter().map(|x| x.path.name()));
+            for name in names {
+                let namespace = self.module_namespace(name);
+                if !namespaces.contains(&namespace) {
+                    namespaces.push(namespace);
+                }
             }
-
-            for global in &self.globals {
-                out.new_line_if_not_start();
-                global.write(&self.config, &mut out);
-                out.new_line();
+            for namespace in namespaces {
+                self.enter_module_namespace(&mut out, namespace);
+                self.write_extern_block(&mut out, |name| self.module_namespace(name) == namespace);
             }
+        } else {
+            self.enter_module_namespace(&mut out, &[]);
+            self.write_extern_block(&mut out, |_| true);
+        }
 
-            for function in &self.functions {
-                out.new_line_if_not_start();
-                function.write(&self.config, &mut out);
-                out.new_line();
+        // Member functions are defined once the functions they call are
+        // declared.
+        for item in &self.items {
+            let item = item.deref();
+            if item.annotations().bool("no-export").unwrap_or(false) {
+                continue;
             }
+            for method in self.methods(item.path()) {
+                let function_namespace = if self.config.module_namespaces.functions {
+                    self.module_namespace(method.function.path.name())
+                } else {
+                    &[]
+                };
+                let call = self.absolute_name(function_namespace, method.function.path.name());
 
-            if self.config.language == Language::Cxx {
+                self.enter_module_namespace(&mut out, self.module_namespace(item.name()));
                 out.new_line_if_not_start();
-                out.write("} // extern \"C\"");
+                method.write_definition(&self.config, &mut out, item.export_name(), &call);
                 out.new_line();
             }
         }
+        self.enter_module_namespace(&mut out, &[]);
 
         if self.config.language == Language::Cxx {
             self.close_namespaces(&mut out);
@@ -251,6 +994,81 @@ impl Bindings {
         }
     }
 
+    /// Writes the globals and functions named `filter` accepts, in an
+    /// `extern "C"` block for C++.
+    fn write_extern_block<F: Write, P: Fn(&str) -> bool>(
+        &self,
+        out: &mut SourceWriter<F>,
+        filter: P,
+    ) {
+        let globals: Vec<&Static> = self
+            .globals
+            .iter()
+            .filter(|x| filter(x.path.name()))
+            .collect();
+        let functions: Vec<&Function> = self
+            .functions
+            .iter()
+            .filter(|x| filter(x.path.name()))
+            .collect();
+        if globals.is_empty() && functions.is_empty() {
+            return;
+        }
+
+        if self.config.language == Language::Cxx {
+            out.new_line_if_not_start();
+            out.write("extern \"C\" {");
+            out.new_line();
+        }
+
+        for global in globals {
+            write_with_text(out, &global.annotations, |out| {
+                global.write(&self.config, out)
+            });
+        }
+
+        for function in functions {
+            write_with_text(out, &function.annotations, |out| {
+                function.write(&self.config, out)
+            });
+        }
+
+        if self.config.language == Language::Cxx {
+            out.new_line_if_not_start();
+            out.write("} // extern \"C\"");
+            out.new_line();
+        }
+    }
+
+    /// Closes and opens namespaces of modules to go from the one `out` is in
+    /// to `namespace`.
+    fn enter_module_namespace<F: Write>(&self, out: &mut SourceWriter<F>, namespace: &[String]) {
+        let current = out.namespace().to_vec();
+        let common = current
+            .iter()
+            .zip(namespace)
+            .take_while(|&(x, y)| x == y)
+            .count();
+
+        for name in current[common..].iter().rev() {
+            out.new_line_if_not_start();
+            write!(out, "}} // namespace {}", name);
+        }
+        if common < current.len() {
+            out.new_line();
+        }
+
+        if common < namespace.len() {
+            out.new_line_if_not_start();
+            for name in &namespace[common..] {
+                write!(out, "namespace {} {{", name);
+                out.new_line();
+            }
+        }
+
+        out.set_namespace(namespace.to_vec());
+    }
+
     pub(crate) fn open_namespaces<F: Write>(&self, out: &mut SourceWriter<F>) {
         let mut wrote_namespace: bool = false;
         if let Some(ref namespace) = self.config.namespace {
@@ -292,3 +1110,136 @@ impl Bindings {
         }
     }
 }
+
+/// Writes an item on lines of its own, with the raw text added before and
+/// after it by item visitors.
+fn write_with_text<F: Write, W: FnOnce(&mut SourceWriter<F>)>(
+    out: &mut SourceWriter<F>,
+    annotations: &AnnotationSet,
+    write_item: W,
+) {
+    out.new_line_if_not_start();
+    for text in &annotations.text_before {
+        write!(out, "{}", text);
+        out.new_line();
+    }
+    write_item(out);
+    for text in &annotations.text_after {
+        out.new_line();
+        write!(out, "{}", text);
+    }
+    out.new_line();
+}
+
+#[test]
+fn explain() {
+    use bindgen::Builder;
+
+    let bindings = Builder::new()
+        .with_language(Language::C)
+        .with_source_str(
+            "demo",
+            "ffi",
+            r#"
+            #[repr(C)]
+            pub struct Used { x: i32 }
+            #[repr(C)]
+            pub struct Unused { x: i32 }
+            #[repr(C)]
+            pub struct Generic<T> { x: T }
+            pub struct NotRepr { x: i32 }
+
+            #[no_mangle]
+            pub extern "C" fn root(a: Used, b: *const [u8], c: Generic<u16>, d: *const NotRepr) {}
+            "#,
+        )
+        .generate()
+        .unwrap();
+
+    assert_eq!(
+        bindings.explain("Used"),
+        "Used:\n  \
+         Parsed struct in crate `demo`, mod `ffi`: taken.\n  \
+         Written as struct `Used`.\n  \
+         Included through `Used` <- fn `root`.\n"
+    );
+    assert!(bindings
+        .explain("Unused")
+        .contains("Not written, as no exported function, static or constant uses it"));
+    assert!(bindings
+        .explain("NotRepr")
+        .contains("Written as opaque struct `NotRepr`."));
+    assert!(bindings
+        .explain("Missing")
+        .contains("No item with this name was parsed."));
+
+    // Items that are synthesized rather than parsed.
+    let slice = bindings.explain("SliceU8");
+    assert!(slice.contains("Generated for const pointers to slices, see `[fat_pointer]`."));
+    assert!(!slice.contains("No item with this name was parsed."));
+    assert!(bindings
+        .explain("Generic_u16")
+        .contains("Generated as an instantiation of the generic `Generic`"));
+    assert!(bindings.explain("Generic").contains(
+        "Not written itself, as generic types are written once for each instantiation in C: \
+         `Generic_u16`."
+    ));
+}
+
+#[test]
+fn explain_renames() {
+    use bindgen::config::{RenamePattern, RenameRule};
+    use bindgen::visitor::VisitedItem;
+    use bindgen::Builder;
+
+    let mut config = Config {
+        language: Language::C,
+        ..Config::default()
+    };
+    config.export.prefix = Some("P_".to_owned());
+    config.export.rename_rules.functions = Some(RenameRule::CamelCase);
+    let mut pattern = RenamePattern::new("^(.*)(Raw|_raw)$", "$1").unwrap();
+    pattern.item_types = vec![ItemType::Functions];
+    config.export.rename_patterns.push(pattern);
+
+    let bindings = Builder::new()
+        .with_config(config)
+        .with_source_str(
+            "demo",
+            "ffi",
+            r#"
+            #[repr(C)]
+            pub struct Point { x: i32 }
+            #[repr(C)]
+            pub struct PointRaw { x: i32 }
+
+            #[no_mangle]
+            pub extern "C" fn make_point_raw(a: Point, b: PointRaw) {}
+            #[no_mangle]
+            pub extern "C" fn get_point() {}
+            "#,
+        )
+        .with_item_visitor(|item: &mut VisitedItem| {
+            if item.name() == "Point" {
+                item.rename("Pt");
+            }
+        })
+        .generate()
+        .unwrap();
+
+    assert!(bindings.explain("Point").contains(
+        "Written as struct `P_Pt`, renamed by an item visitor and prefixed by `[export] prefix`."
+    ));
+    // The pattern only renames functions.
+    assert!(bindings
+        .explain("PointRaw")
+        .contains("Written as struct `P_PointRaw`, prefixed by `[export] prefix`."));
+    assert!(bindings.explain("make_point_raw").contains(
+        "Written as fn `make_point`, renamed by the pattern `^(.*)(Raw|_raw)$` of \
+         `[[export.rename_patterns]]`."
+    ));
+    assert!(bindings.explain("get_point").contains(
+        "Written as fn `getPoint`, renamed by `functions = \"CamelCase\"` in \
+         `[export.rename_rules]`."
+    ));
+}
@@ -4,22 +4,28 @@
 
 use std::path;
 
+use proc_macro2::TokenStream;
+use syn;
+
 use bindgen::bindings::Bindings;
+use bindgen::cache::ParseCache;
 use bindgen::cargo::Cargo;
-use bindgen::config::{Braces, Config, Language};
+use bindgen::config::{Braces, Config, Language, OutputConfig};
 use bindgen::error::Error;
 use bindgen::library::Library;
-use bindgen::parser::{self, Parse};
+use bindgen::parser::{self, Parse, Source};
+use bindgen::visitor::{ItemVisitor, ItemVisitors};
 
 /// A builder for generating a bindings header.
 #[derive(Debug, Clone)]
 pub struct Builder {
     config: Config,
-    srcs: Vec<path::PathBuf>,
+    srcs: Vec<Source>,
     lib: Option<(path::PathBuf, Option<String>)>,
     lib_cargo: Option<Cargo>,
     std_types: bool,
     lockfile: Option<path::PathBuf>,
+    visitors: ItemVisitors,
 }
 
 impl Builder {
@@ -31,6 +37,7 @@ impl Builder {
             lib_cargo: None,
             std_types: true,
             lockfile: None,
+            visitors: ItemVisitors::default(),
         }
     }
 
@@ -206,6 +213,31 @@ impl Builder {
         self
     }
 
+    #[allow(unused)]
+    pub fn with_parse_expand_toolchain<S: AsRef<str>>(mut self, toolchain: S) -> Builder {
+        self.config.parse.expand.toolchain = Some(String::from(toolchain.as_ref()));
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_parse_expand_target<S: AsRef<str>>(mut self, target: S) -> Builder {
+        self.config.parse.expand.target = Some(String::from(target.as_ref()));
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_parse_expand_profile<S: AsRef<str>>(mut self, profile: S) -> Builder {
+        self.config.parse.expand.profile = Some(String::from(profile.as_ref()));
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_parse_expand_rustflags<S: AsRef<str>>(mut self, rustflags: &[S]) -> Builder {
+        self.config.parse.expand.rustflags =
+            rustflags.iter().map(|x| String::from(x.as_ref())).collect();
+        self
+    }
+
     #[allow(unused)]
     pub fn with_documentation(mut self, documentation: bool) -> Builder {
         self.config.documentation = documentation;
@@ -244,10 +276,50 @@ impl Builder {
 
     #[allow(unused)]
     pub fn with_src<P: AsRef<path::Path>>(mut self, src: P) -> Builder {
-        self.srcs.push(src.as_ref().to_owned());
+        self.srcs.push(Source::File(src.as_ref().to_owned()));
+        self
+    }
+
+    /// Adds rust source that's only in memory, like generated code, whose
+    /// items are in the module `mod_path` of the crate `crate_name`. The
+    /// module path is like `ffi::generated`, or empty for the crate's root.
+    /// Its `mod`s must have a body.
+    #[allow(unused)]
+    pub fn with_source_str<S: AsRef<str>>(
+        mut self,
+        crate_name: &str,
+        mod_path: &str,
+        source: S,
+    ) -> Builder {
+        self.srcs.push(Source::Memory {
+            crate_name: crate_name.to_owned(),
+            mod_path: mod_path
+                .split("::")
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_owned())
+                .collect(),
+            source: source.as_ref().to_owned(),
+        });
         self
     }
 
+    /// Adds a parsed file, like `with_source_str`.
+    #[allow(unused)]
+    pub fn with_syn_file(self, crate_name: &str, mod_path: &str, file: &syn::File) -> Builder {
+        self.with_source_str(crate_name, mod_path, quote!(#file).to_string())
+    }
+
+    /// Adds the tokens of the items of a file, like `with_source_str`.
+    #[allow(unused)]
+    pub fn with_token_stream(
+        self,
+        crate_name: &str,
+        mod_path: &str,
+        tokens: TokenStream,
+    ) -> Builder {
+        self.with_source_str(crate_name, mod_path, tokens.to_string())
+    }
+
     #[allow(unused)]
     pub fn with_crate<P: AsRef<path::Path>>(mut self, lib_dir: P) -> Builder {
         debug_assert!(self.lib.is_none());
@@ -287,7 +359,59 @@ impl Builder {
         self
     }
 
-    pub fn generate(self) -> Result<Bindings, Error> {
+    #[allow(unused)]
+    pub fn with_parse_cache(mut self, enabled: bool) -> Builder {
+        self.config.parse.cache.enabled = enabled;
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_parse_cache_dir<P: AsRef<path::Path>>(mut self, dir: P) -> Builder {
+        self.config.parse.cache.dir = Some(dir.as_ref().to_string_lossy().into_owned());
+        self
+    }
+
+    #[allow(unused)]
+    pub fn with_parse_jobs(mut self, jobs: usize) -> Builder {
+        self.config.parse.jobs = Some(jobs);
+        self
+    }
+
+    /// Adds a hook to inspect and modify each item before it's renamed by the
+    /// config, for each set of bindings. Visitors run in the order they were
+    /// added.
+    #[allow(unused)]
+    pub fn with_item_visitor<V: ItemVisitor + Send + 'static>(mut self, visitor: V) -> Builder {
+        self.visitors.push(visitor);
+        self
+    }
+
+    /// Adds a set of bindings to generate with `generate_outputs`, from the
+    /// same parse as the others.
+    #[allow(unused)]
+    pub fn with_output(mut self, output: OutputConfig) -> Builder {
+        self.config.outputs.push(output);
+        self
+    }
+
+    #[allow(unused)]
+    pub(crate) fn has_outputs(&self) -> bool {
+        !self.config.outputs.is_empty()
+    }
+
+    fn parse_cache(&self, cargo: &Cargo) -> Option<ParseCache> {
+        if !self.config.parse.cache.enabled {
+            return None;
+        }
+
+        let dir = match self.config.parse.cache.dir {
+            Some(ref dir) => path::PathBuf::from(dir),
+            None => cargo.target_dir()?.join("cbindgen"),
+        };
+        Some(ParseCache::new(dir))
+    }
+
+    fn parse(&self) -> Result<Parse, Error> {
         let mut result = Parse::new();
 
         if self.std_types {
@@ -295,7 +419,12 @@ impl Builder {
         }
 
         for x in &self.srcs {
-            result.extend_with(&parser::parse_src(x, &self.config.macro_expansion)?);
+            result.extend_with(&parser::parse_src(
+                x,
+                &self.config.macro_expansion,
+                &self.config.parse.exclude,
+                self.config.parse.num_jobs(),
+            )?);
         }
 
         if let Some((lib_dir, binding_lib_name)) = self.lib.clone() {
@@ -319,42 +448,261 @@ impl Builder {
                 )?
             };
 
+            let cache = self.parse_cache(&cargo);
             result.extend_with(&parser::parse_lib(
                 cargo,
                 &self.config.macro_expansion,
                 self.config.parse.parse_deps,
                 &self.config.parse.include,
                 &self.config.parse.exclude,
-                &self.config.parse.expand.crates,
-                self.config.parse.expand.all_features,
-                self.config.parse.expand.default_features,
-                &self.config.parse.expand.features,
+                &self.config.parse.expand,
+                cache,
+                self.config.parse.num_jobs(),
             )?);
         } else if let Some(cargo) = self.lib_cargo.clone() {
+            let cache = self.parse_cache(&cargo);
             result.extend_with(&parser::parse_lib(
                 cargo,
                 &self.config.macro_expansion,
                 self.config.parse.parse_deps,
                 &self.config.parse.include,
                 &self.config.parse.exclude,
-                &self.config.parse.expand.crates,
-                self.config.parse.expand.all_features,
-                self.config.parse.expand.default_features,
-                &self.config.parse.expand.features,
+                &self.config.parse.expand,
+                cache,
+                self.config.parse.num_jobs(),
             )?);
         }
 
+        Ok(result)
+    }
+
+    fn library(&self, config: Config, parse: Parse) -> Library {
         Library::new(
-            self.config,
-            result.constants,
-            result.globals,
-            result.enums,
-            result.structs,
-            result.unions,
-            result.opaque_items,
-            result.typedefs,
-            result.functions,
+            config,
+            parse.constants,
+            parse.globals,
+            parse.enums,
+            parse.structs,
+            parse.unions,
+            parse.opaque_items,
+            parse.typedefs,
+            parse.functions,
+            parse.source_files,
+            parse.missing_files,
+            parse.notes,
         )
+        .with_item_visitors(self.visitors.clone())
+    }
+
+    pub fn generate(self) -> Result<Bindings, Error> {
+        let parse = self.parse()?;
+        self.library(self.config.clone(), parse).generate()
+    }
+
+    /// Generates the bindings of each of the `[[output]]`s of the config, with
+    /// the path to write them to, parsing the sources only once.
+    pub fn generate_outputs(self) -> Result<Vec<(path::PathBuf, Bindings)>, Error> {
+        let parse = self.parse()?;
+        self.config
+            .outputs
+            .iter()
+            .map(|output| {
+                let config = self.config.for_output(output);
+                let bindings = self.library(config, parse.clone()).generate()?;
+                Ok((path::PathBuf::from(&output.path), bindings))
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn outputs() {
+    let source = r#"
+        #[repr(C)]
+        pub struct Public { x: i32 }
+        #[repr(C)]
+        pub struct Internal { x: i32 }
+        #[no_mangle]
+        pub extern "C" fn public(p: Public) {}
+        #[no_mangle]
+        pub extern "C" fn internal(i: Internal) {}
+    "#;
+    let outputs = Builder::new()
+        .with_language(Language::C)
+        .with_source_str("demo", "ffi", source)
+        .with_output(OutputConfig {
+            path: "public.h".to_owned(),
+            exclude: Some(vec!["internal".to_owned()]),
+            ..Default::default()
+        })
+        .with_output(OutputConfig {
+            path: "internal.hpp".to_owned(),
+            language: Some(Language::Cxx),
+            include: Some(vec!["Internal".to_owned()]),
+            exclude: Some(vec!["public".to_owned()]),
+            ..Default::default()
+        })
+        .generate_outputs()
+        .unwrap();
+
+    let written: Vec<_> = outputs
+        .iter()
+        .map(|&(ref path, ref bindings)| (path.to_str().unwrap(), write(bindings)))
+        .collect();
+    assert_eq!(written.len(), 2);
+
+    let (path, ref c) = written[0];
+    assert_eq!(path, "public.h");
+    assert!(c.contains("#include <stdint.h>"));
+    assert!(c.contains("void public(Public p);"));
+    assert!(!c.contains("internal"));
+    assert!(!c.contains("Internal"));
+
+    let (path, ref cxx) = written[1];
+    assert_eq!(path, "internal.hpp");
+    assert!(cxx.contains("#include <cstdint>"));
+    assert!(cxx.contains("void internal(Internal i);"));
+    assert!(cxx.contains("struct Internal {"));
+    assert!(!cxx.contains("public"));
+}
+
+#[cfg(test)]
+fn write(bindings: &Bindings) -> String {
+    let mut out = Vec::new();
+    bindings.write(&mut out);
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn source_str() {
+    let source = r#"
+        #[repr(C)]
+        pub struct Point { x: i32 }
+        pub mod inner {
+            #[repr(C)]
+            pub struct Inner { x: i32 }
+        }
+        #[no_mangle]
+        pub extern "C" fn root(p: Point, i: inner::Inner) {}
+    "#;
+    let bindings = Builder::new()
+        .with_language(Language::C)
+        .with_source_str("my-demo", "ffi::generated", source)
+        .generate()
+        .unwrap();
+    assert!(bindings
+        .explain("Point")
+        .contains("Parsed struct in crate `my-demo`, mod `ffi::generated`: taken."));
+    assert!(bindings
+        .explain("Inner")
+        .contains("Parsed struct in crate `my-demo`, mod `ffi::generated::inner`: taken."));
+    assert!(write(&bindings).contains("void root(Point p, Inner i);"));
+
+    // An empty module path is the crate's root.
+    let bindings = Builder::new()
+        .with_language(Language::C)
+        .with_source_str("demo", "", source)
+        .generate()
+        .unwrap();
+    assert!(bindings
+        .explain("Point")
+        .contains("Parsed struct in crate `demo`: taken."));
+}
+
+#[test]
+fn syn_file_and_token_stream() {
+    let tokens = quote! {
+        #[repr(C)]
+        pub struct Point { x: i32 }
+        #[no_mangle]
+        pub extern "C" fn root(p: Point) {}
+    };
+    let expected = Builder::new()
+        .with_language(Language::C)
+        .with_source_str("demo", "ffi", tokens.to_string())
         .generate()
+        .unwrap();
+    assert!(write(&expected).contains("void root(Point p);"));
+
+    let file: syn::File = syn::parse2(tokens.clone()).unwrap();
+    let bindings = Builder::new()
+        .with_language(Language::C)
+        .with_syn_file("demo", "ffi", &file)
+        .generate()
+        .unwrap();
+    assert_eq!(write(&bindings), write(&expected));
+    assert_eq!(bindings.explain("Point"), expected.explain("Point"));
+
+    let bindings = Builder::new()
+        .with_language(Language::C)
+        .with_token_stream("demo", "ffi", tokens)
+        .generate()
+        .unwrap();
+    assert_eq!(write(&bindings), write(&expected));
+    assert_eq!(bindings.explain("Point"), expected.explain("Point"));
+}
+
+#[test]
+fn source_str_syntax_error() {
+    let result = Builder::new()
+        .with_language(Language::C)
+        .with_source_str("demo", "ffi::generated", "pub struct {")
+        .generate();
+    match result {
+        Err(Error::ParseSyntaxError {
+            ref crate_name,
+            ref src_path,
+            ..
+        }) => {
+            assert_eq!(crate_name, "demo");
+            assert_eq!(src_path, "<demo::ffi::generated>");
+        }
+        _ => panic!("expected a syntax error"),
     }
 }
+
+#[test]
+fn same_name_in_module_namespaces() {
+    let source = r#"
+        pub mod audio {
+            pub mod codec {
+                #[repr(C)]
+                pub struct Config { rate: u32 }
+            }
+        }
+        pub mod video {
+            pub mod codec {
+                #[repr(C)]
+                pub struct Config { fps: u32 }
+            }
+        }
+        #[no_mangle]
+        pub extern "C" fn audio_cfg(c: *const audio::codec::Config) {}
+        #[no_mangle]
+        pub extern "C" fn video_cfg(c: *const video::codec::Config) {}
+    "#;
+    let mut config = Config::default();
+    config.module_namespaces.mirror = true;
+    let result = Builder::new()
+        .with_config(config)
+        .with_language(Language::Cxx)
+        .with_source_str("demo", "ffi", source)
+        .generate();
+    let error = match result {
+        Ok(_) => panic!("the items named `Config` should conflict"),
+        Err(error) => error,
+    };
+    assert_eq!(
+        error.to_string(),
+        "Items named `Config` are in different module namespaces: `ffi::audio::codec`, \
+         `ffi::video::codec`. Rename or exclude all but one of them."
+    );
+
+    // Without namespaces, the items are found by name alone, as always.
+    let bindings = Builder::new()
+        .with_language(Language::Cxx)
+        .with_source_str("demo", "ffi", source)
+        .generate()
+        .unwrap();
+    assert!(write(&bindings).contains("void video_cfg(const Config *c);"));
+}
@@ -0,0 +1,197 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::UNIX_EPOCH;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json;
+
+use bindgen::parser::ParsedCrate;
+
+/// The size and modification time of a source file, used to tell whether it
+/// changed since a crate was cached.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FileStamp {
+    len: u64,
+    modified: Option<(u64, u32)>,
+}
+
+impl FileStamp {
+    fn load(path: &Path) -> Option<FileStamp> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|x| x.duration_since(UNIX_EPOCH).ok())
+            .map(|x| (x.as_secs(), x.subsec_nanos()));
+        Some(FileStamp {
+            len: metadata.len(),
+            modified,
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry<T> {
+    key: String,
+    files: Vec<(PathBuf, FileStamp)>,
+    /// Files that didn't exist, but would have been read if they had.
+    missing: Vec<PathBuf>,
+    value: T,
+}
+
+/// An on-disk cache of parsed crates, and of the source of expanded crates.
+///
+/// Each crate is stored in its own file, along with the stamps of the source
+/// files it was created from. An entry is only used if it was created with the
+/// same key, which covers the cbindgen version and any settings that affect
+/// parsing, none of its source files changed since, and none of the files of
+/// the `mod`s that weren't found were created since.
+#[derive(Debug, Clone)]
+pub struct ParseCache {
+    dir: PathBuf,
+}
+
+impl ParseCache {
+    pub fn new(dir: PathBuf) -> ParseCache {
+        ParseCache { dir }
+    }
+
+    fn entry_path(&self, prefix: &str, crate_name: &str, key: &str) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.dir.join(format!(
+            "{}{}-{:016x}.json",
+            prefix,
+            crate_name,
+            hasher.finish()
+        ))
+    }
+
+    /// Returns the cached crate for `key`, if it's still up to date.
+    pub fn load(&self, crate_name: &str, key: &str) -> Option<ParsedCrate> {
+        self.load_entry(&self.entry_path("", crate_name, key), key)
+    }
+
+    pub fn store(&self, crate_name: &str, key: &str, parsed: &ParsedCrate) {
+        self.store_entry(
+            &self.entry_path("", crate_name, key),
+            key,
+            &parsed.parse.source_files,
+            &parsed.parse.missing_files,
+            parsed,
+        );
+    }
+
+    /// Returns the cached expansion of a crate for `key`, if it's still up to
+    /// date.
+    pub fn load_expanded(&self, crate_name: &str, key: &str) -> Option<String> {
+        self.load_entry(&self.entry_path("expanded-", crate_name, key), key)
+    }
+
+    pub fn store_expanded(&self, crate_name: &str, key: &str, files: &[PathBuf], src: &str) {
+        self.store_entry(
+            &self.entry_path("expanded-", crate_name, key),
+            key,
+            files,
+            &[],
+            &src,
+        );
+    }
+
+    fn load_entry<T: DeserializeOwned>(&self, path: &Path, key: &str) -> Option<T> {
+        let file = File::open(path).ok()?;
+        let entry: CacheEntry<T> = match serde_json::from_reader(BufReader::new(file)) {
+            Ok(entry) => entry,
+            Err(err) => {
+                warn!("Ignoring invalid parse cache entry {:?}: {}", path, err);
+                return None;
+            }
+        };
+
+        if entry.key != key {
+            return None;
+        }
+        for &(ref src, ref stamp) in &entry.files {
+            if FileStamp::load(src).as_ref() != Some(stamp) {
+                return None;
+            }
+        }
+        if entry.missing.iter().any(|x| x.exists()) {
+            return None;
+        }
+
+        Some(entry.value)
+    }
+
+    fn store_entry<T: Serialize>(
+        &self,
+        path: &Path,
+        key: &str,
+        src_files: &[PathBuf],
+        missing: &[PathBuf],
+        value: &T,
+    ) {
+        let mut files = Vec::new();
+        for src in src_files {
+            match FileStamp::load(src) {
+                Some(stamp) => files.push((src.clone(), stamp)),
+                // Don't cache anything we can't tell is up to date.
+                None => return,
+            }
+        }
+
+        let entry = CacheEntry {
+            key: key.to_owned(),
+            files,
+            missing: missing.to_vec(),
+            value,
+        };
+
+        // Write to a temporary file first, so that a concurrent run never
+        // sees a partially written entry.
+        let tmp_path = path.with_extension(format!("{}.tmp", process::id()));
+        let result = fs::create_dir_all(&self.dir)
+            .and_then(|_| File::create(&tmp_path))
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                serde_json::to_writer(&mut writer, &entry)?;
+                writer.flush()
+            })
+            .and_then(|_| fs::rename(&tmp_path, path));
+        if let Err(err) = result {
+            let _ = fs::remove_file(&tmp_path);
+            warn!("Couldn't write parse cache entry {:?}: {}", path, err);
+        }
+    }
+}
+
+#[test]
+fn missing_mod_file() {
+    extern crate tempfile;
+
+    let dir = tempfile::Builder::new().tempdir().unwrap();
+    let lib = dir.path().join("lib.rs");
+    let foo = dir.path().join("foo.rs");
+    fs::write(&lib, "mod foo;").unwrap();
+
+    let cache = ParseCache::new(dir.path().join("cache"));
+    let mut parsed = ParsedCrate::new();
+    parsed.parse.source_files.push(lib.clone());
+    parsed.parse.missing_files.push(foo.clone());
+    cache.store("demo", "key", &parsed);
+    assert!(cache.load("demo", "key").is_some());
+    assert!(cache.load("demo", "other key").is_none());
+
+    // Creating the file of the mod makes the entry stale.
+    fs::write(&foo, "pub fn from_foo() {}").unwrap();
+    assert!(cache.load("demo", "key").is_none());
+}
@@ -8,6 +8,7 @@ use bindgen::cargo::cargo_expand;
 use bindgen::cargo::cargo_lock::{self, Lock};
 use bindgen::cargo::cargo_metadata::{self, Metadata};
 use bindgen::cargo::cargo_toml;
+use bindgen::config::ParseExpandConfig;
 use bindgen::error::Error;
 
 /// Parse a dependency string used in Cargo.lock
@@ -29,6 +30,7 @@ pub(crate) struct Cargo {
     manifest_path: PathBuf,
     binding_crate_name: String,
 
+    lock_path: PathBuf,
     lock: Option<Lock>,
     metadata: Metadata,
     clean: bool,
@@ -74,6 +76,7 @@ impl Cargo {
         Ok(Cargo {
             manifest_path: toml_path,
             binding_crate_name: binding_crate_name,
+            lock_path: lock_path,
             lock: lock,
             metadata: metadata,
             clean: clean,
@@ -84,6 +87,14 @@ impl Cargo {
         &self.binding_crate_name
     }
 
+    pub(crate) fn lock_path(&self) -> &Path {
+        &self.lock_path
+    }
+
+    pub(crate) fn target_dir(&self) -> Option<&Path> {
+        self.metadata.target_directory.as_ref().map(Path::new)
+    }
+
     pub(crate) fn binding_crate_ref(&self) -> PackageRef {
         self.find_pkg_ref(&self.binding_crate_name).unwrap()
     }
@@ -159,7 +170,6 @@ impl Cargo {
     }
 
     /// Finds the directory for a specified package reference.
-    #[allow(unused)]
     pub(crate) fn find_crate_dir(&self, package: &PackageRef) -> Option<PathBuf> {
         for meta_package in &self.metadata.packages {
             if meta_package.name == package.name && meta_package.version == package.version {
@@ -200,18 +210,14 @@ impl Cargo {
     pub(crate) fn expand_crate(
         &self,
         package: &PackageRef,
-        expand_all_features: bool,
-        expand_default_features: bool,
-        expand_features: &Option<Vec<String>>,
+        config: &ParseExpandConfig,
     ) -> Result<String, cargo_expand::Error> {
         cargo_expand::expand(
             &self.manifest_path,
             &package.name,
             &package.version,
             self.clean,
-            expand_all_features,
-            expand_default_features,
-            expand_features,
+            config,
         )
     }
 }
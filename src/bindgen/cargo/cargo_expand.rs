@@ -10,6 +10,10 @@ use std::path::Path;
 use std::process::Command;
 use std::str::{from_utf8, Utf8Error};
 
+use serde_json;
+
+use bindgen::config::ParseExpandConfig;
+
 extern crate tempfile;
 use self::tempfile::Builder;
 
@@ -20,8 +24,10 @@ pub enum Error {
     Io(io::Error),
     /// Output of `cargo metadata` was not valid utf8
     Utf8(Utf8Error),
-    /// Error during execution of `cargo rustc --pretty=expanded`
-    Compile(String),
+    /// The crate failed to compile, with the given error diagnostics
+    Compile(Vec<Diagnostic>),
+    /// `cargo` failed without any compiler diagnostics, with the given output
+    Cargo(String),
 }
 
 impl From<io::Error> for Error {
@@ -40,7 +46,16 @@ impl fmt::Display for Error {
         match self {
             Error::Io(ref err) => err.fmt(f),
             Error::Utf8(ref err) => err.fmt(f),
-            Error::Compile(ref err) => write!(f, "{}", err),
+            Error::Compile(ref diagnostics) => {
+                for (i, diagnostic) in diagnostics.iter().enumerate() {
+                    if i != 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", diagnostic)?;
+                }
+                Ok(())
+            }
+            Error::Cargo(ref err) => write!(f, "{}", err),
         }
     }
 }
@@ -50,11 +65,125 @@ impl error::Error for Error {
         match self {
             Error::Io(ref err) => Some(err),
             Error::Utf8(ref err) => Some(err),
-            Error::Compile(..) => None,
+            Error::Compile(..) | Error::Cargo(..) => None,
+        }
+    }
+}
+
+/// A message from the compiler, as reported by `--message-format=json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Diagnostic {
+    /// `error`, `warning`, `note`, ...
+    pub level: String,
+    pub message: String,
+    pub code: Option<DiagnosticCode>,
+    pub spans: Vec<DiagnosticSpan>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiagnosticCode {
+    /// The error code, e.g. `E0425`
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiagnosticSpan {
+    pub file_name: String,
+    pub line_start: usize,
+    pub column_start: usize,
+    pub is_primary: bool,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.level.starts_with("error")
+    }
+
+    /// The location the diagnostic points at, if any.
+    pub fn primary_span(&self) -> Option<&DiagnosticSpan> {
+        self.spans.iter().find(|x| x.is_primary)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.level)?;
+        if let Some(ref code) = self.code {
+            write!(f, "[{}]", code.code)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(span) = self.primary_span() {
+            write!(
+                f,
+                "\n --> {}:{}:{}",
+                span.file_name, span.line_start, span.column_start
+            )?;
         }
+        Ok(())
     }
 }
 
+/// A line of `cargo --message-format=json` output.
+#[derive(Debug, Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<Diagnostic>,
+}
+
+/// Splits the output of `cargo rustc --message-format=json -- --pretty=expanded`
+/// into the expanded source and the compiler diagnostics, which are printed
+/// to stdout alongside it.
+fn split_output(stdout: &str) -> (String, Vec<Diagnostic>) {
+    let mut src = String::new();
+    let mut diagnostics = Vec::new();
+
+    for line in stdout.lines() {
+        if line.starts_with("{\"reason\":") {
+            if let Ok(message) = serde_json::from_str::<CargoMessage>(line) {
+                if message.reason == "compiler-message" {
+                    diagnostics.extend(message.message);
+                }
+                continue;
+            }
+        }
+        src.push_str(line);
+        src.push('\n');
+    }
+
+    (src, diagnostics)
+}
+
+/// The environment variable to pass rustc flags to cargo in, and its value
+/// with `flags` added to the ones we're run with.
+pub fn rustflags(flags: &[String]) -> (&'static str, String) {
+    add_rustflags(
+        env::var("CARGO_ENCODED_RUSTFLAGS").ok(),
+        env::var("RUSTFLAGS").ok(),
+        flags,
+    )
+}
+
+/// Adds flags to `CARGO_ENCODED_RUSTFLAGS`, which cargo sets for build
+/// scripts and which takes precedence over `RUSTFLAGS`, if it's set, and to
+/// `RUSTFLAGS` otherwise.
+fn add_rustflags(
+    encoded: Option<String>,
+    plain: Option<String>,
+    flags: &[String],
+) -> (&'static str, String) {
+    let (var, separator, mut rustflags) = match encoded {
+        Some(encoded) => ("CARGO_ENCODED_RUSTFLAGS", '\x1f', encoded),
+        None => ("RUSTFLAGS", ' ', plain.unwrap_or_default()),
+    };
+    for flag in flags {
+        if !rustflags.is_empty() {
+            rustflags.push(separator);
+        }
+        rustflags.push_str(flag);
+    }
+    (var, rustflags)
+}
+
 /// Use rustc to expand and pretty print the crate into a single file,
 /// removing any macros in the process.
 pub fn expand(
@@ -62,12 +191,19 @@ pub fn expand(
     crate_name: &str,
     version: &str,
     use_tempdir: bool,
-    expand_all_features: bool,
-    expand_default_features: bool,
-    expand_features: &Option<Vec<String>>,
+    config: &ParseExpandConfig,
 ) -> Result<String, Error> {
-    let cargo = env::var("CARGO").unwrap_or_else(|_| String::from("cargo"));
-    let mut cmd = Command::new(cargo);
+    let mut cmd = match config.toolchain {
+        // `+toolchain` is handled by the rustup proxy, so bypass whichever
+        // cargo and rustc we may have been run by.
+        Some(ref toolchain) => {
+            let mut cmd = Command::new("cargo");
+            cmd.arg(format!("+{}", toolchain));
+            cmd.env_remove("RUSTC");
+            cmd
+        }
+        None => Command::new(env::var("CARGO").unwrap_or_else(|_| String::from("cargo"))),
+    };
 
     let mut _temp_dir = None; // drop guard
     if use_tempdir {
@@ -77,11 +213,25 @@ pub fn expand(
         cmd.env("CARGO_TARGET_DIR", path);
     }
 
+    if !config.rustflags.is_empty() {
+        let (var, rustflags) = rustflags(&config.rustflags);
+        cmd.env(var, rustflags);
+    }
+
     cmd.arg("rustc");
     cmd.arg("--lib");
+    cmd.arg("--message-format=json");
     cmd.arg("--manifest-path");
     cmd.arg(manifest_path);
-    if let Some(features) = expand_features {
+    if let Some(ref target) = config.target {
+        cmd.arg("--target");
+        cmd.arg(target);
+    }
+    if let Some(ref profile) = config.profile {
+        cmd.arg("--profile");
+        cmd.arg(profile);
+    }
+    if let Some(ref features) = config.features {
         cmd.arg("--features");
         let mut features_str = String::new();
         for (index, feature) in features.iter().enumerate() {
@@ -92,10 +242,10 @@ pub fn expand(
         }
         cmd.arg(features_str);
     }
-    if expand_all_features {
+    if config.all_features {
         cmd.arg("--all-features");
     }
-    if !expand_default_features {
+    if !config.default_features {
         cmd.arg("--no-default-features");
     }
     cmd.arg("-p");
@@ -106,12 +256,73 @@ pub fn expand(
     cmd.arg("--pretty=expanded");
     let output = cmd.output()?;
 
-    let src = from_utf8(&output.stdout)?.to_owned();
-    let error = from_utf8(&output.stderr)?.to_owned();
+    let (src, diagnostics) = split_output(from_utf8(&output.stdout)?);
+    let errors: Vec<Diagnostic> = diagnostics.into_iter().filter(|x| x.is_error()).collect();
 
-    if src.len() == 0 {
-        Err(Error::Compile(error))
+    if src.is_empty() {
+        if errors.is_empty() {
+            Err(Error::Cargo(from_utf8(&output.stderr)?.to_owned()))
+        } else {
+            Err(Error::Compile(errors))
+        }
     } else {
+        // Macros are expanded before most errors are found, so the source may
+        // still be usable.
+        for error in &errors {
+            warn!("Expanding crate `{}`: {}", crate_name, error);
+        }
         Ok(src)
     }
 }
+
+#[test]
+fn split() {
+    let stdout = concat!(
+        "{\"reason\":\"compiler-artifact\",\"package_id\":\"demo\"}\n",
+        "pub struct Foo;\n",
+        "{\"reason\":\"compiler-message\",\"message\":{\"level\":\"warning\",",
+        "\"message\":\"unused\",\"code\":null,\"spans\":[]}}\n",
+        "fn f() { let x = \"{\\\"reason\\\":\"; }\n",
+        "{\"reason\": not json\n",
+    );
+    let (src, diagnostics) = split_output(stdout);
+    assert_eq!(
+        src,
+        "pub struct Foo;\nfn f() { let x = \"{\\\"reason\\\":\"; }\n{\"reason\": not json\n"
+    );
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "unused");
+    assert!(!diagnostics[0].is_error());
+
+    let (src, diagnostics) = split_output("");
+    assert!(src.is_empty() && diagnostics.is_empty());
+}
+
+#[test]
+fn rustflags_env() {
+    let flags = vec!["--cfg".to_owned(), "cbindgen".to_owned()];
+    assert_eq!(
+        add_rustflags(None, None, &flags),
+        ("RUSTFLAGS", "--cfg cbindgen".to_owned())
+    );
+    assert_eq!(
+        add_rustflags(None, Some("-Dwarnings".to_owned()), &flags),
+        ("RUSTFLAGS", "-Dwarnings --cfg cbindgen".to_owned())
+    );
+    // Build scripts are run with the encoded flags, which cargo prefers.
+    assert_eq!(
+        add_rustflags(
+            Some("-C\x1fopt-level=2".to_owned()),
+            Some("-Dwarnings".to_owned()),
+            &flags
+        ),
+        (
+            "CARGO_ENCODED_RUSTFLAGS",
+            "-C\x1fopt-level=2\x1f--cfg\x1fcbindgen".to_owned()
+        )
+    );
+    assert_eq!(
+        add_rustflags(Some(String::new()), None, &flags),
+        ("CARGO_ENCODED_RUSTFLAGS", "--cfg\x1fcbindgen".to_owned())
+    );
+}
@@ -28,6 +28,8 @@ pub struct Metadata {
     version: usize,
     /// path to the workspace containing the `Cargo.lock`
     pub workspace_root: String,
+    /// path to the directory build artifacts are put in
+    pub target_directory: Option<String>,
 }
 
 #[derive(Clone, Deserialize, Debug)]
@@ -5,7 +5,7 @@
 use std::io::Write;
 
 use bindgen::declarationtyperesolver::DeclarationType;
-use bindgen::ir::{Function, Type};
+use bindgen::ir::{Function, Path, Type};
 use bindgen::writer::{ListType, SourceWriter};
 
 // This code is for translating Rust types into C declarations.
@@ -33,6 +33,7 @@ impl CDeclarator {
 struct CDecl {
     type_qualifers: String,
     type_name: String,
+    type_path: Option<Path>,
     type_generic_args: Vec<Type>,
     declarators: Vec<CDeclarator>,
     type_ctype: Option<DeclarationType>,
@@ -43,6 +44,7 @@ impl CDecl {
         CDecl {
             type_qualifers: String::new(),
             type_name: String::new(),
+            type_path: None,
             type_generic_args: Vec::new(),
             declarators: Vec::new(),
             type_ctype: None,
@@ -89,6 +91,7 @@ impl CDecl {
                     t
                 );
                 self.type_name = generic.export_name().to_owned();
+                self.type_path = Some(generic.path().clone());
                 assert!(
                     self.type_generic_args.len() == 0,
                     "error generating cdecl for {:?}",
@@ -115,11 +118,11 @@ impl CDecl {
                 self.type_name = p.to_string();
             }
 
-            &Type::ConstPtr(ref t) => {
+            &Type::ConstPtr(ref t, _) => {
                 self.declarators.push(CDeclarator::Ptr(is_const));
                 self.build_type(t, true);
             }
-            &Type::Ptr(ref t) => {
+            &Type::Ptr(ref t, _) => {
                 self.declarators.push(CDeclarator::Ptr(is_const));
                 self.build_type(t, false);
             }
@@ -145,6 +148,9 @@ impl CDecl {
                 self.declarators.push(CDeclarator::Func(args, false));
                 self.build_type(ret, false);
             }
+            &Type::ConstFatPtr(..) | &Type::FatPtr(..) => {
+                panic!("fat pointer should have been lowered before generating cdecl")
+            }
         }
     }
 
@@ -159,16 +165,48 @@ impl CDecl {
             write!(out, "{} ", self.type_qualifers);
         }
 
-        if let Some(ref ctype) = self.type_ctype {
-            write!(out, "{} ", ctype.to_str());
-        }
+        let mapped_type = self
+            .type_path
+            .as_ref()
+            .and_then(|path| out.bindings().mapped_type(path));
+
+        if let Some(mapped_type) = mapped_type {
+            // Mapped types are spelled out with their generic arguments
+            // substituted in.
+            let generic_values: Vec<String> = self
+                .type_generic_args
+                .iter()
+                .map(|ty| {
+                    let mut buffer = Vec::new();
+                    {
+                        let mut writer = SourceWriter::new(&mut buffer, out.bindings());
+                        writer.set_namespace(out.namespace().to_vec());
+                        write_type(&mut writer, ty);
+                    }
+                    String::from_utf8(buffer).unwrap()
+                })
+                .collect();
+            write!(out, "{}", mapped_type.spelling(&generic_values));
+        } else {
+            if let Some(ref ctype) = self.type_ctype {
+                write!(out, "{} ", ctype.to_str());
+            }
 
-        write!(out, "{}", self.type_name);
+            match self.type_path {
+                Some(ref path) => {
+                    let name =
+                        out.bindings()
+                            .qualified_name(path, &self.type_name, out.namespace());
+                    write!(out, "{}", name);
+                }
+                None => write!(out, "{}", self.type_name),
+            }
 
-        if !self.type_generic_args.is_empty() {
-            out.write("<");
-            out.write_horizontal_source_list(&self.type_generic_args, ListType::Join(", "));
-            out.write(">");
+            if !self.type_generic_args.is_empty() {
+                out.write("<");
+                out.write_horizontal_source_list(&self.type_generic_args, ListType::Join(", "));
+                out.write(">");
+            }
         }
 
         // When we have an identifier, put a space between the type and the declarators
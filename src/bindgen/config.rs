@@ -2,7 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::default::Default;
 use std::fmt;
 use std::fs::File;
@@ -10,20 +10,28 @@ use std::io::prelude::*;
 use std::io::{self, BufReader};
 use std::path::Path as StdPath;
 use std::str::FromStr;
+use std::thread;
 
 use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
-use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::Serializer;
 
 use toml;
 
+use bindgen::config_keys;
+use bindgen::explain;
+use bindgen::filter;
+
 use bindgen::ir::annotation::AnnotationSet;
 use bindgen::ir::path::Path;
+use bindgen::regex::Regex;
+use bindgen::rename::IdentifierType;
 pub use bindgen::rename::RenameRule;
 
 pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
 /// A language type to generate bindings for.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum Language {
     Cxx,
     C,
@@ -52,7 +60,7 @@ impl FromStr for Language {
 deserialize_enum_str!(Language);
 
 /// A style of braces to use for generating code.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum Braces {
     SameLine,
     NextLine,
@@ -75,7 +83,7 @@ impl FromStr for Braces {
 deserialize_enum_str!(Braces);
 
 /// A type of layout to use when generating long lines of code.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum Layout {
     Horizontal,
     Vertical,
@@ -101,7 +109,7 @@ impl FromStr for Layout {
 deserialize_enum_str!(Layout);
 
 /// How the comments containing documentation should be styled.
-#[derive(Debug, Clone, PartialEq, Copy)]
+#[derive(Debug, Clone, PartialEq, Copy, Serialize)]
 pub enum DocumentationStyle {
     C,
     C99,
@@ -129,7 +137,7 @@ impl FromStr for DocumentationStyle {
 deserialize_enum_str!(DocumentationStyle);
 
 /// A style of Style to use when generating structs and enums.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum Style {
     Both,
     Tag,
@@ -172,8 +180,68 @@ impl FromStr for Style {
 
 deserialize_enum_str!(Style);
 
+/// A way to declare constants.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ConstantStyle {
+    /// `#define NAME ((type)value)`
+    Define,
+    /// `static const type NAME = value;`
+    StaticConst,
+    /// `static constexpr type NAME = value;`, only for C++ and constants that
+    /// aren't structs
+    Constexpr,
+    /// `inline constexpr type NAME = value;`, only for C++17 and constants
+    /// that aren't structs
+    InlineConstexpr,
+    /// `enum : type { NAME = value };`, only for integers, which must fit an
+    /// `int` in C
+    Enum,
+}
+
+impl FromStr for ConstantStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ConstantStyle, Self::Err> {
+        match s {
+            "Define" | "define" => Ok(ConstantStyle::Define),
+            "StaticConst" | "static_const" => Ok(ConstantStyle::StaticConst),
+            "Constexpr" | "constexpr" => Ok(ConstantStyle::Constexpr),
+            "InlineConstexpr" | "inline_constexpr" => Ok(ConstantStyle::InlineConstexpr),
+            "Enum" | "enum" => Ok(ConstantStyle::Enum),
+            _ => Err(format!("Unrecognized ConstantStyle: '{}'.", s)),
+        }
+    }
+}
+
+deserialize_enum_str!(ConstantStyle);
+
+/// A way to declare a trailing zero-length array, which C++ has no standard
+/// way to declare.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum FlexibleArrayStyle {
+    /// `type name[];`, a flexible array member like in C99
+    Flexible,
+    /// `type name[0];`, a zero-length array
+    ZeroLength,
+}
+
+impl FromStr for FlexibleArrayStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<FlexibleArrayStyle, Self::Err> {
+        match s {
+            "Flexible" | "flexible" => Ok(FlexibleArrayStyle::Flexible),
+            "ZeroLength" | "zero_length" => Ok(FlexibleArrayStyle::ZeroLength),
+            _ => Err(format!("Unrecognized FlexibleArrayStyle: '{}'.", s)),
+        }
+    }
+}
+
+deserialize_enum_str!(FlexibleArrayStyle);
+
 /// Different item types that we can generate and filter.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ItemType {
     Constants,
     Globals,
@@ -181,6 +249,7 @@ pub enum ItemType {
     Structs,
     Unions,
     Typedefs,
+    #[serde(rename = "opaque")]
     OpaqueItems,
     Functions,
 }
@@ -207,26 +276,107 @@ impl FromStr for ItemType {
 deserialize_enum_str!(ItemType);
 
 /// Settings to apply when exporting items.
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
 pub struct ExportConfig {
     /// A list of additional items not used by exported functions to include in
-    /// the generated bindings
+    /// the generated bindings. Rules can be names, globs, regexes between
+    /// slashes, or module-qualified paths.
     pub include: Vec<String>,
-    /// A list of items to not include in the generated bindings
+    /// A list of items to not include in the generated bindings, with rules
+    /// like `include`
     pub exclude: Vec<String>,
     /// Table of name conversions to apply to item names
     pub rename: HashMap<String, String>,
+    /// Regex rename rules, the first matching of which applies to items that
+    /// aren't in `rename`
+    pub rename_patterns: Vec<RenamePattern>,
+    /// Case conversions for the names of functions, constants and globals
+    /// that aren't renamed otherwise
+    pub rename_rules: ItemRenameRules,
     /// Table of raw strings to append to the body of items.
     pub body: HashMap<String, String>,
-    /// A prefix to add before the name of every item
+    /// A prefix to add before the name of every type and constant
     pub prefix: Option<String>,
+    /// Prefixes for each type of item, used instead of `prefix`
+    pub prefixes: HashMap<ItemType, String>,
     /// Types of items to generate.
     pub item_types: Vec<ItemType>,
     /// Whether renaming overrides or extends prefixing.
     pub renaming_overrides_prefixing: bool,
+    /// Table of Rust types, possibly generic, to spell as a given C type
+    pub type_map: HashMap<String, TypeMapping>,
+    /// The item types of the types being renamed, by name, so that references
+    /// to a type get the prefix of its item type.
+    #[serde(skip)]
+    pub(crate) type_item_types: HashMap<String, ItemType>,
+    /// The items in `rename` that were renamed by item visitors rather than
+    /// the config, to explain where their name comes from.
+    #[serde(skip)]
+    pub(crate) visitor_renames: HashSet<String>,
+}
+
+/// A rule renaming the items whose name matches a regex, like
+/// `{ pattern = "^(.*)Ffi$", replacement = "$1" }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct RenamePattern {
+    #[serde(serialize_with = "serialize_regex")]
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub pattern: Regex,
+    /// The new name, in which `$1` or `${1}` stand for what the first group
+    /// of the pattern matched, and `$name` for a named group
+    pub replacement: String,
+    /// The types of items to rename, or all of them if empty
+    #[serde(default)]
+    pub item_types: Vec<ItemType>,
+}
+
+impl RenamePattern {
+    #[allow(unused)]
+    pub fn new(pattern: &str, replacement: &str) -> Result<RenamePattern, String> {
+        Ok(RenamePattern {
+            pattern: Regex::new(pattern)?,
+            replacement: replacement.to_owned(),
+            item_types: Vec::new(),
+        })
+    }
+}
+
+fn serialize_regex<S: Serializer>(regex: &Regex, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(regex)
+}
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern)
+        .map_err(|msg| de::Error::custom(format!("invalid pattern `{}`: {}", pattern, msg)))
+}
+
+/// Case conversions for the names of items that are snake_case, or
+/// SCREAMING_SNAKE_CASE, in Rust.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct ItemRenameRules {
+    pub functions: Option<RenameRule>,
+    pub constants: Option<RenameRule>,
+    pub globals: Option<RenameRule>,
+}
+
+impl ItemRenameRules {
+    fn get(&self, item_type: &ItemType) -> Option<RenameRule> {
+        match *item_type {
+            ItemType::Functions => self.functions,
+            ItemType::Constants => self.constants,
+            ItemType::Globals => self.globals,
+            _ => None,
+        }
+    }
 }
 
 impl ExportConfig {
@@ -238,21 +388,169 @@ impl ExportConfig {
         self.body.get(path.name()).map(|s| s.trim_matches('\n'))
     }
 
+    /// Renames a type, or a constant literal's struct.
     pub(crate) fn rename(&self, item_name: &mut String) {
-        if let Some(name) = self.rename.get(item_name) {
+        let item_type = self.type_item_types.get(item_name.as_str()).cloned();
+        self.rename_item(item_type.as_ref(), item_name);
+    }
+
+    /// Renames an item of `item_type`, which is only unknown for types that
+    /// aren't parsed.
+    ///
+    /// Items are renamed by `rename`, else by the first matching of
+    /// `rename_patterns`, else by the rule of `rename_rules` for their type.
+    /// The prefix for their type is then added, unless they were renamed by
+    /// `rename` or `rename_patterns` and renaming overrides prefixing.
+    pub(crate) fn rename_item(&self, item_type: Option<&ItemType>, item_name: &mut String) {
+        self.rename_item_explained(item_type, item_name);
+    }
+
+    /// Renames an item like `rename_item`, returning which settings renamed
+    /// or prefixed it.
+    pub(crate) fn rename_item_explained(
+        &self,
+        item_type: Option<&ItemType>,
+        item_name: &mut String,
+    ) -> Vec<String> {
+        let mut reasons = Vec::new();
+        let renamed = if let Some(name) = self.rename.get(item_name.as_str()) {
+            reasons.push(if self.visitor_renames.contains(item_name.as_str()) {
+                "renamed by an item visitor".to_owned()
+            } else {
+                "renamed by `[export.rename]`".to_owned()
+            });
             *item_name = name.clone();
-            if self.renaming_overrides_prefixing {
-                return;
+            true
+        } else if let Some((pattern, name)) = self.rename_by_pattern(item_type, item_name) {
+            reasons.push(format!(
+                "renamed by the pattern `{}` of `[[export.rename_patterns]]`",
+                pattern.pattern
+            ));
+            *item_name = name;
+            true
+        } else {
+            let rule = item_type.and_then(|x| self.rename_rules.get(x).map(|y| (x, y)));
+            if let Some((item_type, rule)) = rule {
+                let new_name = if !item_name.chars().any(|c| c.is_lowercase()) {
+                    // Constants are usually SCREAMING_SNAKE_CASE.
+                    rule.apply_to_snake_case(&item_name.to_lowercase(), IdentifierType::Item)
+                } else if item_name.starts_with(|c: char| c.is_uppercase()) {
+                    rule.apply_to_pascal_case(item_name, IdentifierType::Item)
+                } else {
+                    rule.apply_to_snake_case(item_name, IdentifierType::Item)
+                };
+                if new_name != *item_name {
+                    reasons.push(format!(
+                        "renamed by `{} = \"{:?}\"` in `[export.rename_rules]`",
+                        explain::item_type_name(item_type),
+                        rule
+                    ));
+                }
+                *item_name = new_name;
             }
+            false
+        };
+        if renamed && self.renaming_overrides_prefixing {
+            return reasons;
         }
-        if let Some(ref prefix) = self.prefix {
-            item_name.insert_str(0, &prefix);
+
+        let prefix = match item_type {
+            Some(item_type) => self.prefixes.get(item_type).or(match *item_type {
+                // Functions and globals aren't prefixed by `prefix`, as they
+                // weren't before `prefixes` existed.
+                ItemType::Functions | ItemType::Globals => None,
+                _ => self.prefix.as_ref(),
+            }),
+            None => self.prefix.as_ref(),
+        };
+        if let Some(prefix) = prefix {
+            item_name.insert_str(0, prefix);
+            reasons.push(
+                if item_type.map_or(false, |x| self.prefixes.contains_key(x)) {
+                    "prefixed by `[export.prefixes]`".to_owned()
+                } else {
+                    "prefixed by `[export] prefix`".to_owned()
+                },
+            );
         }
+        reasons
+    }
+
+    fn rename_by_pattern(
+        &self,
+        item_type: Option<&ItemType>,
+        item_name: &str,
+    ) -> Option<(&RenamePattern, String)> {
+        self.rename_patterns
+            .iter()
+            .filter(|x| {
+                x.item_types.is_empty() || item_type.map_or(false, |y| x.item_types.contains(y))
+            })
+            .filter_map(|x| Some((x, x.pattern.replace(item_name, &x.replacement)?)))
+            .next()
+    }
+}
+
+/// The C spelling of a Rust type that isn't parsed, for example one from `libc`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeMapping {
+    /// The type to output. Generic parameters of the Rust type are replaced
+    /// with the corresponding arguments.
+    pub name: String,
+    /// A header to include for the type, like `<time.h>` or `"foo.h"`
+    pub include: Option<String>,
+    /// A declaration to output before the type is used, like `struct timespec;`
+    pub declaration: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+struct TypeMappingTable {
+    name: String,
+    include: Option<String>,
+    declaration: Option<String>,
+}
+
+// A type mapping can either be the name of the C type, as in `"timespec" =
+// "struct timespec"`, or a table with extra information about the type.
+impl<'de> Deserialize<'de> for TypeMapping {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TypeMapping, D::Error> {
+        struct TypeMappingVisitor;
+
+        impl<'de> Visitor<'de> for TypeMappingVisitor {
+            type Value = TypeMapping;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string or a map")
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<Self::Value, E> {
+                Ok(TypeMapping {
+                    name: name.to_owned(),
+                    include: None,
+                    declaration: None,
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                let table = <TypeMappingTable as Deserialize>::deserialize(
+                    MapAccessDeserializer::new(map),
+                )?;
+                Ok(TypeMapping {
+                    name: table.name,
+                    include: table.include,
+                    declaration: table.declaration,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(TypeMappingVisitor)
     }
 }
 
 /// Settings to apply to generated functions.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
@@ -298,7 +596,7 @@ impl FunctionConfig {
 }
 
 /// Settings to apply to generated structs.
-#[derive(Debug, Default, Clone, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
@@ -321,10 +619,20 @@ pub struct StructConfig {
     /// Whether to generate a greater than or equal to operator on structs with one field
     pub derive_gte: bool,
     /// Whether associated constants should be in the body. Only applicable to
-    /// non-transparent structs, and in C++-only.
+    /// non-transparent structs, and in C++-only. Constants written as macros
+    /// are still written after the struct.
     pub associated_constants_in_body: bool,
     /// The way to annotation this struct as #[must_use].
     pub must_use: Option<String>,
+    /// Whether to generate member functions for the functions named after a
+    /// struct in snake case, after `method_prefix`, whose first argument
+    /// points to it. Only applicable in C++.
+    pub derive_methods: bool,
+    /// The prefix of the names of functions to generate member functions for
+    pub method_prefix: Option<String>,
+    /// The way to declare a trailing zero-length array in C++. C always uses
+    /// a flexible array member.
+    pub cpp_flexible_array: Option<FlexibleArrayStyle>,
 }
 
 impl StructConfig {
@@ -370,10 +678,24 @@ impl StructConfig {
         }
         self.derive_gte
     }
+    pub(crate) fn derive_methods(&self, annotations: &AnnotationSet) -> bool {
+        if let Some(x) = annotations.bool("derive-methods") {
+            return x;
+        }
+        self.derive_methods
+    }
+    pub(crate) fn flexible_array(&self, language: &Language) -> FlexibleArrayStyle {
+        match *language {
+            Language::C => FlexibleArrayStyle::Flexible,
+            Language::Cxx => self
+                .cpp_flexible_array
+                .unwrap_or(FlexibleArrayStyle::Flexible),
+        }
+    }
 }
 
 /// Settings to apply to generated enums.
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
@@ -428,35 +750,168 @@ impl EnumConfig {
 }
 
 /// Settings to apply to generated constants.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
 pub struct ConstantConfig {
     /// Whether a generated constant can be a static const in C++ mode.
     pub allow_static_const: bool,
+    /// The style to declare constants in. Without one, constants are
+    /// `static const` in C++ if `allow_static_const` is set, and untyped
+    /// `#define`s otherwise.
+    pub style: Option<ConstantStyle>,
 }
 
 impl Default for ConstantConfig {
     fn default() -> ConstantConfig {
         ConstantConfig {
             allow_static_const: true,
+            style: None,
+        }
+    }
+}
+
+impl ConstantConfig {
+    /// The style set for a constant with `annotations`, if any.
+    pub(crate) fn style(&self, annotations: &AnnotationSet) -> Option<ConstantStyle> {
+        if let Some(Some(style)) = annotations.atom("const-style") {
+            match style.parse() {
+                Ok(x) => return Some(x),
+                Err(e) => warn!("Ignoring const-style annotation - ({})", e),
+            }
+        }
+        self.style
+    }
+
+    /// The style of constants without one, or with one they can't use.
+    pub(crate) fn default_style(&self, language: &Language) -> ConstantStyle {
+        if self.allow_static_const && *language == Language::Cxx {
+            ConstantStyle::StaticConst
+        } else {
+            ConstantStyle::Define
+        }
+    }
+}
+
+/// Settings to put the items of each rust module in a C++ namespace of its
+/// own, within the root `namespace` and `namespaces`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct ModuleNamespaceConfig {
+    /// Whether to nest the items of a module in namespaces named after its
+    /// path, like `audio::codec` for `crate::audio::codec`. Items of other
+    /// crates are in a namespace named after their crate.
+    pub mirror: bool,
+    /// The namespaces of modules, like `"crate::audio" = "snd"`, which also
+    /// apply to their submodules. An empty namespace is the root one.
+    pub map: HashMap<String, String>,
+    /// Whether to declare functions and globals in the namespace of their
+    /// module, instead of in a single `extern "C"` block in the root one.
+    pub functions: bool,
+}
+
+impl ModuleNamespaceConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.mirror || !self.map.is_empty()
+    }
+
+    /// The namespace of the items of the module at `module` in a crate.
+    pub(crate) fn namespace(
+        &self,
+        crate_name: &str,
+        binding_crate: bool,
+        module: &[String],
+    ) -> Vec<String> {
+        let mut mirrored = if binding_crate {
+            vec![]
+        } else {
+            vec![crate_name.replace('-', "_")]
+        };
+        mirrored.extend(module.iter().cloned());
+
+        // The longest module path in the map the module is in, if any.
+        let mut mapped: Option<(usize, &str)> = None;
+        for len in 0..module.len() + 1 {
+            for path in filter::qualified_paths(crate_name, binding_crate, &module[..len]) {
+                if let Some(namespace) = self.map.get(&path) {
+                    mapped = Some((len, namespace));
+                }
+            }
+        }
+
+        match mapped {
+            Some((len, namespace)) => {
+                let mut segments: Vec<String> = namespace
+                    .split("::")
+                    .filter(|x| !x.is_empty())
+                    .map(|x| x.to_owned())
+                    .collect();
+                if self.mirror {
+                    segments.extend(module[len..].iter().cloned());
+                }
+                segments
+            }
+            None if self.mirror => mirrored,
+            None => vec![],
         }
     }
 }
 
 /// Settings for custom macro expansion.
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
 pub struct MacroExpansionConfig {
     /// Whether the `bitflags` macro should be expanded.
     pub bitflags: bool,
+    /// Whether item macros defined with `macro_rules!` in the binding crate
+    /// should be expanded.
+    pub macro_rules: bool,
+}
+
+/// Settings to apply to pointers to slices, `str` and trait objects.
+///
+/// Each of these is a "fat" pointer in Rust, and is lowered to a struct with
+/// the given name. An occurrence of `{}` is replaced with the name of the
+/// slice element type or of the trait.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct FatPointerConfig {
+    /// The struct name for `&[T]` and `*const [T]`
+    pub slice: String,
+    /// The struct name for `&mut [T]` and `*mut [T]`
+    pub slice_mut: String,
+    /// The struct name for `&str` and `*const str`
+    pub str: String,
+    /// The struct name for `&mut str` and `*mut str`
+    pub str_mut: String,
+    /// The struct name for `&dyn Trait` and `*const dyn Trait`
+    pub trait_object: String,
+    /// The struct name for `&mut dyn Trait` and `*mut dyn Trait`
+    pub trait_object_mut: String,
+}
+
+impl Default for FatPointerConfig {
+    fn default() -> FatPointerConfig {
+        FatPointerConfig {
+            slice: "Slice{}".to_owned(),
+            slice_mut: "SliceMut{}".to_owned(),
+            str: "Str".to_owned(),
+            str_mut: "StrMut".to_owned(),
+            trait_object: "Dyn{}".to_owned(),
+            trait_object_mut: "DynMut{}".to_owned(),
+        }
+    }
 }
 
 /// Settings to apply when running `rustc --pretty=expanded`
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
@@ -470,6 +925,15 @@ pub struct ParseExpandConfig {
     /// List of features to use when expanding. Combines with `default_features` like in
     /// `Cargo.toml`.
     pub features: Option<Vec<String>>,
+    /// The rustup toolchain to expand with, e.g. `nightly`.
+    pub toolchain: Option<String>,
+    /// The target triple to expand for.
+    pub target: Option<String>,
+    /// The cargo profile to expand with.
+    pub profile: Option<String>,
+    /// Extra flags to pass to rustc through `RUSTFLAGS`, or through
+    /// `CARGO_ENCODED_RUSTFLAGS` if it's set.
+    pub rustflags: Vec<String>,
 }
 
 impl Default for ParseExpandConfig {
@@ -479,6 +943,10 @@ impl Default for ParseExpandConfig {
             all_features: false,
             default_features: true,
             features: None,
+            toolchain: None,
+            target: None,
+            profile: None,
+            rustflags: Vec::new(),
         }
     }
 }
@@ -508,8 +976,7 @@ fn retrocomp_parse_expand_config_deserialize<'de, D: Deserializer<'de>>(
             Ok(ParseExpandConfig {
                 crates,
                 all_features: true,
-                default_features: true,
-                features: None,
+                ..ParseExpandConfig::default()
             })
         }
 
@@ -521,8 +988,24 @@ fn retrocomp_parse_expand_config_deserialize<'de, D: Deserializer<'de>>(
     deserializer.deserialize_any(ParseExpandVisitor)
 }
 
+/// Settings for the on-disk cache of parsed crates.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct ParseCacheConfig {
+    /// Whether to cache parsed crates, and the source of expanded crates, on
+    /// disk. A crate is parsed again if any of its source files changed, or if
+    /// cbindgen or the parse settings did. An expanded crate is also expanded
+    /// again if `Cargo.lock` changed.
+    pub enabled: bool,
+    /// The directory to store the cache in. Defaults to `cbindgen` in cargo's
+    /// target directory.
+    pub dir: Option<String>,
+}
+
 /// Settings to apply when parsing.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
@@ -536,9 +1019,11 @@ pub struct ParseConfig {
     /// before parsing. A crate marked in `expand` doesn't need to be added to any
     /// whitelist.
     pub parse_deps: bool,
-    /// An optional whitelist of names of crates to parse
+    /// An optional whitelist of names of crates to parse, which can be globs
+    /// or regexes between slashes
     pub include: Option<Vec<String>>,
-    /// The names of crates to not parse
+    /// The names of crates to not parse, or paths of modules to skip, with
+    /// rules like `[export] include`
     pub exclude: Vec<String>,
     /// The configuration options for `rustc --pretty=expanded`
     #[serde(deserialize_with = "retrocomp_parse_expand_config_deserialize")]
@@ -546,6 +1031,11 @@ pub struct ParseConfig {
     /// Whether to use a new temporary target directory when running `rustc --pretty=expanded`.
     /// This may be required for some build processes.
     pub clean: bool,
+    /// The configuration options for the on-disk cache of parsed crates
+    pub cache: ParseCacheConfig,
+    /// The number of threads to parse source files with. Defaults to the
+    /// number of CPUs.
+    pub jobs: Option<usize>,
 }
 
 impl Default for ParseConfig {
@@ -556,12 +1046,48 @@ impl Default for ParseConfig {
             exclude: Vec::new(),
             expand: ParseExpandConfig::default(),
             clean: false,
+            cache: ParseCacheConfig::default(),
+            jobs: None,
         }
     }
 }
 
+impl ParseConfig {
+    pub(crate) fn num_jobs(&self) -> usize {
+        self.jobs.unwrap_or_else(|| {
+            thread::available_parallelism()
+                .map(|x| x.get())
+                .unwrap_or(1)
+        })
+    }
+}
+
+/// Settings of one of several sets of bindings generated from a single parse,
+/// which override those of the rest of the config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[serde(deny_unknown_fields)]
+pub struct OutputConfig {
+    /// The file to write the bindings to
+    pub path: String,
+    /// The language to output bindings for
+    pub language: Option<Language>,
+    /// The style to declare structs, enums and unions in for C
+    pub style: Option<Style>,
+    /// An optional name for the root namespace
+    pub namespace: Option<String>,
+    /// An optional list of namespaces
+    pub namespaces: Option<Vec<String>>,
+    /// Optional name to use for an include guard
+    pub include_guard: Option<String>,
+    /// Replaces `[export] include`
+    pub include: Option<Vec<String>>,
+    /// Replaces `[export] exclude`
+    pub exclude: Option<Vec<String>>,
+}
+
 /// A collection of settings to customize the generated bindings.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
@@ -589,6 +1115,9 @@ pub struct Config {
     pub namespace: Option<String>,
     /// An optional list of namespaces. Only applicable when language="C++"
     pub namespaces: Option<Vec<String>>,
+    /// The configuration options for namespaces of rust modules. Only
+    /// applicable when language="C++"
+    pub module_namespaces: ModuleNamespaceConfig,
     /// The style to use for braces
     pub braces: Braces,
     /// The preferred length of a line, used for auto breaking function arguments
@@ -617,12 +1146,18 @@ pub struct Config {
     /// The configuration options for constants
     #[serde(rename = "const")]
     pub constant: ConstantConfig,
+    /// The configuration options for fat pointers
+    pub fat_pointer: FatPointerConfig,
     /// Preprocessor defines to use when generating #ifdef's for #[cfg]
     pub defines: HashMap<String, String>,
     /// Include doc comments from rust as documentation
     pub documentation: bool,
     /// How documentation comments should be styled.
     pub documentation_style: DocumentationStyle,
+    /// Sets of bindings to generate from a single parse, instead of one with
+    /// the rest of the config
+    #[serde(rename = "output")]
+    pub outputs: Vec<OutputConfig>,
 }
 
 impl Default for Config {
@@ -638,6 +1173,7 @@ impl Default for Config {
             no_includes: false,
             namespace: None,
             namespaces: None,
+            module_namespaces: ModuleNamespaceConfig::default(),
             braces: Braces::SameLine,
             line_length: 100,
             tab_width: 2,
@@ -650,9 +1186,11 @@ impl Default for Config {
             structure: StructConfig::default(),
             enumeration: EnumConfig::default(),
             constant: ConstantConfig::default(),
+            fat_pointer: FatPointerConfig::default(),
             defines: HashMap::new(),
             documentation: true,
             documentation_style: DocumentationStyle::Auto,
+            outputs: Vec::new(),
         }
     }
 }
@@ -667,12 +1205,79 @@ impl Config {
             Ok(contents)
         }
 
-        let config_text = read(file_name.as_ref()).unwrap();
+        let config_text = match read(file_name.as_ref()) {
+            Ok(x) => x,
+            Err(e) => return Err(format!("Couldn't read config file: {}.", e)),
+        };
 
-        match toml::from_str::<Config>(&config_text) {
-            Ok(x) => Ok(x),
-            Err(e) => Err(format!("Couldn't parse config file: {}.", e)),
+        Config::from_toml(&config_text)
+            .map_err(|errors| format!("Couldn't parse config file: {}", errors.join(" ")))
+    }
+
+    /// Parses the text of a `cbindgen.toml`. Keys that aren't settings are
+    /// errors, which suggest the setting they may be a misspelling of.
+    pub fn from_toml(text: &str) -> Result<Config, Vec<String>> {
+        let value = match toml::from_str::<toml::Value>(text) {
+            Ok(x) => x,
+            Err(e) => return Err(vec![format!("{}.", e)]),
+        };
+        let mut errors = Vec::new();
+        check_keys(&value, &mut Vec::new(), &mut errors);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        toml::from_str::<Config>(text).map_err(|e| vec![format!("{}.", e)])
+    }
+
+    /// The config as a `cbindgen.toml`, with every setting.
+    pub fn to_toml(&self) -> String {
+        // Going through a `toml::Value` sorts the keys, and writes the plain
+        // values of each table before its sub-tables, as TOML requires.
+        let value = toml::Value::try_from(self).expect("A config is always valid TOML.");
+        toml::to_string(&value).expect("A config is always valid TOML.")
+    }
+
+    /// A commented `cbindgen.toml` for a crate, which leaves every setting
+    /// but the language and the include guard at its default.
+    pub fn template(crate_name: &str, language: &Language) -> String {
+        let language = match *language {
+            Language::C => "C",
+            Language::Cxx => "C++",
+        };
+        let include_guard = format!("{}_H", crate_name.replace('-', "_").to_uppercase());
+        include_str!("config_template.toml")
+            .replace("{crate_name}", crate_name)
+            .replace("{language}", language)
+            .replace("{include_guard}", &include_guard)
+    }
+
+    /// The config of one of the `[[output]]`s, which is this one with the
+    /// settings of the output.
+    pub fn for_output(&self, output: &OutputConfig) -> Config {
+        let mut config = self.clone();
+        config.outputs = Vec::new();
+        if let Some(ref language) = output.language {
+            config.language = language.clone();
+        }
+        if let Some(ref style) = output.style {
+            config.style = style.clone();
+        }
+        if output.namespace.is_some() {
+            config.namespace = output.namespace.clone();
         }
+        if output.namespaces.is_some() {
+            config.namespaces = output.namespaces.clone();
+        }
+        if output.include_guard.is_some() {
+            config.include_guard = output.include_guard.clone();
+        }
+        if let Some(ref include) = output.include {
+            config.export.include = include.clone();
+        }
+        if let Some(ref exclude) = output.exclude {
+            config.export.exclude = exclude.clone();
+        }
+        config
     }
 
     pub fn from_root_or_default<P: AsRef<StdPath>>(root: P) -> Config {
@@ -681,7 +1286,176 @@ impl Config {
         if c.exists() {
             Config::from_file(c).unwrap()
         } else {
+            info!(
+                "No cbindgen.toml in {}, using the default config.",
+                root.as_ref().display()
+            );
             Config::default()
         }
     }
 }
+
+/// The keys of the table at `path` in a config, or `None` for tables whose
+/// keys are names, like `[defines]` or `[export.rename]`.
+fn known_keys(path: &[&str]) -> Option<&'static [&'static str]> {
+    Some(match *path {
+        [] => config_keys::fields::<Config>(),
+        ["parse"] => config_keys::fields::<ParseConfig>(),
+        ["parse", "expand"] => config_keys::fields::<ParseExpandConfig>(),
+        ["parse", "cache"] => config_keys::fields::<ParseCacheConfig>(),
+        ["export"] => config_keys::fields::<ExportConfig>(),
+        ["export", "rename_patterns"] => config_keys::fields::<RenamePattern>(),
+        ["export", "rename_rules"] => config_keys::fields::<ItemRenameRules>(),
+        ["export", "type_map", _] => config_keys::fields::<TypeMappingTable>(),
+        ["macro_expansion"] => config_keys::fields::<MacroExpansionConfig>(),
+        ["fn"] => config_keys::fields::<FunctionConfig>(),
+        ["struct"] => config_keys::fields::<StructConfig>(),
+        ["enum"] => config_keys::fields::<EnumConfig>(),
+        ["const"] => config_keys::fields::<ConstantConfig>(),
+        ["fat_pointer"] => config_keys::fields::<FatPointerConfig>(),
+        ["output"] => config_keys::fields::<OutputConfig>(),
+        ["module_namespaces"] => config_keys::fields::<ModuleNamespaceConfig>(),
+        _ => return None,
+    })
+}
+
+/// Adds an error for each key of `value`, the table at `path`, or of its
+/// sub-tables, that isn't a setting.
+fn check_keys<'a>(value: &'a toml::Value, path: &mut Vec<&'a str>, errors: &mut Vec<String>) {
+    match *value {
+        toml::Value::Table(ref table) => {
+            let known = known_keys(path);
+            for (key, value) in table {
+                path.push(key);
+                match known {
+                    Some(known) if !known.contains(&key.as_str()) => {
+                        errors.push(match config_keys::suggest(key, known) {
+                            Some(x) => {
+                                format!("Unknown key `{}`, did you mean `{}`?", path.join("."), x)
+                            }
+                            None => format!("Unknown key `{}`.", path.join(".")),
+                        });
+                    }
+                    _ => check_keys(value, path, errors),
+                }
+                path.pop();
+            }
+        }
+        // Arrays of tables, like `[[export.rename_patterns]]`.
+        toml::Value::Array(ref values) => {
+            for value in values {
+                check_keys(value, path, errors);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn unknown_keys() {
+    let errors = Config::from_toml(
+        r#"
+        language = "C"
+
+        [enum]
+        rename_variant = "ScreamingSnakeCase"
+
+        [[export.rename_patterns]]
+        pattern = "^(.*)Ffi$"
+        replacement = "$1"
+
+        [[export.rename_patterns]]
+        pattern = "^(.*)Sys$"
+        replacment = "$1"
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        errors,
+        [
+            "Unknown key `enum.rename_variant`, did you mean `rename_variants`?",
+            "Unknown key `export.rename_patterns.replacment`, did you mean `replacement`?",
+        ]
+    );
+
+    assert_eq!(
+        Config::from_toml("[struct]\nderive_hash = true\n").unwrap_err(),
+        ["Unknown key `struct.derive_hash`."]
+    );
+}
+
+#[test]
+fn names_are_not_keys() {
+    // The keys of these tables are item names and cfgs, not settings.
+    let config = Config::from_toml(
+        r#"
+        [export.rename]
+        "rename_variant" = "RenameVariant"
+        "Foo" = "Bar"
+
+        [defines]
+        "feature = serde" = "DEFINE_SERDE"
+
+        [export.type_map]
+        "libc::timespec" = { name = "struct timespec", include = "<time.h>" }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.export.rename["rename_variant"], "RenameVariant");
+    assert_eq!(config.defines["feature = serde"], "DEFINE_SERDE");
+
+    // But the keys of the tables of type mappings are.
+    let errors = Config::from_toml(
+        r#"
+        [export.type_map]
+        "libc::timespec" = { name = "struct timespec", includes = "<time.h>" }
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(
+        errors,
+        ["Unknown key `export.type_map.libc::timespec.includes`, did you mean `include`?"]
+    );
+}
+
+#[test]
+fn dump_round_trip() {
+    let config = Config::from_toml(
+        r#"
+        language = "C++"
+        namespaces = ["ffi", "demo"]
+
+        [export]
+        prefix = "Demo"
+        item_types = ["structs", "functions"]
+
+        [export.rename]
+        "Foo" = "Bar"
+
+        [[export.rename_patterns]]
+        pattern = "^(.*)Ffi$"
+        replacement = "$1"
+        item_types = ["structs"]
+
+        [enum]
+        rename_variants = "ScreamingSnakeCase"
+
+        [defines]
+        "feature = serde" = "DEFINE_SERDE"
+        "#,
+    )
+    .unwrap();
+    let dump = config.to_toml();
+    let reloaded = Config::from_toml(&dump).unwrap();
+    assert_eq!(reloaded.to_toml(), dump);
+    assert_eq!(reloaded.language, Language::Cxx);
+    assert_eq!(reloaded.export.rename["Foo"], "Bar");
+    assert_eq!(
+        reloaded.export.rename_patterns[0].pattern.to_string(),
+        "^(.*)Ffi$"
+    );
+    assert_eq!(reloaded.defines["feature = serde"], "DEFINE_SERDE");
+
+    let dump = Config::default().to_toml();
+    assert_eq!(Config::from_toml(&dump).unwrap().to_toml(), dump);
+}
@@ -0,0 +1,85 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::cell::Cell;
+use std::cmp;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+
+/// The names of the fields of a struct that derives `Deserialize`, which are
+/// the keys of its table in a config.
+pub fn fields<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    let fields = Cell::new(&[][..]);
+    let _ = T::deserialize(FieldsDeserializer(&fields));
+    fields.get()
+}
+
+/// A deserializer that records the fields it's asked for, and fails.
+struct FieldsDeserializer<'a>(&'a Cell<&'static [&'static str]>);
+
+impl<'de, 'a> Deserializer<'de> for FieldsDeserializer<'a> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.0.set(fields);
+        Err(de::Error::custom("only the fields are needed"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map enum identifier ignored_any
+    }
+}
+
+/// The known key closest to a misspelled `key`, if any is close enough to be
+/// what was meant.
+pub fn suggest<'a>(key: &str, known: &[&'a str]) -> Option<&'a str> {
+    let key = key.to_lowercase().replace('-', "_");
+    known
+        .iter()
+        .map(|x| (edit_distance(&key, x), *x))
+        .filter(|&(distance, x)| distance <= cmp::max(1, x.len() / 3))
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, x)| x)
+}
+
+/// The number of characters to insert, remove or replace to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..b.len() + 1).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let replaced = diagonal + if x == *y { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = cmp::min(replaced, cmp::min(row[j], row[j + 1]) + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[test]
+fn suggestions() {
+    let known = [
+        "rename_variants",
+        "prefix_with_name",
+        "derive_helper_methods",
+    ];
+    assert_eq!(suggest("rename_variant", &known), Some("rename_variants"));
+    assert_eq!(suggest("Rename-Variants", &known), Some("rename_variants"));
+    assert_eq!(suggest("prefix_with_nme", &known), Some("prefix_with_name"));
+    assert_eq!(suggest("derive_hash", &known), None);
+    assert_eq!(suggest("x", &known), None);
+}
@@ -3,14 +3,38 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::mem;
 
+use bindgen::error::Error;
+use bindgen::explain::Referrer;
 use bindgen::ir::{ItemContainer, Path};
 
+/// A reference from one item to another.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub from: Referrer,
+    pub to: Path,
+    /// Whether the referred item has to be complete where it's used, as for a
+    /// by-value field, rather than just declared, as for a pointer.
+    pub by_value: bool,
+}
+
 /// A dependency list is used for gathering what order to output the types.
 pub struct Dependencies {
     pub order: Vec<ItemContainer>,
     pub items: HashSet<Path>,
+    /// What first referred to each item, to explain why it's included.
+    pub referrers: HashMap<Path, Referrer>,
+    /// Every reference from one item to another, possibly repeated.
+    pub references: Vec<Reference>,
+    /// The items that are referred to through a pointer before they're
+    /// defined, which need to be declared first.
+    pub forward_declarations: HashSet<Path>,
+    /// The items whose dependencies are being added, innermost last.
+    referrer_stack: Vec<Referrer>,
+    /// How many pointers the types being added are behind.
+    indirection: usize,
 }
 
 impl Dependencies {
@@ -18,12 +42,55 @@ impl Dependencies {
         Dependencies {
             order: Vec::new(),
             items: HashSet::new(),
+            referrers: HashMap::new(),
+            references: Vec::new(),
+            forward_declarations: HashSet::new(),
+            referrer_stack: Vec::new(),
+            indirection: 0,
+        }
+    }
+
+    /// Adds the item at `path`, recording what referred to it. Returns false
+    /// if it was already added.
+    pub fn insert(&mut self, path: &Path) -> bool {
+        if let Some(referrer) = self.referrer_stack.last() {
+            self.references.push(Reference {
+                from: referrer.clone(),
+                to: path.clone(),
+                by_value: self.indirection == 0,
+            });
         }
+        if !self.items.insert(path.clone()) {
+            return false;
+        }
+        if let Some(referrer) = self.referrer_stack.last() {
+            self.referrers.insert(path.clone(), referrer.clone());
+        }
+        true
+    }
+
+    /// Runs `f` to add the dependencies of `referrer`.
+    pub fn with_referrer<F: FnOnce(&mut Dependencies)>(&mut self, referrer: Referrer, f: F) {
+        let indirection = mem::replace(&mut self.indirection, 0);
+        self.referrer_stack.push(referrer);
+        f(self);
+        self.referrer_stack.pop();
+        self.indirection = indirection;
     }
 
-    pub fn sort(&mut self) {
-        // Sort untagged enums and opaque structs into their own layers because they don't
-        // depend on each other or anything else.
+    /// Runs `f` to add the dependencies of a type behind a pointer.
+    pub fn with_indirection<F: FnOnce(&mut Dependencies)>(&mut self, f: F) {
+        self.indirection += 1;
+        f(self);
+        self.indirection -= 1;
+    }
+
+    /// Orders the items so that each comes after the items it needs to be
+    /// complete, and finds the items that need to be declared before they're
+    /// defined. Fails if items need each other to be complete.
+    pub fn sort(&mut self) -> Result<(), Error> {
+        // Sort untagged enums, mapped types and opaque structs into their own layers
+        // because they don't depend on each other or anything else.
         let ordering = |a: &ItemContainer, b: &ItemContainer| match (a, b) {
             (&ItemContainer::Enum(ref x), &ItemContainer::Enum(ref y))
                 if x.tag.is_none() && y.tag.is_none() =>
@@ -33,6 +100,12 @@ impl Dependencies {
             (&ItemContainer::Enum(ref x), _) if x.tag.is_none() => Ordering::Less,
             (_, &ItemContainer::Enum(ref x)) if x.tag.is_none() => Ordering::Greater,
 
+            (&ItemContainer::MappedType(ref x), &ItemContainer::MappedType(ref y)) => {
+                x.path.cmp(&y.path)
+            }
+            (&ItemContainer::MappedType(_), _) => Ordering::Less,
+            (_, &ItemContainer::MappedType(_)) => Ordering::Greater,
+
             (&ItemContainer::OpaqueItem(ref x), &ItemContainer::OpaqueItem(ref y)) => {
                 x.path.cmp(&y.path)
             }
@@ -43,5 +116,85 @@ impl Dependencies {
         };
 
         self.order.sort_by(ordering);
+
+        // Items are added after the items they refer to, except when they
+        // refer to each other, so move the items needed by value first.
+        let mut needs: HashMap<Path, Vec<Path>> = HashMap::new();
+        for reference in &self.references {
+            if let Referrer::Item(ref from) = reference.from {
+                if reference.by_value {
+                    needs
+                        .entry(from.clone())
+                        .or_default()
+                        .push(reference.to.clone());
+                }
+            }
+        }
+
+        let mut items: HashMap<Path, Vec<ItemContainer>> = HashMap::new();
+        let mut paths = Vec::new();
+        for item in mem::replace(&mut self.order, Vec::new()) {
+            let path = item.deref().path().clone();
+            if !items.contains_key(&path) {
+                paths.push(path.clone());
+            }
+            items.entry(path).or_default().push(item);
+        }
+
+        let mut visiting = Vec::new();
+        for path in &paths {
+            place(path, &needs, &mut items, &mut visiting, &mut self.order)?;
+        }
+
+        // Pointers to items that aren't defined yet need a declaration.
+        let mut positions = HashMap::new();
+        for (index, item) in self.order.iter().enumerate() {
+            positions
+                .entry(item.deref().path().clone())
+                .or_insert(index);
+        }
+        for reference in &self.references {
+            if let Referrer::Item(ref from) = reference.from {
+                if reference.by_value {
+                    continue;
+                }
+                if let (Some(from), Some(to)) = (positions.get(from), positions.get(&reference.to))
+                {
+                    if to >= from {
+                        self.forward_declarations.insert(reference.to.clone());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Adds the items at `path` to `order` after the items they need by value, if
+/// they weren't added yet.
+fn place(
+    path: &Path,
+    needs: &HashMap<Path, Vec<Path>>,
+    items: &mut HashMap<Path, Vec<ItemContainer>>,
+    visiting: &mut Vec<Path>,
+    order: &mut Vec<ItemContainer>,
+) -> Result<(), Error> {
+    if let Some(start) = visiting.iter().position(|x| x == path) {
+        let mut cycle: Vec<_> = visiting[start..].iter().map(|x| x.to_string()).collect();
+        cycle.push(path.to_string());
+        return Err(Error::DependencyCycle(cycle));
     }
+    if !items.contains_key(path) {
+        return Ok(());
+    }
+
+    visiting.push(path.clone());
+    for needed in needs.get(path).map(|x| &x[..]).unwrap_or(&[]) {
+        place(needed, needs, items, visiting, order)?;
+    }
+    visiting.pop();
+
+    order.extend(items.remove(path).unwrap());
+    Ok(())
 }
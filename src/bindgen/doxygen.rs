@@ -0,0 +1,103 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::collections::HashMap;
+
+use bindgen::markdown::{self, CodeBlock, Links};
+
+/// The section of the documentation a line is in, after a markdown heading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    Other,
+    Arguments,
+    Returns,
+    Safety,
+}
+
+/// Translates the markdown of rust documentation to Doxygen.
+///
+/// `links` maps the rust names of the generated items to the names they're
+/// exported as, so that intra-doc links can refer to them with `\ref`. Links
+/// to anything else are left as text.
+pub fn translate(lines: &[String], links: &HashMap<String, String>) -> Vec<String> {
+    let links = Links::new(lines, links);
+    let translate = |line: &str| {
+        links.translate(line, &|name, text| match text {
+            Some(text) => format!("\\ref {} \"{}\"", name, text),
+            None => format!("\\ref {}", name),
+        })
+    };
+
+    let mut out = Vec::new();
+    let mut section = Section::Other;
+    // Whether the next paragraph starts the section.
+    let mut section_start = false;
+    let mut code_block: Option<CodeBlock> = None;
+
+    for line in lines {
+        if let Some(block) = code_block.take() {
+            if block.closes(line) {
+                out.push("\\endcode".to_owned());
+                continue;
+            }
+            out.extend(block.shown(line));
+            code_block = Some(block);
+            continue;
+        }
+
+        if let Some(block) = CodeBlock::open(line) {
+            out.push("\\code".to_owned());
+            code_block = Some(block);
+            continue;
+        }
+
+        if let Some(title) = markdown::heading(line) {
+            section = match &*title.to_lowercase() {
+                _ if markdown::is_arguments_heading(title) => Section::Arguments,
+                "returns" | "return" | "return value" => Section::Returns,
+                "safety" => Section::Safety,
+                _ => {
+                    out.push(format!("@par {}", translate(title)));
+                    Section::Other
+                }
+            };
+            section_start = true;
+            continue;
+        }
+
+        if markdown::is_link_definition(line) {
+            continue;
+        }
+
+        if line.is_empty() {
+            // Drop the blank lines after headings and removed lines.
+            if !section_start && out.last().map_or(false, |x| !x.is_empty()) {
+                out.push(String::new());
+            }
+            continue;
+        }
+
+        let text = translate(line);
+        match section {
+            Section::Arguments => match markdown::argument(&text) {
+                Some((name, description)) => out.push(
+                    format!("@param {} {}", name, description)
+                        .trim_end()
+                        .to_owned(),
+                ),
+                None if line.starts_with(' ') => out.push(text.trim_start().to_owned()),
+                None => out.push(text),
+            },
+            Section::Returns if section_start => out.push(format!("@return {}", text)),
+            Section::Safety if section_start => out.push(format!("@warning {}", text)),
+            _ => out.push(text),
+        }
+        section_start = false;
+    }
+
+    while out.last().map_or(false, |x| x.is_empty()) {
+        out.pop();
+    }
+    out
+}
@@ -24,6 +24,21 @@ pub enum Error {
         crate_name: String,
         src_path: String,
     },
+    /// Items that need each other to be complete, so that none of them can be
+    /// defined first. The first item is repeated at the end.
+    DependencyCycle(Vec<String>),
+    InvalidFilterRule {
+        list: String,
+        rule: String,
+        error: String,
+    },
+    /// Items with the same name in modules that `[module_namespaces]` puts in
+    /// different namespaces, which can't be told apart as items are found by
+    /// name.
+    ConflictingModuleNamespaces {
+        name: String,
+        namespaces: Vec<String>,
+    },
 }
 
 impl fmt::Display for Error {
@@ -39,7 +54,7 @@ impl fmt::Display for Error {
             }
             &Error::CargoExpand(ref crate_name, ref error) => write!(
                 f,
-                "Parsing crate `{}`: couldn't run `cargo rustc --pretty=expanded`: {:?}",
+                "Parsing crate `{}`: couldn't run `cargo rustc --pretty=expanded`:\n{}",
                 crate_name, error
             ),
             &Error::ParseSyntaxError {
@@ -70,6 +85,34 @@ impl fmt::Display for Error {
                 "Parsing crate `{}`: cannot open file `{}`.",
                 crate_name, src_path
             ),
+            &Error::DependencyCycle(ref cycle) => {
+                write!(f, "Items contain each other by value: ")?;
+                for (index, path) in cycle.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " -> ")?;
+                    }
+                    write!(f, "{}", path)?;
+                }
+                write!(
+                    f,
+                    ". Use a pointer in one of them so that it can be declared before it's defined."
+                )
+            }
+            &Error::InvalidFilterRule {
+                ref list,
+                ref rule,
+                ref error,
+            } => write!(f, "Invalid rule `{}` in `{}`: {}.", rule, list, error),
+            Error::ConflictingModuleNamespaces {
+                ref name,
+                ref namespaces,
+            } => write!(
+                f,
+                "Items named `{}` are in different module namespaces: {}. Rename or exclude \
+                 all but one of them.",
+                name,
+                namespaces.join(", ")
+            ),
         }
     }
 }
@@ -82,6 +125,9 @@ impl error::Error for Error {
             Error::CargoExpand(_, ref error) => Some(error),
             Error::ParseSyntaxError { ref error, .. } => Some(error),
             Error::ParseCannotOpenFile { .. } => None,
+            Error::DependencyCycle(..) => None,
+            Error::InvalidFilterRule { .. } => None,
+            Error::ConflictingModuleNamespaces { .. } => None,
         }
     }
 }
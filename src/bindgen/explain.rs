@@ -0,0 +1,244 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use syn;
+
+use bindgen::config::ItemType;
+use bindgen::filter::{self, Filter, ItemPaths, Rule};
+use bindgen::ir::{Cfg, Path};
+
+/// What the parser did with a rust item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ParseOutcome {
+    Taken,
+    /// The item was taken, but can only be declared as an opaque type.
+    Opaque(String),
+    Skipped(String),
+}
+
+/// A rust item the parser came across, kept to explain what became of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseNote {
+    pub name: String,
+    pub kind: ItemKind,
+    pub crate_name: String,
+    /// Whether the crate is the binding crate, which rules can refer to as
+    /// `crate`.
+    pub binding_crate: bool,
+    /// The path of the module the item is in, within the crate.
+    pub module: Vec<String>,
+    /// The file the item is in, unless it comes from a macro expansion.
+    pub file: Option<PathBuf>,
+    pub cfg: Option<Cfg>,
+    pub outcome: ParseOutcome,
+}
+
+impl ParseNote {
+    pub fn new(
+        name: &syn::Ident,
+        kind: ItemKind,
+        crate_name: &str,
+        cfg: Option<Cfg>,
+        outcome: ParseOutcome,
+    ) -> ParseNote {
+        ParseNote {
+            name: name.to_string(),
+            kind,
+            crate_name: crate_name.to_owned(),
+            binding_crate: false,
+            module: Vec::new(),
+            file: None,
+            cfg,
+            outcome,
+        }
+    }
+
+    /// The module-qualified paths of the item, as matched by `include` and
+    /// `exclude` rules.
+    pub fn paths(&self) -> Vec<String> {
+        let mut segments = self.module.clone();
+        segments.push(self.name.clone());
+        filter::qualified_paths(&self.crate_name, self.binding_crate, &segments)
+    }
+
+    /// The `[export] item_types` entry the item is generated under, unless it
+    /// was skipped.
+    pub fn item_type(&self) -> Option<ItemType> {
+        Some(match self.outcome {
+            ParseOutcome::Skipped(..) => return None,
+            ParseOutcome::Opaque(..) => ItemType::OpaqueItems,
+            ParseOutcome::Taken => match self.kind {
+                ItemKind::Const => ItemType::Constants,
+                ItemKind::Static => ItemType::Globals,
+                ItemKind::Struct => ItemType::Structs,
+                ItemKind::Union => ItemType::Unions,
+                ItemKind::Enum => ItemType::Enums,
+                ItemKind::Type => ItemType::Typedefs,
+                ItemKind::Fn => ItemType::Functions,
+            },
+        })
+    }
+}
+
+impl fmt::Display for ParseNote {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} in crate `{}`", self.kind, self.crate_name)?;
+        if !self.module.is_empty() {
+            write!(f, ", mod `{}`", self.module.join("::"))?;
+        }
+        if let Some(ref file) = self.file {
+            write!(f, " ({})", file.display())?;
+        }
+        if let Some(ref cfg) = self.cfg {
+            write!(f, " under #[cfg({})]", cfg)?;
+        }
+        match self.outcome {
+            ParseOutcome::Taken => write!(f, ": taken"),
+            ParseOutcome::Opaque(ref reason) => write!(f, ": taken as opaque ({})", reason),
+            ParseOutcome::Skipped(ref reason) => write!(f, ": skipped ({})", reason),
+        }
+    }
+}
+
+/// The kinds of rust items the parser takes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ItemKind {
+    Const,
+    Static,
+    Struct,
+    Union,
+    Enum,
+    Type,
+    Fn,
+}
+
+impl fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ItemKind::Const => "const",
+            ItemKind::Static => "static",
+            ItemKind::Struct => "struct",
+            ItemKind::Union => "union",
+            ItemKind::Enum => "enum",
+            ItemKind::Type => "type",
+            ItemKind::Fn => "fn",
+        })
+    }
+}
+
+/// The spelling of `item_type` in `[export] item_types`.
+pub fn item_type_name(item_type: &ItemType) -> &'static str {
+    match *item_type {
+        ItemType::Constants => "constants",
+        ItemType::Globals => "globals",
+        ItemType::Enums => "enums",
+        ItemType::Structs => "structs",
+        ItemType::Unions => "unions",
+        ItemType::Typedefs => "typedefs",
+        ItemType::OpaqueItems => "opaque",
+        ItemType::Functions => "functions",
+    }
+}
+
+/// What first made an item part of the bindings.
+#[derive(Debug, Clone)]
+pub enum Referrer {
+    Function(Path),
+    Static(Path),
+    Constant(Path),
+    /// The item is listed in `[export] include`.
+    Include,
+    Item(Path),
+}
+
+impl fmt::Display for Referrer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Referrer::Function(ref path) => write!(f, "fn `{}`", path),
+            Referrer::Static(ref path) => write!(f, "static `{}`", path),
+            Referrer::Constant(ref path) => write!(f, "const `{}`", path),
+            Referrer::Include => write!(f, "`[export] include`"),
+            Referrer::Item(ref path) => write!(f, "`{}`", path),
+        }
+    }
+}
+
+/// The parse notes and dependency walk of a set of bindings, used to explain
+/// why an item is or isn't part of them.
+#[derive(Debug, Clone, Default)]
+pub struct Explainer {
+    notes: Vec<ParseNote>,
+    paths: ItemPaths,
+    referrers: HashMap<Path, Referrer>,
+    /// What the items that weren't parsed, but synthesized from other items,
+    /// were synthesized for.
+    origins: HashMap<Path, String>,
+    include: Filter,
+    exclude: Filter,
+}
+
+impl Explainer {
+    pub fn new(
+        notes: Vec<ParseNote>,
+        referrers: HashMap<Path, Referrer>,
+        origins: HashMap<Path, String>,
+        include: Filter,
+        exclude: Filter,
+    ) -> Explainer {
+        Explainer {
+            paths: ItemPaths::new(&notes),
+            notes,
+            referrers,
+            origins,
+            include,
+            exclude,
+        }
+    }
+
+    /// The notes of the rust items named `name`, in the order they were parsed.
+    pub fn notes(&self, name: &str) -> Vec<&ParseNote> {
+        self.notes.iter().filter(|x| x.name == name).collect()
+    }
+
+    /// What the item at `path` was synthesized for, if it wasn't parsed.
+    pub fn origin(&self, path: &Path) -> Option<&str> {
+        self.origins.get(path).map(|x| x.as_str())
+    }
+
+    /// The `[export] include` rule matching the items named `name`, if any.
+    pub fn included_by(&self, name: &str) -> Option<&Rule> {
+        self.include.find(name, self.paths.get(name))
+    }
+
+    /// The `[export] exclude` rule matching the items named `name`, if any.
+    pub fn excluded_by(&self, name: &str) -> Option<&Rule> {
+        self.exclude.find(name, self.paths.get(name))
+    }
+
+    /// The chain of references from an exported function, global, constant or
+    /// `[export] include` to the item at `path`, if it was pulled in by one.
+    pub fn chain(&self, path: &Path) -> Option<String> {
+        let mut chain = format!("`{}`", path);
+        let mut path = path;
+        // Each item has a single referrer which was added before it, so this
+        // can't loop, but don't rely on that.
+        for _ in 0..=self.referrers.len() {
+            match self.referrers.get(path)? {
+                &Referrer::Item(ref referrer) => {
+                    chain.push_str(&format!(" <- `{}`", referrer));
+                    path = referrer;
+                }
+                referrer => {
+                    chain.push_str(&format!(" <- {}", referrer));
+                    return Some(chain);
+                }
+            }
+        }
+        None
+    }
+}
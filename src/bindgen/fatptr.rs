@@ -0,0 +1,237 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::collections::HashMap;
+use std::mem;
+
+use bindgen::config::Config;
+use bindgen::ir::{
+    AnnotationSet, Documentation, FatPointee, GenericParams, GenericPath, Path, PrimitiveType,
+    Struct, Type,
+};
+use bindgen::rename::{IdentifierType, RenameRule};
+
+/// The structs that pointers to slices, `str` and trait objects are lowered
+/// to, keyed by the name they were given.
+#[derive(Default, Clone, Debug)]
+pub struct FatPointers {
+    lowered: HashMap<Path, (FatPointee, bool)>,
+    structs: Vec<Struct>,
+}
+
+impl FatPointers {
+    /// Returns the type to use in place of a fat pointer to `pointee`,
+    /// synthesizing the struct backing it if needed.
+    pub fn lower(
+        &mut self,
+        pointee: &FatPointee,
+        is_mut: bool,
+        generic_params: &GenericParams,
+        config: &Config,
+    ) -> Type {
+        let names = &config.fat_pointer;
+        let (template, name) = match pointee {
+            &FatPointee::Slice(ref ty) if is_mut => (&names.slice_mut, type_name(ty)),
+            &FatPointee::Slice(ref ty) => (&names.slice, type_name(ty)),
+            &FatPointee::Str if is_mut => (&names.str_mut, String::new()),
+            &FatPointee::Str => (&names.str, String::new()),
+            &FatPointee::TraitObject(ref path) if is_mut => {
+                (&names.trait_object_mut, path.name().to_owned())
+            }
+            &FatPointee::TraitObject(ref path) => (&names.trait_object, path.name().to_owned()),
+        };
+        let path = Path::new(template.replace("{}", &name));
+
+        // A slice of a generic parameter needs to be generic over it as well.
+        let params: Vec<Path> = match pointee.elem() {
+            Some(ty) => generic_params
+                .iter()
+                .filter(|param| uses_path(ty, param))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+
+        match self.lowered.get(&path) {
+            Some(&(ref existing, existing_mut)) => {
+                if existing != pointee || existing_mut != is_mut {
+                    error!(
+                        "Different fat pointers are lowered to the same struct {}. Consider \
+                         changing the names in the [fat_pointer] section of the config.",
+                        path
+                    );
+                }
+            }
+            None => {
+                self.lowered.insert(path.clone(), (pointee.clone(), is_mut));
+                self.structs.push(lowered_struct(
+                    path.clone(),
+                    pointee,
+                    is_mut,
+                    GenericParams(params.clone()),
+                ));
+            }
+        }
+
+        Type::Path(GenericPath::new(
+            path,
+            params
+                .into_iter()
+                .map(|param| Type::Path(GenericPath::new(param, vec![])))
+                .collect(),
+        ))
+    }
+
+    pub fn drain_structs(&mut self) -> Vec<Struct> {
+        mem::replace(&mut self.structs, Vec::new())
+    }
+
+    /// Describes the fat pointers each struct was synthesized for, by its
+    /// name.
+    pub fn origins(&self) -> Vec<(Path, String)> {
+        self.lowered
+            .iter()
+            .map(|(path, &(ref pointee, is_mut))| {
+                let pointee = match *pointee {
+                    FatPointee::Slice(..) => "slices".to_owned(),
+                    FatPointee::Str => "`str`".to_owned(),
+                    FatPointee::TraitObject(ref trait_path) => format!("`dyn {}`", trait_path),
+                };
+                let mutability = if is_mut { "mutable" } else { "const" };
+                (
+                    path.clone(),
+                    format!(
+                        "Generated for {} pointers to {}, see `[fat_pointer]`.",
+                        mutability, pointee
+                    ),
+                )
+            })
+            .collect()
+    }
+}
+
+fn lowered_struct(
+    path: Path,
+    pointee: &FatPointee,
+    is_mut: bool,
+    generic_params: GenericParams,
+) -> Struct {
+    let ptr = |ty: Type| {
+        if is_mut {
+            Type::Ptr(Box::new(ty), true)
+        } else {
+            Type::ConstPtr(Box::new(ty), true)
+        }
+    };
+    let len = Type::Primitive(PrimitiveType::USize);
+
+    let (fields, mut doc) = match pointee {
+        &FatPointee::Slice(ref ty) => (
+            vec![
+                ("ptr".to_owned(), ptr((**ty).clone())),
+                ("len".to_owned(), len),
+            ],
+            vec![
+                "A Rust slice, made of a pointer to its first element and the number of".to_owned(),
+                "elements.".to_owned(),
+            ],
+        ),
+        &FatPointee::Str => (
+            vec![
+                ("ptr".to_owned(), ptr(Type::Primitive(PrimitiveType::Char))),
+                ("len".to_owned(), len),
+            ],
+            vec![
+                "A Rust string slice, made of a pointer to its first byte and the number of"
+                    .to_owned(),
+                "bytes. The string is UTF-8 and is not nul-terminated.".to_owned(),
+            ],
+        ),
+        &FatPointee::TraitObject(ref trait_path) => (
+            vec![
+                ("data".to_owned(), ptr(Type::Primitive(PrimitiveType::Void))),
+                (
+                    "vtable".to_owned(),
+                    Type::ConstPtr(Box::new(Type::Primitive(PrimitiveType::Void)), true),
+                ),
+            ],
+            vec![
+                format!(
+                    "A Rust `dyn {}` trait object, made of a pointer to its data and a",
+                    trait_path
+                ),
+                "pointer to its vtable.".to_owned(),
+            ],
+        ),
+    };
+    doc.push(String::new());
+    doc.push("Rust doesn't guarantee the layout of fat pointers, so this struct is not".to_owned());
+    doc.push("ABI-stable and may not match what a Rust compiler actually passes.".to_owned());
+
+    Struct::new(
+        path,
+        generic_params,
+        fields
+            .into_iter()
+            .map(|(name, ty)| (name, ty, Documentation::none()))
+            .collect(),
+        false,
+        false,
+        false,
+        false,
+        None,
+        AnnotationSet::new(),
+        Documentation { doc_comment: doc },
+    )
+}
+
+/// A name for `ty` that can be used as part of an identifier.
+fn type_name(ty: &Type) -> String {
+    match ty {
+        &Type::ConstPtr(ref ty, _) | &Type::Ref(ref ty) => format!("ConstPtr{}", type_name(ty)),
+        &Type::Ptr(ref ty, _) | &Type::MutRef(ref ty) => format!("Ptr{}", type_name(ty)),
+        &Type::Path(ref generic) => {
+            let mut name = generic.export_name().to_owned();
+            for generic in generic.generics() {
+                name.push_str(&type_name(generic));
+            }
+            name
+        }
+        &Type::Primitive(ref primitive) => RenameRule::PascalCase
+            .apply_to_snake_case(primitive.to_repr_rust(), IdentifierType::StructMember),
+        &Type::Array(ref ty, ref len) => format!("{}Array{}", type_name(ty), len.as_str()),
+        &Type::FuncPtr(ref ret, ref args) => {
+            let mut name = "Fn".to_owned();
+            for &(_, ref arg) in args {
+                name.push_str(&type_name(arg));
+            }
+            name.push_str("Ret");
+            name.push_str(&type_name(ret));
+            name
+        }
+        // Fat pointers are lowered from the inside out.
+        &Type::ConstFatPtr(..) | &Type::FatPtr(..) => unreachable!(),
+    }
+}
+
+/// Whether `path` is referred to anywhere in `ty`.
+fn uses_path(ty: &Type, path: &Path) -> bool {
+    match ty {
+        &Type::ConstPtr(ref ty, _)
+        | &Type::Ptr(ref ty, _)
+        | &Type::Ref(ref ty)
+        | &Type::MutRef(ref ty)
+        | &Type::Array(ref ty, _) => uses_path(ty, path),
+        &Type::Path(ref generic) => {
+            generic.path() == path || generic.generics().iter().any(|x| uses_path(x, path))
+        }
+        &Type::Primitive(_) => false,
+        &Type::FuncPtr(ref ret, ref args) => {
+            uses_path(ret, path) || args.iter().any(|&(_, ref arg)| uses_path(arg, path))
+        }
+        &Type::ConstFatPtr(ref pointee) | &Type::FatPtr(ref pointee) => {
+            pointee.elem().map_or(false, |ty| uses_path(ty, path))
+        }
+    }
+}
@@ -0,0 +1,211 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::collections::HashMap;
+use std::fmt;
+
+use bindgen::error::Error;
+use bindgen::explain::ParseNote;
+use bindgen::regex::Regex;
+
+/// A rule of an `include` or `exclude` list.
+///
+/// A rule is either a name, a glob like `internal_*`, or a regex between
+/// slashes like `/^internal_/`. Rules containing `::` match module-qualified
+/// paths like `crate::ffi::private`, and everything in the module, rather
+/// than names.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    source: String,
+    matcher: Matcher,
+    is_path: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Exact(String),
+    Pattern(Regex),
+}
+
+impl Rule {
+    pub fn new(source: &str) -> Result<Rule, String> {
+        let matcher = if source.len() > 1 && source.starts_with('/') && source.ends_with('/') {
+            Matcher::Pattern(Regex::new(&source[1..source.len() - 1])?)
+        } else if source.contains(&['*', '?', '['][..]) {
+            Matcher::Pattern(Regex::new(&glob_to_regex(source))?)
+        } else {
+            Matcher::Exact(source.to_owned())
+        };
+
+        Ok(Rule {
+            source: source.to_owned(),
+            matcher,
+            is_path: source.contains("::"),
+        })
+    }
+
+    /// The name the rule matches, if it's neither a pattern nor a path.
+    pub fn name(&self) -> Option<&str> {
+        match self.matcher {
+            Matcher::Exact(ref name) if !self.is_path => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn is_path(&self) -> bool {
+        self.is_path
+    }
+
+    /// Whether the rule matches an item called `name`, which can also be
+    /// referred to by `paths`.
+    pub fn matches(&self, name: &str, paths: &[String]) -> bool {
+        if !self.is_path {
+            return self.matches_str(name);
+        }
+
+        paths.iter().any(|path| {
+            // A module matches everything in it.
+            path.match_indices("::")
+                .map(|(index, _)| &path[..index])
+                .chain(Some(&path[..]))
+                .any(|x| self.matches_str(x))
+        })
+    }
+
+    fn matches_str(&self, s: &str) -> bool {
+        match self.matcher {
+            Matcher::Exact(ref x) => x == s,
+            Matcher::Pattern(ref x) => x.is_match(s),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Translates a glob to an anchored regex. `*` and `?` don't match the `::`
+/// between path segments, but `**` does.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^:]*"),
+            '?' => regex.push_str("[^:]"),
+            '[' => {
+                regex.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    regex.push('^');
+                }
+                for c in &mut chars {
+                    regex.push(c);
+                    if c == ']' {
+                        break;
+                    }
+                }
+            }
+            c if c.is_alphanumeric() || c == '_' => regex.push(c),
+            c => regex.push_str(&::regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// The rules of an `include` or `exclude` list.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    rules: Vec<Rule>,
+}
+
+impl Filter {
+    /// Loads the rules of `list`, which is named like `[export] exclude` in
+    /// errors.
+    pub fn new(list: &str, rules: &[String]) -> Result<Filter, Error> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                Rule::new(rule).map_err(|error| Error::InvalidFilterRule {
+                    list: list.to_owned(),
+                    rule: rule.clone(),
+                    error,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Filter { rules })
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// The first rule matching an item called `name`, which can also be
+    /// referred to by `paths`.
+    pub fn find(&self, name: &str, paths: &[String]) -> Option<&Rule> {
+        self.rules.iter().find(|x| x.matches(name, paths))
+    }
+
+    /// The first path rule matching a module, which can be referred to by
+    /// `paths`.
+    pub fn find_path(&self, paths: &[String]) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .find(|x| x.is_path() && x.matches("", paths))
+    }
+}
+
+/// The paths a module or an item can be referred to by in rules, which start
+/// with both `crate` and the crate name for the binding crate.
+pub fn qualified_paths(crate_name: &str, binding_crate: bool, segments: &[String]) -> Vec<String> {
+    let crate_name = crate_name.replace('-', "_");
+    let mut roots = vec![crate_name.as_str()];
+    if binding_crate {
+        roots.insert(0, "crate");
+    }
+
+    roots
+        .into_iter()
+        .map(|root| {
+            let mut path = root.to_owned();
+            for segment in segments {
+                path.push_str("::");
+                path.push_str(segment);
+            }
+            path
+        })
+        .collect()
+}
+
+/// The module-qualified paths of the parsed items, by name.
+#[derive(Debug, Clone, Default)]
+pub struct ItemPaths {
+    paths: HashMap<String, Vec<String>>,
+}
+
+impl ItemPaths {
+    pub fn new(notes: &[ParseNote]) -> ItemPaths {
+        let mut paths: HashMap<String, Vec<String>> = HashMap::new();
+        for note in notes {
+            let item_paths = paths.entry(note.name.clone()).or_default();
+            for path in note.paths() {
+                if !item_paths.contains(&path) {
+                    item_paths.push(path);
+                }
+            }
+        }
+        ItemPaths { paths }
+    }
+
+    pub fn get(&self, name: &str) -> &[String] {
+        self.paths.get(name).map(|x| &x[..]).unwrap_or(&[])
+    }
+}
@@ -0,0 +1,171 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::io::{self, Write};
+
+use serde_json;
+
+/// The items written to a set of bindings, and which items each of them
+/// refers to.
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyGraph {
+    pub nodes: Vec<DependencyNode>,
+    pub edges: Vec<DependencyEdge>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyNode {
+    /// The kind and rust name of the item, like `struct Foo`, which is unique.
+    pub id: String,
+    pub kind: String,
+    pub name: String,
+    /// The name of the item in the bindings, after renaming.
+    pub export_name: String,
+    /// The `#[cfg]` the item is under, if any.
+    pub cfg: Option<String>,
+    pub opaque: bool,
+    /// Whether the item is listed in `[export] include`.
+    pub included: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DependencyEdge {
+    pub from: String,
+    pub to: String,
+}
+
+impl DependencyGraph {
+    pub fn write_json<W: Write>(&self, out: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(out, self)?;
+        Ok(())
+    }
+
+    /// Writes the graph in Graphviz's DOT format. Functions, statics and
+    /// constants are boxes, opaque items are dashed.
+    pub fn write_dot<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "digraph dependencies {{")?;
+        for node in &self.nodes {
+            let mut label = node.id.clone();
+            if node.export_name != node.name {
+                label.push_str(&format!("\n(as {})", node.export_name));
+            }
+            if let Some(ref cfg) = node.cfg {
+                label.push_str(&format!("\n#[cfg({})]", cfg));
+            }
+
+            let mut attributes = vec![format!("label={}", dot_string(&label))];
+            match node.kind.as_str() {
+                "fn" | "static" | "const" => attributes.push("shape=box".to_owned()),
+                _ => {}
+            }
+            if node.opaque {
+                attributes.push("style=dashed".to_owned());
+            }
+            if node.included {
+                attributes.push("peripheries=2".to_owned());
+            }
+            writeln!(
+                out,
+                "  {} [{}];",
+                dot_string(&node.id),
+                attributes.join(", ")
+            )?;
+        }
+        for edge in &self.edges {
+            writeln!(
+                out,
+                "  {} -> {};",
+                dot_string(&edge.from),
+                dot_string(&edge.to)
+            )?;
+        }
+        writeln!(out, "}}")
+    }
+}
+
+fn dot_string(s: &str) -> String {
+    format!(
+        "\"{}\"",
+        s.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+#[test]
+fn dependency_graph() {
+    use bindgen::{Builder, Language};
+
+    let bindings = Builder::new()
+        .with_language(Language::C)
+        .with_source_str(
+            "demo",
+            "",
+            r#"
+            #[cfg(unix)]
+            #[repr(C)]
+            pub struct Handle { fd: i32 }
+            #[cfg(windows)]
+            #[repr(C)]
+            pub struct Handle { handle: *mut u8 }
+
+            pub struct Opaque { x: i32 }
+
+            #[repr(C)]
+            pub struct Pair { a: *const Opaque, b: *const Opaque }
+
+            #[no_mangle]
+            pub extern "C" fn root(h: Handle, p: Pair, q: *const Pair) {}
+            "#,
+        )
+        .generate()
+        .unwrap();
+    let graph = bindings.dependency_graph();
+
+    let node = |id: &str| graph.nodes.iter().find(|x| x.id == id).unwrap();
+    assert_eq!(graph.nodes.len(), 4);
+    assert_eq!(
+        node("struct Handle").cfg,
+        Some("any(unix, windows)".to_owned())
+    );
+    assert!(node("opaque Opaque").opaque);
+    assert!(!node("struct Pair").opaque);
+    assert_eq!(node("fn root").kind, "fn");
+
+    // References are only listed once, even if there are several of them.
+    let edge = |from: &str, to: &str| DependencyEdge {
+        from: from.to_owned(),
+        to: to.to_owned(),
+    };
+    assert_eq!(graph.edges.len(), 3);
+    assert!(graph.edges.contains(&edge("fn root", "struct Handle")));
+    assert!(graph.edges.contains(&edge("fn root", "struct Pair")));
+    assert!(graph.edges.contains(&edge("struct Pair", "opaque Opaque")));
+
+    let mut dot = Vec::new();
+    graph.write_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.starts_with("digraph dependencies {\n"));
+    assert!(dot
+        .contains("  \"struct Handle\" [label=\"struct Handle\\n#[cfg(any(unix, windows))]\"];\n"));
+    assert!(dot.contains("  \"opaque Opaque\" [label=\"opaque Opaque\", style=dashed];\n"));
+    assert!(dot.contains("  \"fn root\" [label=\"fn root\", shape=box];\n"));
+    assert!(dot.contains("  \"struct Pair\" -> \"opaque Opaque\";\n"));
+    assert!(dot.ends_with("}\n"));
+
+    let mut json = Vec::new();
+    graph.write_json(&mut json).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json["nodes"].as_array().unwrap().len(), 4);
+    assert_eq!(json["edges"].as_array().unwrap().len(), 3);
+    let handle = json["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|x| x["id"] == "struct Handle")
+        .unwrap();
+    assert_eq!(handle["cfg"], "any(unix, windows)");
+    assert_eq!(handle["export_name"], "Handle");
+    assert_eq!(handle["opaque"], false);
+}
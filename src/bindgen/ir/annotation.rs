@@ -23,7 +23,7 @@ use bindgen::utilities::SynAttributeHelpers;
 //  * cbindgen:function-postfix=WR_DESTRUCTOR_SAFE
 
 /// A value specified by an annotation.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AnnotationValue {
     List(Vec<String>),
     Atom(Option<String>),
@@ -31,10 +31,16 @@ pub enum AnnotationValue {
 }
 
 /// A set of annotations specified by a document comment.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnnotationSet {
     annotations: HashMap<String, AnnotationValue>,
     pub must_use: bool,
+    /// The note of `#[deprecated]`, which is empty if there's none.
+    pub deprecated: Option<String>,
+    /// Raw text to write before the item, added by an item visitor.
+    pub text_before: Vec<String>,
+    /// Raw text to write after the item, added by an item visitor.
+    pub text_after: Vec<String>,
 }
 
 impl AnnotationSet {
@@ -42,21 +48,30 @@ impl AnnotationSet {
         AnnotationSet {
             annotations: HashMap::new(),
             must_use: false,
+            deprecated: None,
+            text_before: Vec::new(),
+            text_after: Vec::new(),
         }
     }
 
     pub fn is_empty(&self) -> bool {
-        self.annotations.is_empty() && !self.must_use
+        self.annotations.is_empty()
+            && !self.must_use
+            && self.deprecated.is_none()
+            && self.text_before.is_empty()
+            && self.text_after.is_empty()
     }
 
     pub fn load(attrs: &[syn::Attribute]) -> Result<AnnotationSet, String> {
         let lines: Vec<String> = attrs
             .get_comment_lines()
             .into_iter()
-            .filter(|x| !x.is_empty() && x.starts_with("cbindgen:"))
+            .map(|x| x.trim_start().to_owned())
+            .filter(|x| x.starts_with("cbindgen:"))
             .collect();
 
         let must_use = attrs.has_attr_word("must_use");
+        let deprecated = attrs.get_deprecated_note();
 
         let mut annotations = HashMap::new();
 
@@ -110,9 +125,21 @@ impl AnnotationSet {
         Ok(AnnotationSet {
             annotations,
             must_use,
+            deprecated,
+            text_before: Vec::new(),
+            text_after: Vec::new(),
         })
     }
 
+    #[allow(unused)]
+    pub fn get(&self, name: &str) -> Option<&AnnotationValue> {
+        self.annotations.get(name)
+    }
+    #[allow(unused)]
+    pub fn set(&mut self, name: &str, value: AnnotationValue) {
+        self.annotations.insert(name.to_owned(), value);
+    }
+
     pub fn list(&self, name: &str) -> Option<Vec<String>> {
         match self.annotations.get(name) {
             Some(&AnnotationValue::List(ref x)) => Some(x.clone()),
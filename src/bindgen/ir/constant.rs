@@ -9,23 +9,24 @@ use std::mem;
 
 use syn;
 
-use bindgen::config::{Config, Language};
+use bindgen::config::{Config, ConstantStyle, ItemType, Language};
 use bindgen::declarationtyperesolver::DeclarationTypeResolver;
 use bindgen::dependencies::Dependencies;
+use bindgen::ir::discriminant::binary_op;
 use bindgen::ir::{
     AnnotationSet, Cfg, ConditionWrite, Documentation, GenericParams, Item, ItemContainer, Path,
-    Struct, ToCondition, Type,
+    PrimitiveType, Struct, ToCondition, Type,
 };
 use bindgen::library::Library;
 use bindgen::writer::{Source, SourceWriter};
 use bindgen::Bindings;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Literal {
     Expr(String),
     BinOp {
         left: Box<Literal>,
-        op: &'static str,
+        op: String,
         right: Box<Literal>,
     },
     Struct {
@@ -119,6 +120,19 @@ impl Literal {
         }
     }
 
+    /// The value of an integer literal, if it has one that fits an `i64`.
+    pub(crate) fn integer_value(&self) -> Option<i64> {
+        match *self {
+            Literal::Expr(ref value) => value.parse().ok(),
+            Literal::BinOp {
+                ref left,
+                ref op,
+                ref right,
+            } => binary_op(left.integer_value()?, op, right.integer_value()?).ok(),
+            Literal::Struct { .. } => None,
+        }
+    }
+
     pub fn load(expr: &syn::Expr) -> Result<Literal, String> {
         match *expr {
             syn::Expr::Binary(ref bin_expr) => {
@@ -136,7 +150,7 @@ impl Literal {
                 };
                 Ok(Literal::BinOp {
                     left: Box::new(l),
-                    op,
+                    op: op.to_owned(),
                     right: Box::new(r),
                 })
             }
@@ -211,7 +225,7 @@ impl Literal {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Constant {
     pub path: Path,
     pub export_name: String,
@@ -326,7 +340,9 @@ impl Item for Constant {
 
     fn rename_for_config(&mut self, config: &Config) {
         if self.associated_to.is_none() {
-            config.export.rename(&mut self.export_name);
+            config
+                .export
+                .rename_item(Some(&ItemType::Constants), &mut self.export_name);
         }
         self.value.rename_for_config(config);
         self.ty.rename_for_config(config, &GenericParams::default()); // FIXME: should probably propagate something here
@@ -337,7 +353,94 @@ impl Item for Constant {
     }
 }
 
+/// Whether a constant of type `ty` can be an enumerator. C requires them to
+/// fit in an `int`.
+fn can_be_enumerator(ty: &Type, language: &Language) -> bool {
+    let primitive = match *ty {
+        Type::Primitive(ref x) => x,
+        _ => return false,
+    };
+    match *primitive {
+        PrimitiveType::Char
+        | PrimitiveType::SChar
+        | PrimitiveType::UChar
+        | PrimitiveType::Short
+        | PrimitiveType::UShort
+        | PrimitiveType::Int
+        | PrimitiveType::Int8
+        | PrimitiveType::Int16
+        | PrimitiveType::Int32
+        | PrimitiveType::UInt8
+        | PrimitiveType::UInt16 => true,
+        PrimitiveType::WChar
+        | PrimitiveType::Long
+        | PrimitiveType::LongLong
+        | PrimitiveType::UInt
+        | PrimitiveType::ULong
+        | PrimitiveType::ULongLong
+        | PrimitiveType::USize
+        | PrimitiveType::UInt32
+        | PrimitiveType::UInt64
+        | PrimitiveType::ISize
+        | PrimitiveType::Int64
+        | PrimitiveType::SizeT
+        | PrimitiveType::SSizeT
+        | PrimitiveType::PtrDiffT => *language == Language::Cxx,
+        PrimitiveType::Void
+        | PrimitiveType::Bool
+        | PrimitiveType::Float
+        | PrimitiveType::Double
+        | PrimitiveType::VaList => false,
+    }
+}
+
 impl Constant {
+    /// The style set for the constant, if it can be declared in it.
+    fn set_style(&self, config: &Config) -> Option<ConstantStyle> {
+        let style = config.constant.style(&self.annotations)?;
+        let applies = match style {
+            ConstantStyle::Define | ConstantStyle::StaticConst => true,
+            // Struct literals aren't constant expressions.
+            ConstantStyle::Constexpr | ConstantStyle::InlineConstexpr => {
+                config.language == Language::Cxx && self.ty.is_primitive_or_ptr_primitive()
+            }
+            ConstantStyle::Enum => can_be_enumerator(&self.ty, &config.language),
+        };
+        if applies {
+            Some(style)
+        } else {
+            None
+        }
+    }
+
+    /// The style the constant is declared in.
+    pub fn style(&self, config: &Config) -> ConstantStyle {
+        self.set_style(config)
+            .unwrap_or_else(|| config.constant.default_style(&config.language))
+    }
+
+    /// Whether the constant is written in the body of the struct it's
+    /// associated to, with `associated_constants_in_body`. Macros can't be.
+    pub fn is_in_body(&self, config: &Config, associated_to_struct: Option<&Struct>) -> bool {
+        associated_to_struct.map_or(false, |s| !s.is_transparent)
+            && config.language == Language::Cxx
+            && config.structure.associated_constants_in_body
+            && self.style(config) != ConstantStyle::Define
+    }
+
+    /// Whether the constant is defined in the body of the struct it's
+    /// associated to, rather than only declared there.
+    pub fn is_defined_in_body(
+        &self,
+        config: &Config,
+        associated_to_struct: Option<&Struct>,
+    ) -> bool {
+        self.is_in_body(config, associated_to_struct)
+            && self.style(config) != ConstantStyle::StaticConst
+    }
+
+    /// Writes the constant in the body of the struct it's associated to. Only
+    /// `static const` constants need a definition after it.
     pub fn write_declaration<F: Write>(
         &self,
         config: &Config,
@@ -345,18 +448,56 @@ impl Constant {
         associated_to_struct: &Struct,
     ) {
         debug_assert!(self.associated_to.is_some());
-        debug_assert!(config.language == Language::Cxx);
-        debug_assert!(!associated_to_struct.is_transparent);
-        debug_assert!(config.structure.associated_constants_in_body);
-        debug_assert!(config.constant.allow_static_const);
+        debug_assert!(self.is_in_body(config, Some(associated_to_struct)));
 
-        if let Type::ConstPtr(..) = self.ty {
-            out.write("static ");
-        } else {
-            out.write("static const ");
+        let style = self.style(config);
+        let value = match self.value(out.bindings()) {
+            Some(value) => value,
+            None => return,
+        };
+
+        let condition = (&self.cfg).to_condition(config);
+        condition.write_before(config, out);
+        match style {
+            ConstantStyle::StaticConst => {
+                if let Type::ConstPtr(..) = self.ty {
+                    out.write("static ");
+                } else {
+                    out.write("static const ");
+                }
+                self.ty.write(config, out);
+                write!(out, " {};", self.export_name())
+            }
+            ConstantStyle::Constexpr | ConstantStyle::InlineConstexpr => {
+                // Static constexpr members are inline already.
+                out.write("static constexpr ");
+                self.ty.write(config, out);
+                write!(out, " {} = {};", self.export_name(), value)
+            }
+            ConstantStyle::Enum => {
+                out.write("enum : ");
+                self.ty.write(config, out);
+                write!(out, " {{ {} = {} }};", self.export_name(), value)
+            }
+            ConstantStyle::Define => unreachable!(),
+        }
+        condition.write_after(config, out);
+    }
+
+    /// The value to write, which is that of the field of a transparent
+    /// struct, or `None` if it refers to something that isn't written.
+    fn value<'a>(&'a self, bindings: &Bindings) -> Option<&'a Literal> {
+        if !self.value.is_valid(bindings) {
+            return None;
         }
-        self.ty.write(config, out);
-        write!(out, " {};", self.export_name())
+        Some(match self.value {
+            Literal::Struct {
+                ref fields,
+                ref path,
+                ..
+            } if bindings.struct_is_transparent(path) => &fields[0].1,
+            _ => &self.value,
+        })
     }
 
     pub fn write<F: Write>(
@@ -371,17 +512,16 @@ impl Constant {
             }
         }
 
-        if !self.value.is_valid(out.bindings()) {
+        let value = match self.value(out.bindings()) {
+            Some(value) => value,
+            None => return,
+        };
+
+        if self.is_defined_in_body(config, associated_to_struct) {
             return;
         }
-
-        let associated_to_transparent = associated_to_struct.map_or(false, |s| s.is_transparent);
-
-        let in_body = associated_to_struct.is_some()
-            && config.language == Language::Cxx
-            && config.structure.associated_constants_in_body
-            && config.constant.allow_static_const
-            && !associated_to_transparent;
+        let style = self.style(config);
+        let in_body = self.is_in_body(config, associated_to_struct);
 
         let condition = (&self.cfg).to_condition(config);
         condition.write_before(config, out);
@@ -407,26 +547,44 @@ impl Constant {
             Cow::Owned(format!("{}_{}", associated_name, self.export_name()))
         };
 
-        let value = match self.value {
-            Literal::Struct {
-                ref fields,
-                ref path,
-                ..
-            } if out.bindings().struct_is_transparent(path) => &fields[0].1,
-            _ => &self.value,
-        };
-
-        if config.constant.allow_static_const && config.language == Language::Cxx {
-            out.write(if in_body { "inline " } else { "static " });
-            if let Type::ConstPtr(..) = self.ty {
-                // Nothing.
-            } else {
-                out.write("const ");
+        match style {
+            ConstantStyle::StaticConst => {
+                out.write(if in_body { "inline " } else { "static " });
+                if let Type::ConstPtr(..) = self.ty {
+                    // Nothing.
+                } else {
+                    out.write("const ");
+                }
+                self.ty.write(config, out);
+                write!(out, " {} = {};", name, value)
             }
-            self.ty.write(config, out);
-            write!(out, " {} = {};", name, value)
-        } else {
-            write!(out, "#define {} {}", name, value)
+            ConstantStyle::Constexpr | ConstantStyle::InlineConstexpr => {
+                out.write(if style == ConstantStyle::Constexpr {
+                    "static constexpr "
+                } else {
+                    "inline constexpr "
+                });
+                self.ty.write(config, out);
+                write!(out, " {} = {};", name, value)
+            }
+            ConstantStyle::Enum => {
+                out.write("enum ");
+                if config.language == Language::Cxx {
+                    out.write(": ");
+                    self.ty.write(config, out);
+                    out.write(" ");
+                }
+                write!(out, "{{ {} = {} }};", name, value)
+            }
+            ConstantStyle::Define => match self.ty {
+                // An untyped macro, unless the style is set.
+                Type::Primitive(..) if self.set_style(config).is_some() => {
+                    write!(out, "#define {} ((", name);
+                    self.ty.write(config, out);
+                    write!(out, "){})", value)
+                }
+                _ => write!(out, "#define {} {}", name, value),
+            },
         }
         condition.write_after(config, out);
     }
@@ -0,0 +1,251 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::fmt;
+
+use syn;
+
+use bindgen::config::{Config, ItemType};
+use bindgen::ir::{PrimitiveType, ReprType};
+
+/// The integer constant expression of an enum discriminant, which can refer to
+/// constants, e.g. `1 << 3`, `BASE + 1` or `b'x' as isize`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Discriminant {
+    Value(i64),
+    /// A constant, by its name and the name it's exported as.
+    Constant(String, String),
+    /// A unary operation, by its C operator.
+    Unary(String, Box<Discriminant>),
+    /// A binary operation, by its C operator.
+    Binary(Box<Discriminant>, String, Box<Discriminant>),
+    Cast(Box<Discriminant>, PrimitiveType),
+}
+
+impl Discriminant {
+    pub fn load(expr: &syn::Expr) -> Result<Discriminant, String> {
+        match *expr {
+            syn::Expr::Lit(ref lit) => match lit.lit {
+                syn::Lit::Int(ref lit) if lit.value() <= ::std::i64::MAX as u64 => {
+                    Ok(Discriminant::Value(lit.value() as i64))
+                }
+                syn::Lit::Byte(ref lit) => Ok(Discriminant::Value(i64::from(lit.value()))),
+                syn::Lit::Char(ref lit) => Ok(Discriminant::Value(i64::from(lit.value() as u32))),
+                syn::Lit::Bool(ref lit) => Ok(Discriminant::Value(lit.value as i64)),
+                _ => Err("Unsupported literal.".to_owned()),
+            },
+            syn::Expr::Paren(ref paren) => Discriminant::load(&paren.expr),
+            syn::Expr::Group(ref group) => Discriminant::load(&group.expr),
+            syn::Expr::Unary(ref unary) => {
+                let op = match unary.op {
+                    syn::UnOp::Neg(..) => "-",
+                    syn::UnOp::Not(..) => "~",
+                    syn::UnOp::Deref(..) => return Err("Unsupported dereference.".to_owned()),
+                };
+                Ok(Discriminant::Unary(
+                    op.to_owned(),
+                    Box::new(Discriminant::load(&unary.expr)?),
+                ))
+            }
+            syn::Expr::Binary(ref binary) => {
+                let op = match binary.op {
+                    syn::BinOp::Add(..) => "+",
+                    syn::BinOp::Sub(..) => "-",
+                    syn::BinOp::Mul(..) => "*",
+                    syn::BinOp::Div(..) => "/",
+                    syn::BinOp::Rem(..) => "%",
+                    syn::BinOp::Shl(..) => "<<",
+                    syn::BinOp::Shr(..) => ">>",
+                    syn::BinOp::BitAnd(..) => "&",
+                    syn::BinOp::BitOr(..) => "|",
+                    syn::BinOp::BitXor(..) => "^",
+                    _ => return Err(format!("Unsupported binary op {:?}.", binary.op)),
+                };
+                Ok(Discriminant::Binary(
+                    Box::new(Discriminant::load(&binary.left)?),
+                    op.to_owned(),
+                    Box::new(Discriminant::load(&binary.right)?),
+                ))
+            }
+            syn::Expr::Path(syn::ExprPath {
+                qself: None,
+                ref path,
+                ..
+            }) => {
+                // Only constants of the crate or its modules, not associated ones.
+                let is_module = |segment: &syn::PathSegment| {
+                    segment.ident == "crate" || segment.ident == "self" || segment.ident == "super"
+                };
+                let segments = &path.segments;
+                match segments.last() {
+                    Some(last) if segments.iter().take(segments.len() - 1).all(is_module) => {
+                        let name = last.value().ident.to_string();
+                        Ok(Discriminant::Constant(name.clone(), name))
+                    }
+                    _ => Err("Unsupported path.".to_owned()),
+                }
+            }
+            syn::Expr::Cast(ref cast) => {
+                let ty = match *cast.ty {
+                    syn::Type::Path(syn::TypePath {
+                        qself: None,
+                        ref path,
+                    }) if path.segments.len() == 1 => {
+                        PrimitiveType::maybe(&path.segments[0].ident.to_string())
+                    }
+                    _ => None,
+                };
+                match ty {
+                    Some(ty) => Ok(Discriminant::Cast(
+                        Box::new(Discriminant::load(&cast.expr)?),
+                        ty,
+                    )),
+                    None => Err("Unsupported cast.".to_owned()),
+                }
+            }
+            _ => Err("Unsupported expression.".to_owned()),
+        }
+    }
+
+    /// Evaluates the expression in the type `ty`, like rust does for the
+    /// discriminants of an enum with that repr, with `constant` giving the
+    /// values of the constants it refers to. It's `None` if one of them is
+    /// unknown.
+    pub fn evaluate<F>(&self, ty: Option<ReprType>, constant: &F) -> Result<Option<i64>, String>
+    where
+        F: Fn(&str) -> Option<i64>,
+    {
+        let value = match *self {
+            Discriminant::Value(value) => value,
+            Discriminant::Constant(ref name, _) => return Ok(constant(name)),
+            Discriminant::Unary(ref op, ref expr) => match expr.evaluate(ty, constant)? {
+                Some(value) if op == "-" => value
+                    .checked_neg()
+                    .ok_or_else(|| format!("-{} overflows.", value))?,
+                // Inverting the bits of an unsigned value doesn't make it
+                // negative.
+                Some(value) => ty.map_or(!value, |ty| ty.wrap(!value)),
+                None => return Ok(None),
+            },
+            Discriminant::Binary(ref left, ref op, ref right) => {
+                // The shift amount can have any type.
+                let right_ty = if op == "<<" || op == ">>" { None } else { ty };
+                match (
+                    left.evaluate(ty, constant)?,
+                    right.evaluate(right_ty, constant)?,
+                ) {
+                    (Some(left), Some(right)) => binary_op(left, op, right)?,
+                    _ => return Ok(None),
+                }
+            }
+            // The operand has a type of its own.
+            Discriminant::Cast(ref expr, ref ty) => {
+                return match expr.evaluate(None, constant)? {
+                    Some(value) => cast(value, ty).map(Some),
+                    None => Ok(None),
+                };
+            }
+        };
+        match ty {
+            Some(ty) if !ty.holds(value) => Err(format!("{} overflows {:?}.", value, ty)),
+            _ => Ok(Some(value)),
+        }
+    }
+
+    pub fn rename_for_config(&mut self, config: &Config) {
+        match *self {
+            Discriminant::Value(..) => {}
+            Discriminant::Constant(_, ref mut export_name) => {
+                config
+                    .export
+                    .rename_item(Some(&ItemType::Constants), export_name);
+            }
+            Discriminant::Unary(_, ref mut expr) | Discriminant::Cast(ref mut expr, _) => {
+                expr.rename_for_config(config);
+            }
+            Discriminant::Binary(ref mut left, _, ref mut right) => {
+                left.rename_for_config(config);
+                right.rename_for_config(config);
+            }
+        }
+    }
+
+    fn is_operation(&self) -> bool {
+        match *self {
+            Discriminant::Value(value) => value < 0,
+            Discriminant::Constant(..) => false,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Discriminant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Operands are parenthesized, as C doesn't have rust's precedence.
+        let operand = |f: &mut fmt::Formatter, expr: &Discriminant| {
+            if expr.is_operation() {
+                write!(f, "({})", expr)
+            } else {
+                write!(f, "{}", expr)
+            }
+        };
+        match *self {
+            Discriminant::Value(value) => write!(f, "{}", value),
+            Discriminant::Constant(_, ref export_name) => write!(f, "{}", export_name),
+            Discriminant::Unary(ref op, ref expr) => {
+                write!(f, "{}", op)?;
+                operand(f, expr)
+            }
+            Discriminant::Binary(ref left, ref op, ref right) => {
+                operand(f, left)?;
+                write!(f, " {} ", op)?;
+                operand(f, right)
+            }
+            Discriminant::Cast(ref expr, ref ty) => {
+                write!(f, "({})", ty.to_repr_c())?;
+                operand(f, expr)
+            }
+        }
+    }
+}
+
+/// Applies a binary operation of `Discriminant` or `Literal`, by its C
+/// operator, failing on overflow like rust does for constants.
+pub(crate) fn binary_op(left: i64, op: &str, right: i64) -> Result<i64, String> {
+    let value = match op {
+        "+" => left.checked_add(right),
+        "-" => left.checked_sub(right),
+        "*" => left.checked_mul(right),
+        "/" => left.checked_div(right),
+        "%" => left.checked_rem(right),
+        "<<" if right >= 0 && right < 64 => left.checked_shl(right as u32),
+        ">>" if right >= 0 && right < 64 => left.checked_shr(right as u32),
+        "&" => Some(left & right),
+        "|" => Some(left | right),
+        "^" => Some(left ^ right),
+        _ => None,
+    };
+    value.ok_or_else(|| format!("{} {} {} overflows.", left, op, right))
+}
+
+/// Casts a value to an integer type, truncating it like `as` does.
+fn cast(value: i64, ty: &PrimitiveType) -> Result<i64, String> {
+    Ok(match *ty {
+        PrimitiveType::UInt8 | PrimitiveType::UChar => i64::from(value as u8),
+        PrimitiveType::UInt16 => i64::from(value as u16),
+        PrimitiveType::UInt32 => i64::from(value as u32),
+        PrimitiveType::Int8 | PrimitiveType::SChar => i64::from(value as i8),
+        PrimitiveType::Int16 => i64::from(value as i16),
+        PrimitiveType::Int32 => i64::from(value as i32),
+        PrimitiveType::Int64 | PrimitiveType::ISize | PrimitiveType::PtrDiffT => value,
+        PrimitiveType::UInt64 | PrimitiveType::USize if value >= 0 => value,
+        _ => {
+            return Err(format!(
+                "{} as {} isn't supported.",
+                value,
+                ty.to_repr_rust()
+            ))
+        }
+    })
+}
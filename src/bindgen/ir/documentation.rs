@@ -7,22 +7,30 @@ use std::io::Write;
 use syn;
 
 use bindgen::config::{Config, DocumentationStyle, Language};
+use bindgen::doxygen;
 use bindgen::utilities::SynAttributeHelpers;
 use bindgen::writer::{Source, SourceWriter};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Documentation {
     pub doc_comment: Vec<String>,
 }
 
 impl Documentation {
     pub fn load(attrs: &[syn::Attribute]) -> Self {
-        let doc = attrs
+        let mut doc: Vec<String> = attrs
             .get_comment_lines()
             .into_iter()
-            .filter(|x| !x.is_empty() && !x.starts_with("cbindgen:"))
+            .filter(|x| !x.trim_start().starts_with("cbindgen:"))
             .collect();
 
+        // Keep the blank lines between paragraphs, but not around them.
+        while doc.last().map_or(false, |x| x.is_empty()) {
+            doc.pop();
+        }
+        let start = doc.iter().take_while(|x| x.is_empty()).count();
+        doc.drain(..start);
+
         Documentation { doc_comment: doc }
     }
 
@@ -63,7 +71,15 @@ impl Source for Documentation {
             _ => (),
         }
 
-        for line in &self.doc_comment {
+        // Doxygen reads both `/**` and `///` comments.
+        let lines = match style {
+            DocumentationStyle::Doxy | DocumentationStyle::Cxx => {
+                doxygen::translate(&self.doc_comment, out.bindings().doc_links())
+            }
+            _ => self.doc_comment.clone(),
+        };
+
+        for line in &lines {
             match style {
                 DocumentationStyle::C => out.write(""),
                 DocumentationStyle::Doxy => out.write(" *"),
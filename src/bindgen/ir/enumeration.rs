@@ -9,9 +9,10 @@ use syn;
 use bindgen::config::{Config, Language};
 use bindgen::declarationtyperesolver::DeclarationTypeResolver;
 use bindgen::dependencies::Dependencies;
+use bindgen::fatptr::FatPointers;
 use bindgen::ir::{
-    AnnotationSet, Cfg, ConditionWrite, Documentation, GenericParams, GenericPath, Item,
-    ItemContainer, Path, Repr, ReprStyle, ReprType, Struct, ToCondition, Type,
+    AnnotationSet, Cfg, ConditionWrite, Discriminant, Documentation, GenericParams, GenericPath,
+    Item, ItemContainer, Path, Repr, ReprStyle, ReprType, Struct, ToCondition, Type,
 };
 use bindgen::library::Library;
 use bindgen::mangle;
@@ -20,35 +21,15 @@ use bindgen::rename::{IdentifierType, RenameRule};
 use bindgen::utilities::find_first_some;
 use bindgen::writer::{ListType, Source, SourceWriter};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnumVariant {
     pub name: String,
     pub export_name: String,
-    pub discriminant: Option<i64>,
+    pub discriminant: Option<Discriminant>,
     pub body: Option<(String, Struct)>,
     pub documentation: Documentation,
 }
 
-fn value_from_expr(val: &syn::Expr) -> Option<i64> {
-    match *val {
-        syn::Expr::Lit(ref lit) => match lit.lit {
-            syn::Lit::Int(ref lit) if lit.value() <= ::std::i64::MAX as u64 => {
-                Some(lit.value() as i64)
-            }
-            _ => None,
-        },
-        syn::Expr::Unary(ref unary) => {
-            let v = value_from_expr(&unary.expr)?;
-            match unary.op {
-                syn::UnOp::Deref(..) => None,
-                syn::UnOp::Neg(..) => v.checked_mul(-1),
-                syn::UnOp::Not(..) => v.checked_neg(),
-            }
-        }
-        _ => None,
-    }
-}
-
 impl EnumVariant {
     pub fn load(
         is_tagged: bool,
@@ -57,9 +38,9 @@ impl EnumVariant {
         mod_cfg: Option<&Cfg>,
     ) -> Result<Self, String> {
         let discriminant = match variant.discriminant {
-            Some((_, ref expr)) => match value_from_expr(expr) {
-                Some(v) => Some(v),
-                None => return Err(format!("Unsupported discriminant {:?}.", expr)),
+            Some((_, ref expr)) => match Discriminant::load(expr) {
+                Ok(v) => Some(v),
+                Err(msg) => return Err(format!("Unsupported discriminant {:?}: {}", expr, msg)),
             },
             None => None,
         };
@@ -146,7 +127,7 @@ impl EnumVariant {
 
     pub fn new(
         name: String,
-        discriminant: Option<i64>,
+        discriminant: Option<Discriminant>,
         body: Option<(String, Struct)>,
         documentation: Documentation,
     ) -> Self {
@@ -175,7 +156,7 @@ impl EnumVariant {
     fn specialize(&self, generic_values: &[Type], mappings: &[(&Path, &Type)]) -> Self {
         Self::new(
             mangle::mangle_name(&self.name, generic_values),
-            self.discriminant,
+            self.discriminant.clone(),
             self.body
                 .as_ref()
                 .map(|&(ref name, ref ty)| (name.clone(), ty.specialize(generic_values, mappings))),
@@ -194,20 +175,32 @@ impl EnumVariant {
             ty.mangle_paths(monomorphs);
         }
     }
+
+    fn simplify_standard_types(&mut self) {
+        if let Some((_, ref mut ty)) = self.body {
+            ty.simplify_standard_types();
+        }
+    }
+
+    fn lower_fat_pointers(&mut self, config: &Config, out: &mut FatPointers) {
+        if let Some((_, ref mut ty)) = self.body {
+            ty.lower_fat_pointers(config, out);
+        }
+    }
 }
 
 impl Source for EnumVariant {
     fn write<F: Write>(&self, config: &Config, out: &mut SourceWriter<F>) {
         self.documentation.write(config, out);
         write!(out, "{}", self.export_name);
-        if let Some(discriminant) = self.discriminant {
+        if let Some(ref discriminant) = self.discriminant {
             write!(out, " = {}", discriminant);
         }
         out.write(",");
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Enum {
     pub path: Path,
     pub export_name: String,
@@ -250,6 +243,41 @@ impl Enum {
         }
     }
 
+    pub fn simplify_standard_types(&mut self) {
+        for variant in &mut self.variants {
+            variant.simplify_standard_types();
+        }
+    }
+
+    pub fn lower_fat_pointers(&mut self, config: &Config, out: &mut FatPointers) {
+        for variant in &mut self.variants {
+            variant.lower_fat_pointers(config, out);
+        }
+    }
+
+    /// Replaces the discriminants that are expressions with their values,
+    /// with `constant` giving the values of the constants they refer to. The
+    /// ones that refer to unknown constants are left as expressions.
+    pub fn evaluate_discriminants<F>(&mut self, constant: &F)
+    where
+        F: Fn(&str) -> Option<i64>,
+    {
+        for variant in &mut self.variants {
+            let value = match variant.discriminant {
+                Some(ref discriminant) => discriminant.evaluate(self.repr.ty, constant),
+                None => continue,
+            };
+            match value {
+                Ok(Some(value)) => variant.discriminant = Some(Discriminant::Value(value)),
+                Ok(None) => {}
+                Err(msg) => warn!(
+                    "Can't evaluate the discriminant of {}::{}: {}",
+                    self.path, variant.name, msg
+                ),
+            }
+        }
+    }
+
     pub fn load(item: &syn::ItemEnum, mod_cfg: Option<&Cfg>) -> Result<Enum, String> {
         let repr = Repr::load(&item.attrs)?;
         if repr == Repr::RUST {
@@ -386,6 +414,9 @@ impl Item for Enum {
             if let Some((_, ref mut body)) = variant.body {
                 body.rename_for_config(config);
             }
+            if let Some(ref mut discriminant) = variant.discriminant {
+                discriminant.rename_for_config(config);
+            }
         }
 
         if config.enumeration.prefix_with_name
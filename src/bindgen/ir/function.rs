@@ -2,16 +2,19 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use std::collections::HashMap;
 use std::io::Write;
 
 use syn;
 
 use bindgen::cdecl;
-use bindgen::config::{Config, Language, Layout};
+use bindgen::config::{Config, ItemType, Language, Layout};
 use bindgen::declarationtyperesolver::DeclarationTypeResolver;
 use bindgen::dependencies::Dependencies;
+use bindgen::fatptr::FatPointers;
 use bindgen::ir::{
-    AnnotationSet, Cfg, ConditionWrite, Documentation, Path, PrimitiveType, ToCondition, Type,
+    AnnotationSet, AnnotationValue, Cfg, ConditionWrite, Documentation, GenericParams, Path,
+    PrimitiveType, ToCondition, Type,
 };
 use bindgen::library::Library;
 use bindgen::monomorph::Monomorphs;
@@ -20,9 +23,12 @@ use bindgen::reserved;
 use bindgen::utilities::{find_first_some, IterHelpers};
 use bindgen::writer::{Source, SourceWriter};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Function {
     pub path: Path,
+    /// The name the function is declared as, which is a macro for its symbol
+    /// if it's renamed.
+    pub export_name: String,
     pub ret: Type,
     pub args: Vec<(String, Type)>,
     pub extern_decl: bool,
@@ -52,6 +58,7 @@ impl Function {
         };
 
         Ok(Function {
+            export_name: path.name().to_owned(),
             path,
             ret,
             args,
@@ -73,6 +80,14 @@ impl Function {
         }
     }
 
+    pub fn lower_fat_pointers(&mut self, config: &Config, out: &mut FatPointers) {
+        let generic_params = GenericParams::default();
+        self.ret.lower_fat_pointers(&generic_params, config, out);
+        for &mut (_, ref mut ty) in &mut self.args {
+            ty.lower_fat_pointers(&generic_params, config, out);
+        }
+    }
+
     pub fn add_dependencies(&self, library: &Library, out: &mut Dependencies) {
         self.ret.add_dependencies(library, out);
         for &(_, ref ty) in &self.args {
@@ -102,6 +117,10 @@ impl Function {
     }
 
     pub fn rename_for_config(&mut self, config: &Config) {
+        config
+            .export
+            .rename_item(Some(&ItemType::Functions), &mut self.export_name);
+
         // Rename the types used in arguments
         let generic_params = Default::default();
         self.ret.rename_for_config(config, &generic_params);
@@ -135,6 +154,17 @@ impl Function {
     }
 }
 
+impl Function {
+    /// Writes a macro for the name the function was renamed to, as the
+    /// function can only be declared as its symbol.
+    fn write_alias<F: Write>(&self, out: &mut SourceWriter<F>) {
+        if self.export_name != self.path.name() {
+            out.new_line();
+            write!(out, "#define {} {}", self.export_name, self.path.name());
+        }
+    }
+}
+
 impl Source for Function {
     fn write<F: Write>(&self, config: &Config, out: &mut SourceWriter<F>) {
         fn write_1<W: Write>(func: &Function, config: &Config, out: &mut SourceWriter<W>) {
@@ -167,6 +197,7 @@ impl Source for Function {
                 }
             }
             out.write(";");
+            func.write_alias(out);
 
             condition.write_after(config, out);
         }
@@ -204,6 +235,7 @@ impl Source for Function {
                 }
             }
             out.write(";");
+            func.write_alias(out);
 
             condition.write_after(config, out);
         };
@@ -220,6 +252,162 @@ impl Source for Function {
     }
 }
 
+/// A C++ member function of a struct or opaque item, which calls a function
+/// whose first argument points to it with `this`.
+#[derive(Debug, Clone)]
+pub struct Method {
+    pub name: String,
+    pub function: Function,
+    /// Whether the first argument points to a const item, which makes the
+    /// member function const.
+    pub is_const: bool,
+    /// Whether the first argument is a reference rather than a pointer.
+    pub by_ref: bool,
+}
+
+impl Method {
+    /// The member functions of `classes`, the structs and opaque items which
+    /// can have them, by the name of the item in rust.
+    pub fn find(
+        config: &Config,
+        classes: &HashMap<String, AnnotationSet>,
+        functions: &[Function],
+    ) -> HashMap<String, Vec<Method>> {
+        let mut methods: HashMap<String, Vec<Method>> = HashMap::new();
+        for function in functions {
+            let explicit = match function.annotations.get("method") {
+                Some(&AnnotationValue::Bool(false)) => continue,
+                Some(&AnnotationValue::Bool(true)) => Some(None),
+                Some(&AnnotationValue::Atom(ref name)) => Some(name.clone()),
+                _ => None,
+            };
+
+            let receiver = function.args.first().and_then(|&(_, ref ty)| match *ty {
+                Type::ConstPtr(ref ty, _) => Some((ty, true, false)),
+                Type::Ptr(ref ty, _) => Some((ty, false, false)),
+                Type::Ref(ref ty) => Some((ty, true, true)),
+                Type::MutRef(ref ty) => Some((ty, false, true)),
+                _ => None,
+            });
+            let class = receiver.and_then(|(ty, is_const, by_ref)| match **ty {
+                Type::Path(ref generic) if generic.generics().is_empty() => classes
+                    .get(generic.path().name())
+                    .map(|annotations| (generic.path().name(), annotations, is_const, by_ref)),
+                _ => None,
+            });
+            let (class, annotations, is_const, by_ref) = match class {
+                Some(x) => x,
+                None => {
+                    if explicit.is_some() {
+                        warn!(
+                            "Can't make {} a member function, as its first argument doesn't \
+                             point to a struct.",
+                            function.path
+                        );
+                    }
+                    continue;
+                }
+            };
+
+            // `{method_prefix}{class in snake case}_{name}`
+            let inferred = {
+                let prefix = config.structure.method_prefix.as_ref().map_or("", |x| x);
+                let class_prefix = format!(
+                    "{}{}_",
+                    prefix,
+                    RenameRule::SnakeCase.apply_to_pascal_case(class, IdentifierType::Item)
+                );
+                let name = function.path.name();
+                if name.starts_with(&class_prefix) && name.len() > class_prefix.len() {
+                    Some(name[class_prefix.len()..].to_owned())
+                } else {
+                    None
+                }
+            };
+
+            let name = match explicit {
+                Some(Some(name)) => name,
+                Some(None) => inferred.unwrap_or_else(|| function.path.name().to_owned()),
+                None if config.structure.derive_methods(annotations) => match inferred {
+                    Some(name) => name,
+                    None => continue,
+                },
+                None => continue,
+            };
+
+            methods.entry(class.to_owned()).or_default().push(Method {
+                name,
+                function: function.clone(),
+                is_const,
+                by_ref,
+            });
+        }
+        methods
+    }
+
+    /// Adds the items the signature of the member function refers to.
+    pub fn add_dependencies(&self, library: &Library, out: &mut Dependencies) {
+        self.function.ret.add_dependencies(library, out);
+        for &(_, ref ty) in self.function.args.iter().skip(1) {
+            ty.add_dependencies(library, out);
+        }
+    }
+
+    /// The function with the signature of the member function, named `name`.
+    fn signature(&self, name: String) -> Function {
+        let mut signature = self.function.clone();
+        signature.path = Path::new(name);
+        signature.args.remove(0);
+        signature
+    }
+
+    /// Writes the declaration of the member function, in the body of its
+    /// struct.
+    pub fn write_declaration<F: Write>(&self, config: &Config, out: &mut SourceWriter<F>) {
+        let condition = (&self.function.cfg).to_condition(config);
+        condition.write_before(config, out);
+
+        self.function.documentation.write(config, out);
+        cdecl::write_func(out, &self.signature(self.name.clone()), false, false);
+        if self.is_const {
+            out.write(" const");
+        }
+        out.write(";");
+
+        condition.write_after(config, out);
+    }
+
+    /// Writes the inline definition of the member function of `class`, which
+    /// calls its function as `call`.
+    pub fn write_definition<F: Write>(
+        &self,
+        config: &Config,
+        out: &mut SourceWriter<F>,
+        class: &str,
+        call: &str,
+    ) {
+        let condition = (&self.function.cfg).to_condition(config);
+        condition.write_before(config, out);
+
+        out.write("inline ");
+        let signature = self.signature(format!("{}::{}", class, self.name));
+        cdecl::write_func(out, &signature, false, false);
+        if self.is_const {
+            out.write(" const");
+        }
+        out.open_brace();
+        if self.function.ret != Type::Primitive(PrimitiveType::Void) {
+            out.write("return ");
+        }
+        let mut args = vec![if self.by_ref { "*this" } else { "this" }];
+        args.extend(signature.args.iter().map(|&(ref name, _)| name.as_str()));
+        write!(out, "{}({});", call, args.join(", "));
+        out.close_brace(false);
+
+        condition.write_after(config, out);
+    }
+}
+
 pub trait SynFnArgHelpers {
     fn as_ident_and_type(&self) -> Result<Option<(String, Type)>, String>;
 }
@@ -9,7 +9,7 @@ use bindgen::ir::{Path, Type};
 use bindgen::utilities::IterHelpers;
 use bindgen::writer::{Source, SourceWriter};
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct GenericParams(pub Vec<Path>);
 
 impl GenericParams {
@@ -55,7 +55,7 @@ impl Source for GenericParams {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct GenericPath {
     path: Path,
     export_name: String,
@@ -91,6 +91,10 @@ impl GenericPath {
         &self.generics
     }
 
+    pub fn generics_mut(&mut self) -> &mut [Type] {
+        &mut self.generics
+    }
+
     pub fn ctype(&self) -> Option<&DeclarationType> {
         self.ctype.as_ref()
     }
@@ -114,6 +118,11 @@ impl GenericPath {
 
     pub fn resolve_declaration_types(&mut self, resolver: &DeclarationTypeResolver) {
         self.ctype = resolver.type_for(&self.path);
+        // Only mapped types keep their generics in C, since everything else
+        // is monomorphized, but they need to be spelled correctly too.
+        for generic in &mut self.generics {
+            generic.resolve_declaration_types(resolver);
+        }
     }
 
     pub fn load(path: &syn::Path) -> Result<Self, String> {
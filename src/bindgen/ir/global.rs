@@ -6,14 +6,17 @@ use std::io::Write;
 
 use syn;
 
-use bindgen::config::Config;
+use bindgen::config::{Config, ItemType};
 use bindgen::declarationtyperesolver::DeclarationTypeResolver;
 use bindgen::dependencies::Dependencies;
-use bindgen::ir::{AnnotationSet, Cfg, Documentation, Item, ItemContainer, Path, Type};
+use bindgen::fatptr::FatPointers;
+use bindgen::ir::{
+    AnnotationSet, Cfg, Documentation, GenericParams, Item, ItemContainer, Path, Type,
+};
 use bindgen::library::Library;
 use bindgen::writer::{Source, SourceWriter};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Static {
     pub path: Path,
     pub export_name: String,
@@ -65,6 +68,11 @@ impl Static {
     pub fn simplify_standard_types(&mut self) {
         self.ty.simplify_standard_types();
     }
+
+    pub fn lower_fat_pointers(&mut self, config: &Config, out: &mut FatPointers) {
+        self.ty
+            .lower_fat_pointers(&GenericParams::default(), config, out);
+    }
 }
 
 impl Item for Static {
@@ -93,6 +101,9 @@ impl Item for Static {
     }
 
     fn rename_for_config(&mut self, config: &Config) {
+        config
+            .export
+            .rename_item(Some(&ItemType::Globals), &mut self.export_name);
         self.ty.rename_for_config(config, &Default::default());
     }
 
@@ -115,6 +126,13 @@ impl Source for Static {
             }
         }
         self.ty.write(config, out);
-        write!(out, " {};", self.export_name());
+        write!(out, " {};", self.path.name());
+
+        // Globals can only be declared as their symbol, so a renamed global
+        // is a macro for it.
+        if self.export_name != self.path.name() {
+            out.new_line();
+            write!(out, "#define {} {}", self.export_name, self.path.name());
+        }
     }
 }
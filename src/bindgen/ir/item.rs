@@ -9,7 +9,8 @@ use bindgen::config::Config;
 use bindgen::declarationtyperesolver::DeclarationTypeResolver;
 use bindgen::dependencies::Dependencies;
 use bindgen::ir::{
-    AnnotationSet, Cfg, Constant, Enum, OpaqueItem, Path, Static, Struct, Type, Typedef, Union,
+    AnnotationSet, Cfg, Constant, Enum, MappedType, OpaqueItem, Path, Static, Struct, Type,
+    Typedef, Union,
 };
 use bindgen::library::Library;
 use bindgen::monomorph::Monomorphs;
@@ -51,6 +52,7 @@ pub enum ItemContainer {
     Union(Union),
     Enum(Enum),
     Typedef(Typedef),
+    MappedType(MappedType),
 }
 
 impl ItemContainer {
@@ -63,17 +65,18 @@ impl ItemContainer {
             &ItemContainer::Union(ref x) => x,
             &ItemContainer::Enum(ref x) => x,
             &ItemContainer::Typedef(ref x) => x,
+            &ItemContainer::MappedType(ref x) => x,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ItemValue<T: Item> {
     Cfg(Vec<T>),
     Single(T),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ItemMap<T: Item> {
     data: BTreeMap<Path, ItemValue<T>>,
 }
@@ -0,0 +1,142 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::io::Write;
+
+use syn;
+
+use bindgen::config::{Config, TypeMapping};
+use bindgen::ir::{
+    AnnotationSet, Cfg, GenericParams, GenericPath, Item, ItemContainer, Path, Type,
+};
+use bindgen::library::Library;
+use bindgen::monomorph::Monomorphs;
+use bindgen::writer::{Source, SourceWriter};
+
+/// A type from `[export.type_map]`, which is spelled as given in the config
+/// instead of being generated.
+#[derive(Debug, Clone)]
+pub struct MappedType {
+    pub path: Path,
+    /// The full path of the pattern if it's qualified, like `libc::timespec`.
+    pub qualified_path: Option<String>,
+    pub generic_params: GenericParams,
+    pub mapping: TypeMapping,
+    pub annotations: AnnotationSet,
+}
+
+impl MappedType {
+    pub fn load(pattern: &str, mapping: &TypeMapping) -> Result<MappedType, String> {
+        let path = syn::parse_str::<syn::Path>(pattern)
+            .map_err(|_| format!("{} is not a valid type path.", pattern))?;
+        let generic_path = GenericPath::load(&path)?;
+        let qualified_path = if path.segments.len() > 1 {
+            let segments: Vec<_> = path.segments.iter().map(|x| x.ident.to_string()).collect();
+            Some(segments.join("::"))
+        } else {
+            None
+        };
+
+        let mut generic_params = Vec::new();
+        for generic in generic_path.generics() {
+            match generic {
+                &Type::Path(ref param) if param.generics().is_empty() => {
+                    generic_params.push(param.path().clone());
+                }
+                _ => {
+                    return Err(format!(
+                        "{} has a generic argument that isn't a name.",
+                        pattern
+                    ))
+                }
+            }
+        }
+
+        Ok(MappedType {
+            path: generic_path.path().clone(),
+            qualified_path,
+            generic_params: GenericParams(generic_params),
+            mapping: mapping.clone(),
+            annotations: AnnotationSet::new(),
+        })
+    }
+
+    /// The C spelling of this type, given the spelling of its generic
+    /// arguments. If the pattern has no generic parameters, any arguments
+    /// are ignored.
+    pub fn spelling(&self, generic_values: &[String]) -> String {
+        if self.generic_params.len() != generic_values.len() {
+            return self.mapping.name.clone();
+        }
+
+        let mut spelling = String::new();
+        let mut rest = self.mapping.name.as_str();
+        while let Some(start) = rest.find(|c: char| c.is_alphanumeric() || c == '_') {
+            spelling.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest
+                .find(|c: char| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            match self.generic_params.iter().position(|x| x.name() == word) {
+                Some(i) => spelling.push_str(&generic_values[i]),
+                None => spelling.push_str(word),
+            }
+            rest = &rest[end..];
+        }
+        spelling.push_str(rest);
+        spelling
+    }
+}
+
+impl Item for MappedType {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn export_name(&self) -> &str {
+        &self.mapping.name
+    }
+
+    fn cfg(&self) -> Option<&Cfg> {
+        None
+    }
+
+    fn annotations(&self) -> &AnnotationSet {
+        &self.annotations
+    }
+
+    fn annotations_mut(&mut self) -> &mut AnnotationSet {
+        &mut self.annotations
+    }
+
+    fn container(&self) -> ItemContainer {
+        ItemContainer::MappedType(self.clone())
+    }
+
+    fn instantiate_monomorph(
+        &self,
+        generic_values: &[Type],
+        library: &Library,
+        out: &mut Monomorphs,
+    ) {
+        // The type is spelled out with its generic arguments, so only they
+        // need to be instantiated.
+        for ty in generic_values {
+            ty.add_monomorphs(library, out);
+        }
+        out.insert_mapped(GenericPath::new(
+            self.path.clone(),
+            generic_values.to_owned(),
+        ));
+    }
+}
+
+impl Source for MappedType {
+    fn write<F: Write>(&self, _config: &Config, out: &mut SourceWriter<F>) {
+        if let Some(ref declaration) = self.mapping.declaration {
+            write!(out, "{}", declaration);
+        }
+    }
+}
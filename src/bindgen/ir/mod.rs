@@ -5,12 +5,14 @@
 pub mod annotation;
 pub mod cfg;
 pub mod constant;
+pub mod discriminant;
 pub mod documentation;
 pub mod enumeration;
 pub mod function;
 pub mod generic_path;
 pub mod global;
 pub mod item;
+pub mod mapped_type;
 pub mod opaque;
 pub mod path;
 pub mod repr;
@@ -22,12 +24,14 @@ pub mod union;
 pub use self::annotation::{AnnotationSet, AnnotationValue};
 pub use self::cfg::*;
 pub use self::constant::*;
+pub use self::discriminant::*;
 pub use self::documentation::Documentation;
 pub use self::enumeration::*;
 pub use self::function::*;
 pub use self::generic_path::*;
 pub use self::global::*;
 pub use self::item::*;
+pub use self::mapped_type::*;
 pub use self::opaque::*;
 pub use self::path::*;
 pub use self::repr::*;
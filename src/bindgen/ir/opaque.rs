@@ -18,7 +18,7 @@ use bindgen::mangle;
 use bindgen::monomorph::Monomorphs;
 use bindgen::writer::{Source, SourceWriter};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpaqueItem {
     pub path: Path,
     pub export_name: String,
@@ -96,7 +96,12 @@ impl Item for OpaqueItem {
         config.export.rename(&mut self.export_name);
     }
 
-    fn add_dependencies(&self, _: &Library, _: &mut Dependencies) {}
+    fn add_dependencies(&self, library: &Library, out: &mut Dependencies) {
+        // Like those of structs, see `Struct::add_dependencies`.
+        for method in library.methods(&self.path) {
+            out.with_indirection(|out| method.add_dependencies(library, out));
+        }
+    }
 
     fn instantiate_monomorph(
         &self,
@@ -147,7 +152,29 @@ impl Source for OpaqueItem {
                 self.export_name()
             );
         } else {
-            write!(out, "struct {};", self.export_name());
+            let methods = out.bindings().methods(&self.path);
+            if methods.is_empty() {
+                write!(out, "struct {};", self.export_name());
+            } else {
+                // The struct is complete to have methods, so it's kept from
+                // being created, copied or destroyed in C++ instead.
+                let name = self.export_name();
+                write!(out, "struct {}", name);
+                out.open_brace();
+                write!(out, "{}() = delete;", name);
+                out.new_line();
+                write!(out, "{0}(const {0}&) = delete;", name);
+                out.new_line();
+                write!(out, "{0}& operator=(const {0}&) = delete;", name);
+                out.new_line();
+                write!(out, "~{}() = delete;", name);
+                out.new_line();
+                for method in methods {
+                    out.new_line();
+                    method.write_declaration(config, out);
+                }
+                out.close_brace(true);
+            }
         }
 
         condition.write_after(config, out);
@@ -4,7 +4,7 @@
 
 use syn;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReprStyle {
     Rust,
     C,
@@ -17,7 +17,7 @@ impl Default for ReprStyle {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReprType {
     U8,
     U16,
@@ -29,7 +29,38 @@ pub enum ReprType {
     ISize,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+impl ReprType {
+    /// Whether a value fits in the type, assuming that `isize` and `usize`
+    /// have 64 bits.
+    pub fn holds(&self, value: i64) -> bool {
+        let (min, max) = match *self {
+            ReprType::U8 => (0, i64::from(::std::u8::MAX)),
+            ReprType::U16 => (0, i64::from(::std::u16::MAX)),
+            ReprType::U32 => (0, i64::from(::std::u32::MAX)),
+            ReprType::USize => (0, ::std::i64::MAX),
+            ReprType::I8 => (i64::from(::std::i8::MIN), i64::from(::std::i8::MAX)),
+            ReprType::I16 => (i64::from(::std::i16::MIN), i64::from(::std::i16::MAX)),
+            ReprType::I32 => (i64::from(::std::i32::MIN), i64::from(::std::i32::MAX)),
+            ReprType::ISize => (::std::i64::MIN, ::std::i64::MAX),
+        };
+        value >= min && value <= max
+    }
+
+    /// Truncates a value to the type, like `as` does.
+    pub fn wrap(&self, value: i64) -> i64 {
+        match *self {
+            ReprType::U8 => i64::from(value as u8),
+            ReprType::U16 => i64::from(value as u16),
+            ReprType::U32 => i64::from(value as u32),
+            ReprType::I8 => i64::from(value as i8),
+            ReprType::I16 => i64::from(value as i16),
+            ReprType::I32 => i64::from(value as i32),
+            ReprType::USize | ReprType::ISize => value,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct Repr {
     pub style: ReprStyle,
     pub ty: Option<ReprType>,
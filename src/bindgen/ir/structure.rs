@@ -6,22 +6,24 @@ use std::io::Write;
 
 use syn;
 
-use bindgen::config::{Config, Language};
+use bindgen::config::{Config, FlexibleArrayStyle, Language};
 use bindgen::declarationtyperesolver::DeclarationTypeResolver;
 use bindgen::dependencies::Dependencies;
+use bindgen::explain::Referrer;
+use bindgen::fatptr::FatPointers;
 use bindgen::ir::{
-    AnnotationSet, Cfg, ConditionWrite, Constant, Documentation, GenericParams, Item,
-    ItemContainer, Path, Repr, ToCondition, Type, Typedef,
+    AnnotationSet, ArrayLength, Cfg, ConditionWrite, Constant, Documentation, GenericParams, Item,
+    ItemContainer, Path, PrimitiveType, Repr, ToCondition, Type, Typedef,
 };
 use bindgen::library::Library;
 use bindgen::mangle;
 use bindgen::monomorph::Monomorphs;
 use bindgen::rename::{IdentifierType, RenameRule};
 use bindgen::reserved;
-use bindgen::utilities::{find_first_some, IterHelpers};
+use bindgen::utilities::{find_first_some, IterHelpers, SynAttributeHelpers};
 use bindgen::writer::{ListType, Source, SourceWriter};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Struct {
     pub path: Path,
     pub export_name: String,
@@ -34,10 +36,18 @@ pub struct Struct {
     pub is_enum_variant_body: bool,
     pub is_transparent: bool,
     pub tuple_struct: bool,
+    /// Whether the struct is declared before it's defined, because items
+    /// before it refer to it. It's then defined through its tag in C.
+    pub is_forward_declared: bool,
     pub cfg: Option<Cfg>,
     pub annotations: AnnotationSet,
     pub documentation: Documentation,
     pub associated_constants: Vec<Constant>,
+    /// The widths of the fields, if they're bitfields.
+    pub bitfield_widths: Vec<u32>,
+    /// Whether it's a field of another struct or union, or an element of an
+    /// array, by value, where it can't have a flexible array member.
+    pub is_embedded: bool,
 }
 
 impl Struct {
@@ -51,6 +61,14 @@ impl Struct {
     }
 
     pub fn load(item: &syn::ItemStruct, mod_cfg: Option<&Cfg>) -> Result<Self, String> {
+        if let Some(args) = item.attrs.get_attr_list("bitfield") {
+            // Layout mismatches are warned about, since they're easy to miss.
+            return Struct::load_bitfields(item, &args, mod_cfg).map_err(|msg| {
+                warn!("Can't use {} as bitfields: {}", item.ident, msg);
+                msg
+            });
+        }
+
         let is_transparent = match Repr::load(&item.attrs)? {
             Repr::C => false,
             Repr::TRANSPARENT => true,
@@ -81,6 +99,32 @@ impl Struct {
             }
         };
 
+        // Each field of a #[repr(C)] struct has storage of its own, so writing
+        // it as a bitfield would change the layout.
+        for (i, field) in item.fields.iter().enumerate() {
+            let annotations = AnnotationSet::load(&field.attrs).ok();
+            if annotations.map_or(false, |x| x.atom("bitfield").is_some()) {
+                let name = field
+                    .ident
+                    .as_ref()
+                    .map_or(i.to_string(), |x| x.to_string());
+                warn!(
+                    "Ignoring the bitfield width of {}::{}, as only the fields of a #[bitfield] struct are bitfields.",
+                    item.ident, name
+                );
+            }
+        }
+
+        // Only the last field can be declared as a flexible array member.
+        for &(ref name, ref ty, _) in fields.iter().rev().skip(1) {
+            if is_zero_length_array(ty) {
+                warn!(
+                    "Zero-length array {}::{} isn't the last field, so it's declared with a length of 0.",
+                    item.ident, name
+                );
+            }
+        }
+
         let is_tagged = false;
         let is_enum_variant_body = false;
 
@@ -98,6 +142,118 @@ impl Struct {
         ))
     }
 
+    /// Loads a struct with a `#[bitfield(storage)]` attribute, like those of
+    /// the `bitfield-struct` crate, whose fields are bitfields packed in the
+    /// storage integer. The width of each field is given by a `#[bits(width)]`
+    /// attribute or a `cbindgen:bitfield=width` annotation, and is that of its
+    /// type otherwise.
+    fn load_bitfields(
+        item: &syn::ItemStruct,
+        args: &[syn::NestedMeta],
+        mod_cfg: Option<&Cfg>,
+    ) -> Result<Self, String> {
+        let storage = match args.first() {
+            Some(&syn::NestedMeta::Meta(syn::Meta::Word(ref ident))) => {
+                PrimitiveType::maybe(&ident.to_string())
+            }
+            _ => None,
+        };
+        let (storage, storage_width) =
+            match storage.and_then(|x| bitfield_width(&x).map(|w| (x, w))) {
+                Some(ref x) if x.0 != PrimitiveType::Bool => x.clone(),
+                _ => return Err("Bitfield storage isn't an integer type.".to_owned()),
+            };
+
+        if !item.generics.params.is_empty() {
+            return Err("Bitfields can't be generic.".to_owned());
+        }
+
+        let named = match item.fields {
+            syn::Fields::Named(ref fields) => &fields.named,
+            _ => return Err("Bitfields have to be named fields.".to_owned()),
+        };
+
+        let mut fields = Vec::new();
+        let mut widths = Vec::new();
+        for field in named {
+            let name = field
+                .ident
+                .as_ref()
+                .ok_or_else(|| "field is missing identifier".to_owned())?
+                .to_string();
+            let bits = match field.attrs.get_attr_list("bits") {
+                Some(args) => match args.first() {
+                    Some(&syn::NestedMeta::Literal(syn::Lit::Int(ref width))) => {
+                        Some(width.value() as u32)
+                    }
+                    _ => return Err(format!("Can't read the `#[bits]` of bitfield {}.", name)),
+                },
+                None => None,
+            };
+            let annotated = match AnnotationSet::load(&field.attrs)?.atom("bitfield") {
+                Some(Some(width)) => match width.parse::<u32>() {
+                    Ok(x) => Some(x),
+                    Err(_) => return Err(format!("Bitfield {} has a width of `{}`.", name, width)),
+                },
+                _ => None,
+            };
+            let width = bits.or(annotated).or_else(|| match Type::load(&field.ty) {
+                Ok(Some(Type::Primitive(ref x))) => bitfield_width(x),
+                _ => None,
+            });
+            let width = match width {
+                Some(x) if x > 0 && x <= storage_width => x,
+                Some(x) => return Err(format!("Bitfield {} has a width of {} bits.", name, x)),
+                None => return Err(format!("Bitfield {} needs a `#[bits]` width.", name)),
+            };
+
+            fields.push((
+                name,
+                Type::Primitive(storage.clone()),
+                Documentation::load(&field.attrs),
+            ));
+            widths.push(width);
+        }
+
+        // Anything else would change the layout of the storage integer.
+        let total: u32 = widths.iter().sum();
+        if total != storage_width {
+            let msg = format!(
+                "Bitfields of {} add up to {} bits instead of the {} of {}.",
+                item.ident, total, storage_width, storage
+            );
+            return Err(msg);
+        }
+
+        let mut bitfields = Struct::new(
+            Path::new(item.ident.to_string()),
+            GenericParams::new(&item.generics),
+            fields,
+            false,
+            false,
+            false,
+            false,
+            Cfg::append(mod_cfg, Cfg::load(&item.attrs)),
+            AnnotationSet::load(&item.attrs)?,
+            Documentation::load(&item.attrs),
+        );
+        bitfields.bitfield_widths = widths;
+        Ok(bitfields)
+    }
+
+    /// Whether the last field is a zero-length array that can be declared as a
+    /// flexible array member, which needs another field before it and can't
+    /// be in the union of a tagged enum, or be embedded in something else.
+    pub fn has_flexible_array(&self) -> bool {
+        !self.is_enum_variant_body
+            && !self.is_embedded
+            && self.fields.len() > 1
+            && self
+                .fields
+                .last()
+                .map_or(false, |&(_, ref ty, _)| is_zero_length_array(ty))
+    }
+
     pub fn new(
         path: Path,
         generic_params: GenericParams,
@@ -120,19 +276,49 @@ impl Struct {
             is_enum_variant_body,
             is_transparent,
             tuple_struct,
+            is_forward_declared: false,
             cfg,
             annotations,
             documentation,
             associated_constants: vec![],
+            bitfield_widths: vec![],
+            is_embedded: false,
         }
     }
 
+    /// Writes a declaration of the struct, for the items that refer to it
+    /// before it's defined.
+    pub fn write_forward_declaration<F: Write>(&self, config: &Config, out: &mut SourceWriter<F>) {
+        let condition = (&self.cfg).to_condition(config);
+        condition.write_before(config, out);
+
+        self.generic_params.write(config, out);
+
+        if config.language == Language::C && config.style.generate_typedef() {
+            write!(
+                out,
+                "typedef struct {} {};",
+                self.export_name, self.export_name
+            );
+        } else {
+            write!(out, "struct {};", self.export_name);
+        }
+
+        condition.write_after(config, out);
+    }
+
     pub fn simplify_standard_types(&mut self) {
         for &mut (_, ref mut ty, _) in &mut self.fields {
             ty.simplify_standard_types();
         }
     }
 
+    pub fn lower_fat_pointers(&mut self, config: &Config, out: &mut FatPointers) {
+        for &mut (_, ref mut ty, _) in &mut self.fields {
+            ty.lower_fat_pointers(&self.generic_params, config, out);
+        }
+    }
+
     pub fn is_generic(&self) -> bool {
         self.generic_params.len() > 0
     }
@@ -290,8 +476,18 @@ impl Item for Struct {
             ty.add_dependencies_ignoring_generics(&self.generic_params, library, out);
         }
 
+        // The constants are written after the struct, so the struct itself
+        // doesn't need what they refer to.
         for c in &self.associated_constants {
-            c.add_dependencies(library, out);
+            out.with_referrer(Referrer::Constant(c.path.clone()), |out| {
+                c.add_dependencies(library, out);
+            });
+        }
+
+        // Member functions are defined after the functions, so declaring them
+        // only needs what they refer to declared.
+        for method in library.methods(&self.path) {
+            out.with_indirection(|out| method.add_dependencies(library, out));
         }
     }
 
@@ -365,7 +561,10 @@ impl Source for Struct {
         //   typedef struct {
         // C with Both as style:
         //   typedef struct Name {
-        if config.language == Language::C && config.style.generate_typedef() {
+        let typedef = config.language == Language::C
+            && config.style.generate_typedef()
+            && !self.is_forward_declared;
+        if typedef {
             out.write("typedef ");
         }
 
@@ -377,20 +576,38 @@ impl Source for Struct {
             }
         }
 
-        if config.language == Language::Cxx || config.style.generate_tag() {
+        if !typedef || config.style.generate_tag() {
             write!(out, " {}", self.export_name());
         }
 
         out.open_brace();
 
+        // Bitfields are declared with their width after their name, and a
+        // trailing zero-length array as a flexible array member.
+        let flexible_array = self.has_flexible_array()
+            && config.structure.flexible_array(&config.language) == FlexibleArrayStyle::Flexible;
+        let fields: Vec<_> = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, &(ref name, ref ty, ref documentation))| {
+                let name = match self.bitfield_widths.get(i) {
+                    Some(width) => format!("{} : {}", name, width),
+                    None => name.clone(),
+                };
+                let ty = match *ty {
+                    Type::Array(ref ty, _) if flexible_array && i == self.fields.len() - 1 => {
+                        Type::Array(ty.clone(), ArrayLength::Value(String::new()))
+                    }
+                    _ => ty.clone(),
+                };
+                (name, ty, documentation.clone())
+            })
+            .collect();
         if config.documentation {
-            out.write_vertical_source_list(&self.fields, ListType::Cap(";"));
+            out.write_vertical_source_list(&fields, ListType::Cap(";"));
         } else {
-            let vec: Vec<_> = self
-                .fields
-                .iter()
-                .map(|&(ref name, ref ty, _)| (name.clone(), ty.clone()))
-                .collect();
+            let vec: Vec<_> = fields.into_iter().map(|(name, ty, _)| (name, ty)).collect();
             out.write_vertical_source_list(&vec[..], ListType::Cap(";"));
         }
 
@@ -579,23 +796,29 @@ impl Source for Struct {
             {
                 emit_op(">=", "&&");
             }
+
+            let methods = out.bindings().methods(&self.path);
+            if !methods.is_empty() && !wrote_start_newline {
+                out.new_line();
+            }
+            for method in methods {
+                out.new_line();
+                method.write_declaration(config, out);
+            }
         }
 
         if let Some(body) = config.export.extra_body(&self.path) {
             out.write_raw_block(body);
         }
 
-        if config.language == Language::Cxx
-            && config.structure.associated_constants_in_body
-            && config.constant.allow_static_const
-        {
-            for constant in &self.associated_constants {
+        for constant in &self.associated_constants {
+            if constant.is_in_body(config, Some(self)) {
                 out.new_line();
                 constant.write_declaration(config, out, self);
             }
         }
 
-        if config.language == Language::C && config.style.generate_typedef() {
+        if typedef {
             out.close_brace(false);
             write!(out, " {};", self.export_name());
         } else {
@@ -603,14 +826,40 @@ impl Source for Struct {
         }
 
         for constant in &self.associated_constants {
-            out.new_line();
-            constant.write(config, out, Some(self));
+            if !constant.is_defined_in_body(config, Some(self)) {
+                out.new_line();
+                constant.write(config, out, Some(self));
+            }
         }
 
         condition.write_after(config, out);
     }
 }
 
+/// Whether a type is `[T; 0]`, the rust idiom for a flexible array member.
+fn is_zero_length_array(ty: &Type) -> bool {
+    match *ty {
+        Type::Array(_, ArrayLength::Value(ref len)) => len == "0",
+        _ => false,
+    }
+}
+
+/// The number of bits of a type that can be a bitfield.
+fn bitfield_width(ty: &PrimitiveType) -> Option<u32> {
+    match *ty {
+        PrimitiveType::Bool => Some(1),
+        PrimitiveType::Char
+        | PrimitiveType::SChar
+        | PrimitiveType::UChar
+        | PrimitiveType::UInt8
+        | PrimitiveType::Int8 => Some(8),
+        PrimitiveType::UInt16 | PrimitiveType::Int16 => Some(16),
+        PrimitiveType::UInt32 | PrimitiveType::Int32 => Some(32),
+        PrimitiveType::UInt64 | PrimitiveType::Int64 => Some(64),
+        _ => None,
+    }
+}
+
 pub trait SynFieldHelpers {
     fn as_ident_and_type(&self) -> Result<Option<(String, Type, Documentation)>, String>;
 }
@@ -11,13 +11,15 @@ use bindgen::cdecl;
 use bindgen::config::Config;
 use bindgen::declarationtyperesolver::DeclarationTypeResolver;
 use bindgen::dependencies::Dependencies;
-use bindgen::ir::{Documentation, GenericParams, GenericPath, Path};
+use bindgen::explain::Referrer;
+use bindgen::fatptr::FatPointers;
+use bindgen::ir::{Documentation, GenericParams, GenericPath, ItemContainer, Path};
 use bindgen::library::Library;
 use bindgen::monomorph::Monomorphs;
 use bindgen::utilities::IterHelpers;
 use bindgen::writer::{Source, SourceWriter};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum PrimitiveType {
     Void,
     Bool,
@@ -178,8 +180,32 @@ impl fmt::Display for PrimitiveType {
     }
 }
 
+/// The primitive that a `NonZero*` integer wraps, if `name` is one.
+fn nonzero_primitive(name: &str, generics: &[Type]) -> Option<PrimitiveType> {
+    if !generics.is_empty() {
+        return match (name, generics) {
+            ("NonZero", &[Type::Primitive(ref primitive)]) => Some(primitive.clone()),
+            _ => None,
+        };
+    }
+
+    match name {
+        "NonZeroU8" => Some(PrimitiveType::UInt8),
+        "NonZeroU16" => Some(PrimitiveType::UInt16),
+        "NonZeroU32" => Some(PrimitiveType::UInt32),
+        "NonZeroU64" => Some(PrimitiveType::UInt64),
+        "NonZeroUsize" => Some(PrimitiveType::USize),
+        "NonZeroI8" => Some(PrimitiveType::Int8),
+        "NonZeroI16" => Some(PrimitiveType::Int16),
+        "NonZeroI32" => Some(PrimitiveType::Int32),
+        "NonZeroI64" => Some(PrimitiveType::Int64),
+        "NonZeroIsize" => Some(PrimitiveType::ISize),
+        _ => None,
+    }
+}
+
 // The `U` part of `[T; U]`
-#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum ArrayLength {
     Name(String),
     Value(String),
@@ -199,10 +225,74 @@ impl ArrayLength {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// The dynamically sized target of a fat pointer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum FatPointee {
+    Slice(Box<Type>),
+    Str,
+    TraitObject(Path),
+}
+
+impl FatPointee {
+    fn load(ty: &syn::Type) -> Result<Option<FatPointee>, String> {
+        let pointee = match ty {
+            &syn::Type::Slice(ref slice) => match Type::load(&slice.elem)? {
+                Some(elem) => FatPointee::Slice(Box::new(elem)),
+                None => return Err("Cannot have a slice of zero sized types.".to_owned()),
+            },
+            &syn::Type::Path(ref path) if path.qself.is_none() && path.path.is_ident("str") => {
+                FatPointee::Str
+            }
+            &syn::Type::TraitObject(ref trait_object) => {
+                let trait_path = trait_object.bounds.iter().find_map(|bound| match bound {
+                    &syn::TypeParamBound::Trait(ref bound) => bound.path.segments.last(),
+                    _ => None,
+                });
+                match trait_path {
+                    Some(segment) => {
+                        FatPointee::TraitObject(Path::new(segment.value().ident.to_string()))
+                    }
+                    None => return Err("Trait object without a trait bound.".to_owned()),
+                }
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(pointee))
+    }
+
+    pub fn elem(&self) -> Option<&Type> {
+        match self {
+            &FatPointee::Slice(ref ty) => Some(ty),
+            &FatPointee::Str | &FatPointee::TraitObject(..) => None,
+        }
+    }
+
+    pub fn elem_mut(&mut self) -> Option<&mut Type> {
+        match self {
+            &mut FatPointee::Slice(ref mut ty) => Some(ty),
+            &mut FatPointee::Str | &mut FatPointee::TraitObject(..) => None,
+        }
+    }
+
+    fn specialize(&self, mappings: &[(&Path, &Type)]) -> FatPointee {
+        match self {
+            &FatPointee::Slice(ref ty) => FatPointee::Slice(Box::new(ty.specialize(mappings))),
+            other => other.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Type {
-    ConstPtr(Box<Type>),
-    Ptr(Box<Type>),
+    /// A pointer, and whether it can be null. References and pointers like
+    /// `NonNull<T>` can't, which lets `Option` of them be simplified away.
+    ConstPtr(Box<Type>, bool),
+    Ptr(Box<Type>, bool),
+    /// A pointer to a slice, `str` or trait object. These have no C
+    /// representation and get lowered to a struct by the library.
+    ConstFatPtr(FatPointee),
+    FatPtr(FatPointee),
     Ref(Box<Type>),
     MutRef(Box<Type>),
     Path(GenericPath),
@@ -215,6 +305,13 @@ impl Type {
     pub fn load(ty: &syn::Type) -> Result<Option<Type>, String> {
         let converted = match ty {
             &syn::Type::Reference(ref reference) => {
+                if let Some(pointee) = FatPointee::load(&reference.elem)? {
+                    return Ok(Some(match reference.mutability {
+                        Some(_) => Type::FatPtr(pointee),
+                        None => Type::ConstFatPtr(pointee),
+                    }));
+                }
+
                 let converted = Type::load(&reference.elem)?;
 
                 let converted = match converted {
@@ -227,11 +324,18 @@ impl Type {
                 };
 
                 match reference.mutability {
-                    Some(_) => Type::Ptr(Box::new(converted)),
-                    None => Type::ConstPtr(Box::new(converted)),
+                    Some(_) => Type::Ptr(Box::new(converted), false),
+                    None => Type::ConstPtr(Box::new(converted), false),
                 }
             }
             &syn::Type::Ptr(ref pointer) => {
+                if let Some(pointee) = FatPointee::load(&pointer.elem)? {
+                    return Ok(Some(match pointer.mutability {
+                        Some(_) => Type::FatPtr(pointee),
+                        None => Type::ConstFatPtr(pointee),
+                    }));
+                }
+
                 let converted = Type::load(&pointer.elem)?;
 
                 let converted = match converted {
@@ -244,8 +348,8 @@ impl Type {
                 };
 
                 match pointer.mutability {
-                    Some(_) => Type::Ptr(Box::new(converted)),
-                    None => Type::ConstPtr(Box::new(converted)),
+                    Some(_) => Type::Ptr(Box::new(converted), true),
+                    None => Type::ConstPtr(Box::new(converted), true),
                 }
             }
             &syn::Type::Path(ref path) => {
@@ -340,6 +444,11 @@ impl Type {
                 }
                 return Err("Tuples are not supported types.".to_owned());
             }
+            &syn::Type::Slice(..) | &syn::Type::TraitObject(..) => {
+                return Err(
+                    "Slices and trait objects are only supported behind a pointer.".to_owned(),
+                );
+            }
             _ => return Err(format!("Unsupported type: {:?}", ty)),
         };
 
@@ -349,7 +458,7 @@ impl Type {
     pub fn is_primitive_or_ptr_primitive(&self) -> bool {
         match self {
             &Type::Primitive(..) => true,
-            &Type::ConstPtr(ref x) => match x.as_ref() {
+            &Type::ConstPtr(ref x, _) => match x.as_ref() {
                 &Type::Primitive(..) => true,
                 _ => false,
             },
@@ -357,11 +466,19 @@ impl Type {
         }
     }
 
-    pub fn is_repr_ptr(&self) -> bool {
+    /// Whether `Option<Self>` is guaranteed to have the same layout as `Self`,
+    /// with `None` represented as null or zero.
+    fn has_null_niche(&self) -> bool {
         match self {
-            &Type::Ptr(..) => true,
-            &Type::ConstPtr(..) => true,
+            &Type::ConstPtr(_, is_nullable) | &Type::Ptr(_, is_nullable) => !is_nullable,
             &Type::FuncPtr(..) => true,
+            &Type::Path(ref path) => match path.name() {
+                "NonNull" | "Box" => true,
+                "ManuallyDrop" | "Wrapping" => {
+                    path.generics().len() == 1 && path.generics()[0].has_null_niche()
+                }
+                name => nonzero_primitive(name, path.generics()).is_some(),
+            },
             _ => false,
         }
     }
@@ -372,6 +489,10 @@ impl Type {
             _ => return None,
         };
 
+        if let Some(primitive) = nonzero_primitive(path.name(), path.generics()) {
+            return Some(Type::Primitive(primitive));
+        }
+
         if path.generics().len() != 1 {
             return None;
         }
@@ -380,9 +501,15 @@ impl Type {
         generic.simplify_standard_types();
 
         match path.name() {
-            // FIXME(#223): This is not quite correct.
-            "Option" if generic.is_repr_ptr() => Some(generic),
-            "NonNull" => Some(Type::Ptr(Box::new(generic))),
+            // `None` takes the place of the null pointer or zero, which is only
+            // possible if the wrapped type can't be null itself.
+            "Option" if path.generics()[0].has_null_niche() => Some(match generic {
+                Type::ConstPtr(ty, _) => Type::ConstPtr(ty, true),
+                Type::Ptr(ty, _) => Type::Ptr(ty, true),
+                other => other,
+            }),
+            "NonNull" | "Box" => Some(Type::Ptr(Box::new(generic), false)),
+            "ManuallyDrop" | "MaybeUninit" | "Cell" | "UnsafeCell" | "Wrapping" => Some(generic),
             _ => None,
         }
     }
@@ -390,6 +517,29 @@ impl Type {
     pub fn simplify_standard_types(&mut self) {
         if let Some(ty) = self.simplified_type() {
             *self = ty;
+            return;
+        }
+
+        // Generic arguments of other paths are left alone, as they'd need to
+        // be mangled.
+        match self {
+            &mut Type::ConstPtr(ref mut ty, _)
+            | &mut Type::Ptr(ref mut ty, _)
+            | &mut Type::Ref(ref mut ty)
+            | &mut Type::MutRef(ref mut ty)
+            | &mut Type::Array(ref mut ty, _) => ty.simplify_standard_types(),
+            &mut Type::Path(..) | &mut Type::Primitive(..) => {}
+            &mut Type::FuncPtr(ref mut ret, ref mut args) => {
+                ret.simplify_standard_types();
+                for (_, ref mut arg) in args {
+                    arg.simplify_standard_types();
+                }
+            }
+            &mut Type::ConstFatPtr(ref mut pointee) | &mut Type::FatPtr(ref mut pointee) => {
+                if let Some(ty) = pointee.elem_mut() {
+                    ty.simplify_standard_types();
+                }
+            }
         }
     }
 
@@ -398,8 +548,8 @@ impl Type {
             Type::Array(ref mut ty, ..)
             | Type::MutRef(ref mut ty)
             | Type::Ref(ref mut ty)
-            | Type::Ptr(ref mut ty)
-            | Type::ConstPtr(ref mut ty) => ty.replace_self_with(self_ty),
+            | Type::Ptr(ref mut ty, _)
+            | Type::ConstPtr(ref mut ty, _) => ty.replace_self_with(self_ty),
             Type::Path(ref mut generic_path) => {
                 generic_path.replace_self_with(self_ty);
             }
@@ -410,6 +560,11 @@ impl Type {
                     arg.1.replace_self_with(self_ty);
                 }
             }
+            Type::ConstFatPtr(ref mut pointee) | Type::FatPtr(ref mut pointee) => {
+                if let Some(ty) = pointee.elem_mut() {
+                    ty.replace_self_with(self_ty);
+                }
+            }
         }
     }
 
@@ -417,8 +572,8 @@ impl Type {
         let mut current = self;
         loop {
             match *current {
-                Type::ConstPtr(ref ty) => current = ty,
-                Type::Ptr(ref ty) => current = ty,
+                Type::ConstPtr(ref ty, _) => current = ty,
+                Type::Ptr(ref ty, _) => current = ty,
                 Type::Ref(ref ty) => current = ty,
                 Type::MutRef(ref ty) => current = ty,
                 Type::Path(ref generic) => {
@@ -433,14 +588,21 @@ impl Type {
                 Type::FuncPtr(..) => {
                     return None;
                 }
+                Type::ConstFatPtr(..) | Type::FatPtr(..) => {
+                    return None;
+                }
             };
         }
     }
 
     pub fn specialize(&self, mappings: &[(&Path, &Type)]) -> Type {
         match self {
-            &Type::ConstPtr(ref ty) => Type::ConstPtr(Box::new(ty.specialize(mappings))),
-            &Type::Ptr(ref ty) => Type::Ptr(Box::new(ty.specialize(mappings))),
+            &Type::ConstPtr(ref ty, is_nullable) => {
+                Type::ConstPtr(Box::new(ty.specialize(mappings)), is_nullable)
+            }
+            &Type::Ptr(ref ty, is_nullable) => {
+                Type::Ptr(Box::new(ty.specialize(mappings)), is_nullable)
+            }
             &Type::Ref(ref ty) => Type::Ref(Box::new(ty.specialize(mappings))),
             &Type::MutRef(ref ty) => Type::MutRef(Box::new(ty.specialize(mappings))),
             &Type::Path(ref generic_path) => {
@@ -471,6 +633,8 @@ impl Type {
                     .map(|(name, ty)| (name, ty.specialize(mappings)))
                     .collect(),
             ),
+            &Type::ConstFatPtr(ref pointee) => Type::ConstFatPtr(pointee.specialize(mappings)),
+            &Type::FatPtr(ref pointee) => Type::FatPtr(pointee.specialize(mappings)),
         }
     }
 
@@ -481,28 +645,55 @@ impl Type {
         out: &mut Dependencies,
     ) {
         match self {
-            &Type::ConstPtr(ref ty) => {
-                ty.add_dependencies_ignoring_generics(generic_params, library, out);
-            }
-            &Type::Ptr(ref ty) => {
-                ty.add_dependencies_ignoring_generics(generic_params, library, out);
+            &Type::ConstPtr(ref ty, _) | &Type::Ptr(ref ty, _) => {
+                out.with_indirection(|out| {
+                    ty.add_dependencies_ignoring_generics(generic_params, library, out);
+                });
             }
             &Type::Ref(ref ty) | &Type::MutRef(ref ty) => {
-                ty.add_dependencies_ignoring_generics(generic_params, library, out);
+                out.with_indirection(|out| {
+                    ty.add_dependencies_ignoring_generics(generic_params, library, out);
+                });
             }
             &Type::Path(ref generic) => {
-                for generic_value in generic.generics() {
-                    generic_value.add_dependencies_ignoring_generics(generic_params, library, out);
-                }
                 let path = generic.path();
+                let items = if generic_params.contains(path) {
+                    None
+                } else {
+                    library.get_items(path)
+                };
+                for (index, generic_value) in generic.generics().iter().enumerate() {
+                    // An instantiation only needs the arguments that the
+                    // template stores by value to be complete, so that
+                    // `Buffer<Node>` can be a field of `Node` if `Buffer<T>`
+                    // only has pointers to `T`.
+                    let by_value = items.as_ref().map_or(true, |items| {
+                        items.iter().any(|x| stores_by_value(x, index))
+                    });
+                    if by_value {
+                        generic_value.add_dependencies_ignoring_generics(
+                            generic_params,
+                            library,
+                            out,
+                        );
+                    } else {
+                        out.with_indirection(|out| {
+                            generic_value.add_dependencies_ignoring_generics(
+                                generic_params,
+                                library,
+                                out,
+                            );
+                        });
+                    }
+                }
                 if !generic_params.contains(path) {
-                    if let Some(items) = library.get_items(path) {
-                        if !out.items.contains(path) {
-                            out.items.insert(path.clone());
-
-                            for item in &items {
-                                item.deref().add_dependencies(library, out);
-                            }
+                    if let Some(items) = items {
+                        if out.insert(path) {
+                            out.with_referrer(Referrer::Item(path.clone()), |out| {
+                                for item in &items {
+                                    item.deref().add_dependencies(library, out);
+                                }
+                            });
                             for item in items {
                                 out.order.push(item);
                             }
@@ -521,24 +712,46 @@ impl Type {
                 ty.add_dependencies_ignoring_generics(generic_params, library, out);
             }
             &Type::FuncPtr(ref ret, ref args) => {
-                ret.add_dependencies_ignoring_generics(generic_params, library, out);
-                for (_, ref arg) in args {
-                    arg.add_dependencies_ignoring_generics(generic_params, library, out);
+                out.with_indirection(|out| {
+                    ret.add_dependencies_ignoring_generics(generic_params, library, out);
+                    for (_, ref arg) in args {
+                        arg.add_dependencies_ignoring_generics(generic_params, library, out);
+                    }
+                });
+            }
+            &Type::ConstFatPtr(ref pointee) | &Type::FatPtr(ref pointee) => {
+                if let Some(ty) = pointee.elem() {
+                    out.with_indirection(|out| {
+                        ty.add_dependencies_ignoring_generics(generic_params, library, out);
+                    });
                 }
             }
         }
     }
 
+    /// Whether the generic parameter `param` is part of this type by value,
+    /// rather than behind a pointer. Arguments of other generic types are
+    /// assumed to be stored by value.
+    fn has_by_value(&self, param: &Path) -> bool {
+        match *self {
+            Type::Path(ref generic) => {
+                generic.path() == param || generic.generics().iter().any(|x| x.has_by_value(param))
+            }
+            Type::Array(ref ty, _) => ty.has_by_value(param),
+            _ => false,
+        }
+    }
+
     pub fn add_dependencies(&self, library: &Library, out: &mut Dependencies) {
         self.add_dependencies_ignoring_generics(&GenericParams::default(), library, out)
     }
 
     pub fn add_monomorphs(&self, library: &Library, out: &mut Monomorphs) {
         match self {
-            &Type::ConstPtr(ref ty) => {
+            &Type::ConstPtr(ref ty, _) => {
                 ty.add_monomorphs(library, out);
             }
-            &Type::Ptr(ref ty) => {
+            &Type::Ptr(ref ty, _) => {
                 ty.add_monomorphs(library, out);
             }
             &Type::Ref(ref ty) | &Type::MutRef(ref ty) => {
@@ -566,15 +779,20 @@ impl Type {
                     arg.add_monomorphs(library, out);
                 }
             }
+            &Type::ConstFatPtr(ref pointee) | &Type::FatPtr(ref pointee) => {
+                if let Some(ty) = pointee.elem() {
+                    ty.add_monomorphs(library, out);
+                }
+            }
         }
     }
 
     pub fn rename_for_config(&mut self, config: &Config, generic_params: &GenericParams) {
         match self {
-            &mut Type::ConstPtr(ref mut ty) => {
+            &mut Type::ConstPtr(ref mut ty, _) => {
                 ty.rename_for_config(config, generic_params);
             }
-            &mut Type::Ptr(ref mut ty) => {
+            &mut Type::Ptr(ref mut ty, _) => {
                 ty.rename_for_config(config, generic_params);
             }
             &mut Type::Ref(ref mut ty) | &mut Type::MutRef(ref mut ty) => {
@@ -594,15 +812,20 @@ impl Type {
                     arg.rename_for_config(config, generic_params);
                 }
             }
+            &mut Type::ConstFatPtr(ref mut pointee) | &mut Type::FatPtr(ref mut pointee) => {
+                if let Some(ty) = pointee.elem_mut() {
+                    ty.rename_for_config(config, generic_params);
+                }
+            }
         }
     }
 
     pub fn resolve_declaration_types(&mut self, resolver: &DeclarationTypeResolver) {
         match self {
-            &mut Type::ConstPtr(ref mut ty) => {
+            &mut Type::ConstPtr(ref mut ty, _) => {
                 ty.resolve_declaration_types(resolver);
             }
-            &mut Type::Ptr(ref mut ty) => {
+            &mut Type::Ptr(ref mut ty, _) => {
                 ty.resolve_declaration_types(resolver);
             }
             &mut Type::Ref(ref mut ty) | &mut Type::MutRef(ref mut ty) => {
@@ -621,15 +844,20 @@ impl Type {
                     arg.resolve_declaration_types(resolver);
                 }
             }
+            &mut Type::ConstFatPtr(ref mut pointee) | &mut Type::FatPtr(ref mut pointee) => {
+                if let Some(ty) = pointee.elem_mut() {
+                    ty.resolve_declaration_types(resolver);
+                }
+            }
         }
     }
 
     pub fn mangle_paths(&mut self, monomorphs: &Monomorphs) {
         match self {
-            &mut Type::ConstPtr(ref mut ty) => {
+            &mut Type::ConstPtr(ref mut ty, _) => {
                 ty.mangle_paths(monomorphs);
             }
-            &mut Type::Ptr(ref mut ty) => {
+            &mut Type::Ptr(ref mut ty, _) => {
                 ty.mangle_paths(monomorphs);
             }
             &mut Type::Ref(ref mut ty) | &mut Type::MutRef(ref mut ty) => {
@@ -640,6 +868,13 @@ impl Type {
                     return;
                 }
 
+                if monomorphs.is_mapped(generic_path) {
+                    for generic in generic_path.generics_mut() {
+                        generic.mangle_paths(monomorphs);
+                    }
+                    return;
+                }
+
                 if let Some(mangled_path) = monomorphs.mangle_path(&generic_path) {
                     *generic_path = GenericPath::new(mangled_path.clone(), vec![]);
                 } else {
@@ -660,9 +895,57 @@ impl Type {
                     arg.mangle_paths(monomorphs);
                 }
             }
+            &mut Type::ConstFatPtr(ref mut pointee) | &mut Type::FatPtr(ref mut pointee) => {
+                if let Some(ty) = pointee.elem_mut() {
+                    ty.mangle_paths(monomorphs);
+                }
+            }
         }
     }
 
+    pub fn lower_fat_pointers(
+        &mut self,
+        generic_params: &GenericParams,
+        config: &Config,
+        out: &mut FatPointers,
+    ) {
+        match self {
+            &mut Type::ConstPtr(ref mut ty, _)
+            | &mut Type::Ptr(ref mut ty, _)
+            | &mut Type::Ref(ref mut ty)
+            | &mut Type::MutRef(ref mut ty)
+            | &mut Type::Array(ref mut ty, _) => {
+                ty.lower_fat_pointers(generic_params, config, out);
+            }
+            &mut Type::Path(ref mut generic_path) => {
+                for generic in generic_path.generics_mut() {
+                    generic.lower_fat_pointers(generic_params, config, out);
+                }
+            }
+            &mut Type::Primitive(_) => {}
+            &mut Type::FuncPtr(ref mut ret, ref mut args) => {
+                ret.lower_fat_pointers(generic_params, config, out);
+                for (_, ref mut arg) in args {
+                    arg.lower_fat_pointers(generic_params, config, out);
+                }
+            }
+            &mut Type::ConstFatPtr(ref mut pointee) | &mut Type::FatPtr(ref mut pointee) => {
+                if let Some(ty) = pointee.elem_mut() {
+                    ty.lower_fat_pointers(generic_params, config, out);
+                }
+            }
+        }
+
+        let lowered = match self {
+            &mut Type::ConstFatPtr(ref pointee) => {
+                out.lower(pointee, false, generic_params, config)
+            }
+            &mut Type::FatPtr(ref pointee) => out.lower(pointee, true, generic_params, config),
+            _ => return,
+        };
+        *self = lowered;
+    }
+
     pub fn can_cmp_order(&self) -> bool {
         match self {
             &Type::ConstPtr(..) => true,
@@ -672,6 +955,7 @@ impl Type {
             &Type::Primitive(ref p) => p.can_cmp_order(),
             &Type::Array(..) => false,
             &Type::FuncPtr(..) => false,
+            &Type::ConstFatPtr(..) | &Type::FatPtr(..) => false,
         }
     }
 
@@ -684,6 +968,7 @@ impl Type {
             &Type::Primitive(ref p) => p.can_cmp_eq(),
             &Type::Array(..) => false,
             &Type::FuncPtr(..) => true,
+            &Type::ConstFatPtr(..) | &Type::FatPtr(..) => false,
         }
     }
 }
@@ -712,3 +997,32 @@ impl Source for (String, Type, Documentation) {
         cdecl::write_field(out, &self.1, &self.0);
     }
 }
+
+/// Whether the generic `item` stores its generic parameter at `index` by
+/// value, assuming it does for items whose layout isn't known.
+fn stores_by_value(item: &ItemContainer, index: usize) -> bool {
+    let fields_store = |generic_params: &GenericParams,
+                        fields: &[(String, Type, Documentation)]| {
+        match generic_params.0.get(index) {
+            Some(param) => fields.iter().any(|x| x.1.has_by_value(param)),
+            None => true,
+        }
+    };
+    match *item {
+        ItemContainer::Struct(ref x) => fields_store(&x.generic_params, &x.fields),
+        ItemContainer::Union(ref x) => fields_store(&x.generic_params, &x.fields),
+        ItemContainer::Enum(ref x) => x
+            .variants
+            .iter()
+            .filter_map(|x| x.body.as_ref())
+            .any(|&(_, ref body)| fields_store(&x.generic_params, &body.fields)),
+        ItemContainer::Typedef(ref x) => match x.generic_params.0.get(index) {
+            Some(param) => x.aliased.has_by_value(param),
+            None => true,
+        },
+        ItemContainer::OpaqueItem(..) => false,
+        ItemContainer::Constant(..) | ItemContainer::Static(..) | ItemContainer::MappedType(..) => {
+            true
+        }
+    }
+}
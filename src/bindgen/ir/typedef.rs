@@ -10,6 +10,7 @@ use syn;
 use bindgen::config::{Config, Language};
 use bindgen::declarationtyperesolver::DeclarationTypeResolver;
 use bindgen::dependencies::Dependencies;
+use bindgen::fatptr::FatPointers;
 use bindgen::ir::{
     AnnotationSet, Cfg, ConditionWrite, Documentation, GenericParams, Item, ItemContainer, Path,
     ToCondition, Type,
@@ -20,7 +21,7 @@ use bindgen::monomorph::Monomorphs;
 use bindgen::writer::{Source, SourceWriter};
 
 /// A type alias that is represented as a C typedef
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Typedef {
     pub path: Path,
     pub export_name: String,
@@ -72,6 +73,11 @@ impl Typedef {
         self.aliased.simplify_standard_types();
     }
 
+    pub fn lower_fat_pointers(&mut self, config: &Config, out: &mut FatPointers) {
+        self.aliased
+            .lower_fat_pointers(&self.generic_params, config, out);
+    }
+
     pub fn transfer_annotations(&mut self, out: &mut HashMap<Path, AnnotationSet>) {
         if self.annotations.is_empty() {
             return;
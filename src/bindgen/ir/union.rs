@@ -9,6 +9,7 @@ use syn;
 use bindgen::config::{Config, Language};
 use bindgen::declarationtyperesolver::DeclarationTypeResolver;
 use bindgen::dependencies::Dependencies;
+use bindgen::fatptr::FatPointers;
 use bindgen::ir::SynFieldHelpers;
 use bindgen::ir::{
     AnnotationSet, Cfg, ConditionWrite, Documentation, GenericParams, Item, ItemContainer, Path,
@@ -21,13 +22,16 @@ use bindgen::rename::{IdentifierType, RenameRule};
 use bindgen::utilities::{find_first_some, IterHelpers};
 use bindgen::writer::{ListType, Source, SourceWriter};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Union {
     pub path: Path,
     pub export_name: String,
     pub generic_params: GenericParams,
     pub fields: Vec<(String, Type, Documentation)>,
     pub tuple_union: bool,
+    /// Whether the union is declared before it's defined, because items
+    /// before it refer to it. It's then defined through its tag in C.
+    pub is_forward_declared: bool,
     pub cfg: Option<Cfg>,
     pub annotations: AnnotationSet,
     pub documentation: Documentation,
@@ -75,18 +79,46 @@ impl Union {
             generic_params,
             fields,
             tuple_union,
+            is_forward_declared: false,
             cfg,
             annotations,
             documentation,
         }
     }
 
+    /// Writes a declaration of the union, for the items that refer to it
+    /// before it's defined.
+    pub fn write_forward_declaration<F: Write>(&self, config: &Config, out: &mut SourceWriter<F>) {
+        let condition = (&self.cfg).to_condition(config);
+        condition.write_before(config, out);
+
+        self.generic_params.write(config, out);
+
+        if config.language == Language::C && config.style.generate_typedef() {
+            write!(
+                out,
+                "typedef union {} {};",
+                self.export_name, self.export_name
+            );
+        } else {
+            write!(out, "union {};", self.export_name);
+        }
+
+        condition.write_after(config, out);
+    }
+
     pub fn simplify_standard_types(&mut self) {
         for &mut (_, ref mut ty, _) in &mut self.fields {
             ty.simplify_standard_types();
         }
     }
 
+    pub fn lower_fat_pointers(&mut self, config: &Config, out: &mut FatPointers) {
+        for &mut (_, ref mut ty, _) in &mut self.fields {
+            ty.lower_fat_pointers(&self.generic_params, config, out);
+        }
+    }
+
     pub fn is_generic(&self) -> bool {
         self.generic_params.len() > 0
     }
@@ -257,13 +289,16 @@ impl Source for Union {
         //   typedef union {
         // C with Both as style:
         //   typedef union Name {
-        if config.language == Language::C && config.style.generate_typedef() {
+        let typedef = config.language == Language::C
+            && config.style.generate_typedef()
+            && !self.is_forward_declared;
+        if typedef {
             out.write("typedef ");
         }
 
         out.write("union");
 
-        if config.language == Language::Cxx || config.style.generate_tag() {
+        if !typedef || config.style.generate_tag() {
             write!(out, " {}", self.export_name);
         }
 
@@ -284,7 +319,7 @@ impl Source for Union {
             out.write_raw_block(body);
         }
 
-        if config.language == Language::C && config.style.generate_typedef() {
+        if typedef {
             out.close_brace(false);
             write!(out, " {};", self.export_name);
         } else {
@@ -2,17 +2,22 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::mem;
+use std::path::PathBuf;
 
 use bindgen::bindings::Bindings;
-use bindgen::config::{Config, Language};
+use bindgen::config::{Config, FlexibleArrayStyle, Language};
 use bindgen::declarationtyperesolver::DeclarationTypeResolver;
 use bindgen::dependencies::Dependencies;
 use bindgen::error::Error;
-use bindgen::ir::{Constant, Enum, Function, Item, ItemContainer, ItemMap};
-use bindgen::ir::{OpaqueItem, Path, Static, Struct, Typedef, Union};
+use bindgen::explain::{Explainer, ParseNote, Referrer};
+use bindgen::fatptr::FatPointers;
+use bindgen::filter::{Filter, ItemPaths};
+use bindgen::ir::{Constant, Enum, Function, Item, ItemContainer, ItemMap, MappedType, Method};
+use bindgen::ir::{OpaqueItem, Path, Static, Struct, Type, Typedef, Union};
 use bindgen::monomorph::Monomorphs;
+use bindgen::visitor::{ItemVisitors, VisitedItem};
 use bindgen::ItemType;
 
 #[derive(Debug, Clone)]
@@ -25,7 +30,18 @@ pub struct Library {
     unions: ItemMap<Union>,
     opaque_items: ItemMap<OpaqueItem>,
     typedefs: ItemMap<Typedef>,
+    mapped_types: ItemMap<MappedType>,
     functions: Vec<Function>,
+    /// The C++ member functions of structs and opaque items, by their name in
+    /// rust.
+    methods: HashMap<String, Vec<Method>>,
+    source_files: Vec<PathBuf>,
+    /// The files that modules which weren't found could be in.
+    missing_files: Vec<PathBuf>,
+    notes: Vec<ParseNote>,
+    visitors: ItemVisitors,
+    /// What the items that are synthesized rather than parsed are for.
+    origins: HashMap<Path, String>,
 }
 
 impl Library {
@@ -39,7 +55,28 @@ impl Library {
         opaque_items: ItemMap<OpaqueItem>,
         typedefs: ItemMap<Typedef>,
         functions: Vec<Function>,
+        source_files: Vec<PathBuf>,
+        missing_files: Vec<PathBuf>,
+        notes: Vec<ParseNote>,
     ) -> Library {
+        let mut mapped_types = ItemMap::new();
+        let mut patterns: Vec<_> = config.export.type_map.iter().collect();
+        patterns.sort_by_key(|&(pattern, _)| pattern);
+        for (pattern, mapping) in patterns {
+            match MappedType::load(pattern, mapping) {
+                Ok(mapped_type) => {
+                    let path = mapped_type.path.clone();
+                    if !mapped_types.try_insert(mapped_type) {
+                        warn!(
+                            "Skip type mapping {} - (there's already a mapping for {}).",
+                            pattern, path
+                        );
+                    }
+                }
+                Err(msg) => warn!("Skip type mapping {} - ({})", pattern, msg),
+            }
+        }
+
         Library {
             config: config,
             constants: constants,
@@ -49,53 +86,113 @@ impl Library {
             unions: unions,
             opaque_items: opaque_items,
             typedefs: typedefs,
+            mapped_types: mapped_types,
             functions: functions,
+            methods: HashMap::new(),
+            origins: HashMap::new(),
+            source_files: source_files,
+            missing_files,
+            notes: notes,
+            visitors: ItemVisitors::default(),
         }
     }
 
+    pub(crate) fn with_item_visitors(mut self, visitors: ItemVisitors) -> Library {
+        self.visitors = visitors;
+        self
+    }
+
     pub fn generate(mut self) -> Result<Bindings, Error> {
-        self.remove_excluded();
+        let include = Filter::new("[export] include", &self.config.export.include)?;
+        let exclude = Filter::new("[export] exclude", &self.config.export.exclude)?;
+        let paths = ItemPaths::new(&self.notes);
+
+        self.evaluate_discriminants();
+        self.remove_shadowing_mappings(&paths);
+        self.remove_excluded(&exclude, &paths);
         self.functions.sort_by(|x, y| x.path.cmp(&y.path));
         self.transfer_annotations();
+        self.visit_items();
         self.simplify_standard_types();
 
         if self.config.language == Language::C {
             self.instantiate_monomorphs();
+        }
+        self.lower_fat_pointers();
+        if self.config.language == Language::C {
             self.resolve_declaration_types();
         }
+        self.find_embedded_structs();
 
         self.rename_items();
+        self.find_methods();
+        self.warn_constants_out_of_body();
 
         let mut dependencies = Dependencies::new();
 
         for function in &self.functions {
-            function.add_dependencies(&self, &mut dependencies);
+            dependencies.with_referrer(Referrer::Function(function.path.clone()), |out| {
+                function.add_dependencies(&self, out);
+            });
         }
         self.globals.for_all_items(|global| {
-            global.add_dependencies(&self, &mut dependencies);
+            dependencies.with_referrer(Referrer::Static(global.path.clone()), |out| {
+                global.add_dependencies(&self, out);
+            });
         });
         self.constants.for_all_items(|constant| {
-            constant.add_dependencies(&self, &mut dependencies);
+            dependencies.with_referrer(Referrer::Constant(constant.path.clone()), |out| {
+                constant.add_dependencies(&self, out);
+            });
         });
-        for name in &self.config.export.include {
-            let path = Path::new(name.clone());
+        for path in self.included_items(&include, &paths) {
             if let Some(items) = self.get_items(&path) {
-                if !dependencies.items.contains(&path) {
-                    dependencies.items.insert(path);
-
-                    for item in &items {
-                        item.deref().add_dependencies(&self, &mut dependencies);
+                dependencies.with_referrer(Referrer::Include, |out| {
+                    if out.insert(&path) {
+                        out.with_referrer(Referrer::Item(path.clone()), |out| {
+                            for item in &items {
+                                item.deref().add_dependencies(&self, out);
+                            }
+                        });
+                        for item in items {
+                            out.order.push(item);
+                        }
                     }
-                    for item in items {
-                        dependencies.order.push(item);
+                });
+            }
+        }
+
+        dependencies.sort()?;
+
+        let mut items = dependencies.order;
+        for item in &mut items {
+            match *item {
+                ItemContainer::Struct(ref mut x)
+                    if dependencies.forward_declarations.contains(&x.path) =>
+                {
+                    if x.is_transparent {
+                        warn!(
+                            "Can't declare {} before it's defined, as it's transparent.",
+                            x.path
+                        );
+                    } else {
+                        x.is_forward_declared = true;
                     }
                 }
+                ItemContainer::Union(ref mut x)
+                    if dependencies.forward_declarations.contains(&x.path) =>
+                {
+                    x.is_forward_declared = true;
+                }
+                ref x if dependencies.forward_declarations.contains(x.deref().path()) => {
+                    warn!(
+                        "Can't declare {} before it's defined, only structs and unions can be.",
+                        x.deref().path()
+                    );
+                }
+                _ => {}
             }
         }
-
-        dependencies.sort();
-
-        let items = dependencies.order;
         let constants = if self.config.export.should_generate(ItemType::Constants) {
             self.constants.to_vec()
         } else {
@@ -113,16 +210,122 @@ impl Library {
             vec![]
         };
 
+        if self.config.language == Language::Cxx && self.config.module_namespaces.is_enabled() {
+            let paths = items
+                .iter()
+                .map(|x| x.deref().path())
+                .chain(constants.iter().map(|x| &x.path))
+                .chain(globals.iter().map(|x| &x.path))
+                .chain(functions.iter().map(|x| &x.path));
+            for path in paths {
+                self.check_module_namespaces(path.name())?;
+            }
+        }
+
         Ok(Bindings::new(
             self.config,
             self.structs,
+            self.mapped_types,
             constants,
             globals,
             items,
             functions,
+            self.methods,
+            self.source_files,
+            self.missing_files,
+            Explainer::new(
+                self.notes,
+                dependencies.referrers,
+                self.origins,
+                include,
+                exclude,
+            ),
+            dependencies.references,
         ))
     }
 
+    /// Fails if the items named `name` are in modules with different
+    /// namespaces, as they'd all be written in the namespace of the first.
+    fn check_module_namespaces(&self, name: &str) -> Result<(), Error> {
+        let mut namespaces: Vec<String> = Vec::new();
+        for note in &self.notes {
+            if note.name != name || note.item_type().is_none() {
+                continue;
+            }
+            let namespace = self
+                .config
+                .module_namespaces
+                .namespace(&note.crate_name, note.binding_crate, &note.module)
+                .join("::");
+            if !namespaces.contains(&namespace) {
+                namespaces.push(namespace);
+            }
+        }
+        if namespaces.len() > 1 {
+            return Err(Error::ConflictingModuleNamespaces {
+                name: name.to_owned(),
+                namespaces: namespaces
+                    .into_iter()
+                    .map(|x| {
+                        if x.is_empty() {
+                            "the root one".to_owned()
+                        } else {
+                            format!("`{}`", x)
+                        }
+                    })
+                    .collect(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The C++ member functions of the struct or opaque item at `path`.
+    pub fn methods(&self, path: &Path) -> &[Method] {
+        self.methods.get(path.name()).map(|x| &x[..]).unwrap_or(&[])
+    }
+
+    fn find_methods(&mut self) {
+        if self.config.language != Language::Cxx
+            || !self.config.export.should_generate(ItemType::Functions)
+        {
+            return;
+        }
+
+        let mut classes = HashMap::new();
+        self.structs.for_all_items(|x| {
+            if !x.is_generic() && !x.is_transparent {
+                classes.insert(x.path.name().to_owned(), x.annotations.clone());
+            }
+        });
+        self.opaque_items.for_all_items(|x| {
+            if x.generic_params.is_empty() {
+                classes.insert(x.path.name().to_owned(), x.annotations.clone());
+            }
+        });
+        self.methods = Method::find(&self.config, &classes, &self.functions);
+    }
+
+    /// Warns about the associated constants that `associated_constants_in_body`
+    /// can't put in the body of their struct, as it can't have macros.
+    fn warn_constants_out_of_body(&self) {
+        if self.config.language != Language::Cxx
+            || !self.config.structure.associated_constants_in_body
+        {
+            return;
+        }
+        self.structs.for_all_items(|x| {
+            for constant in &x.associated_constants {
+                if !x.is_transparent && !constant.is_in_body(&self.config, Some(x)) {
+                    warn!(
+                        "Writing {}::{} after the struct rather than in its body, as it's a #define.",
+                        x.path,
+                        constant.path.name()
+                    );
+                }
+            }
+        });
+    }
+
     pub fn get_items(&self, p: &Path) -> Option<Vec<ItemContainer>> {
         macro_rules! find {
             ($field:ident, $kind:ident) => {
@@ -134,6 +337,12 @@ impl Library {
             };
         }
 
+        // Mapped types take precedence over any items with the same name, as
+        // the ones that don't refer to them have been removed.
+        if let Some(x) = self.mapped_types.get_items(p) {
+            return Some(x);
+        }
+
         find!(enums, Enums);
         find!(structs, Structs);
         find!(unions, Unions);
@@ -143,25 +352,102 @@ impl Library {
         None
     }
 
-    fn remove_excluded(&mut self) {
-        let config = &self.config;
-        // FIXME: interpret `config.export.exclude` as `Path`s.
-        self.functions
-            .retain(|x| !config.export.exclude.iter().any(|y| y == x.path().name()));
-        self.enums
-            .filter(|x| config.export.exclude.iter().any(|y| y == x.path().name()));
-        self.structs
-            .filter(|x| config.export.exclude.iter().any(|y| y == x.path().name()));
-        self.unions
-            .filter(|x| config.export.exclude.iter().any(|y| y == x.path().name()));
+    /// The items to add for `[export] include`, in the order of its rules.
+    fn included_items(&self, include: &Filter, paths: &ItemPaths) -> Vec<Path> {
+        let mut names = Vec::new();
+        self.enums.for_all_items(|x| names.push(x.path().clone()));
+        self.structs.for_all_items(|x| names.push(x.path().clone()));
+        self.unions.for_all_items(|x| names.push(x.path().clone()));
         self.opaque_items
-            .filter(|x| config.export.exclude.iter().any(|y| y == x.path().name()));
+            .for_all_items(|x| names.push(x.path().clone()));
         self.typedefs
-            .filter(|x| config.export.exclude.iter().any(|y| y == x.path().name()));
-        self.globals
-            .filter(|x| config.export.exclude.iter().any(|y| y == x.path().name()));
-        self.constants
-            .filter(|x| config.export.exclude.iter().any(|y| y == x.path().name()));
+            .for_all_items(|x| names.push(x.path().clone()));
+        names.sort();
+        names.dedup();
+
+        let mut included = Vec::new();
+        for rule in include.rules() {
+            if let Some(name) = rule.name() {
+                included.push(Path::new(name));
+                continue;
+            }
+            for path in &names {
+                if rule.matches(path.name(), paths.get(path.name())) && !included.contains(path) {
+                    info!(
+                        "Including `{}`, as it matches `{}` in `[export] include`.",
+                        path, rule
+                    );
+                    included.push(path.clone());
+                }
+            }
+        }
+        included
+    }
+
+    /// Evaluates the discriminants of enums, including the ones that refer to
+    /// constants, which can be excluded from the bindings themselves.
+    fn evaluate_discriminants(&mut self) {
+        let mut values = HashMap::new();
+        self.constants.for_all_items(|x| {
+            if x.associated_to.is_none() {
+                values
+                    .entry(x.path.name().to_owned())
+                    .or_insert_with(Vec::new)
+                    .push(x.value.integer_value());
+            }
+        });
+
+        // Constants with several `#[cfg]`s don't have a single value.
+        let constant = |name: &str| match values.get(name) {
+            Some(values) if values.len() == 1 => values[0],
+            _ => None,
+        };
+        self.enums
+            .for_all_items_mut(|x| x.evaluate_discriminants(&constant));
+    }
+
+    /// Removes the mappings of qualified paths, like `libc::timespec`, whose
+    /// name is that of a parsed item with another path, so that they don't
+    /// replace it. Mappings of unqualified names replace any item with that
+    /// name.
+    fn remove_shadowing_mappings(&mut self, paths: &ItemPaths) {
+        self.mapped_types.filter(|x| match x.qualified_path {
+            Some(ref qualified_path) => {
+                let item_paths = paths.get(x.path.name());
+                let shadows = !item_paths.is_empty() && !item_paths.contains(qualified_path);
+                if shadows {
+                    warn!(
+                        "Skip type mapping {} - (it would replace {}, which has the same name).",
+                        qualified_path,
+                        item_paths.join(", ")
+                    );
+                }
+                shadows
+            }
+            None => false,
+        });
+    }
+
+    fn remove_excluded(&mut self, exclude: &Filter, paths: &ItemPaths) {
+        let is_excluded = |path: &Path| match exclude.find(path.name(), paths.get(path.name())) {
+            Some(rule) => {
+                info!(
+                    "Excluding `{}`, as it matches `{}` in `[export] exclude`.",
+                    path, rule
+                );
+                true
+            }
+            None => false,
+        };
+
+        self.functions.retain(|x| !is_excluded(x.path()));
+        self.enums.filter(|x| is_excluded(x.path()));
+        self.structs.filter(|x| is_excluded(x.path()));
+        self.unions.filter(|x| is_excluded(x.path()));
+        self.opaque_items.filter(|x| is_excluded(x.path()));
+        self.typedefs.filter(|x| is_excluded(x.path()));
+        self.globals.filter(|x| is_excluded(x.path()));
+        self.constants.filter(|x| is_excluded(x.path()));
     }
 
     fn transfer_annotations(&mut self) {
@@ -253,7 +539,106 @@ impl Library {
         }
     }
 
+    /// Runs the item visitors of the builder on each item, then renames and
+    /// removes the items they asked to.
+    fn visit_items(&mut self) {
+        if self.visitors.is_empty() {
+            return;
+        }
+
+        let mut renamed = Vec::new();
+        let mut removed = Vec::new();
+        {
+            let visitors = &self.visitors;
+            let mut visit = |mut item: VisitedItem| {
+                visitors.visit(&mut item);
+                if let Some(name) = item.new_name.take() {
+                    renamed.push((item.name().to_owned(), name));
+                }
+                if item.removed {
+                    removed.push((item.item_type().clone(), item.name().to_owned()));
+                }
+            };
+            macro_rules! visit {
+                ($items:expr, $item_type:ident) => {
+                    $items.for_all_items_mut(|x| {
+                        visit(VisitedItem::new(
+                            ItemType::$item_type,
+                            &x.path,
+                            &mut x.annotations,
+                            &mut x.documentation,
+                        ))
+                    });
+                };
+            }
+            visit!(self.constants, Constants);
+            visit!(self.globals, Globals);
+            visit!(self.enums, Enums);
+            visit!(self.structs, Structs);
+            visit!(self.unions, Unions);
+            visit!(self.opaque_items, OpaqueItems);
+            visit!(self.typedefs, Typedefs);
+            for x in &mut self.functions {
+                visit(VisitedItem::new(
+                    ItemType::Functions,
+                    &x.path,
+                    &mut x.annotations,
+                    &mut x.documentation,
+                ));
+            }
+        }
+
+        for (name, new_name) in renamed {
+            self.config.export.visitor_renames.insert(name.clone());
+            self.config.export.rename.insert(name, new_name);
+        }
+        if removed.is_empty() {
+            return;
+        }
+        let is_removed = |item_type: ItemType, path: &Path| {
+            let removed = removed.contains(&(item_type, path.name().to_owned()));
+            if removed {
+                info!("Removing `{}`, as an item visitor asked to.", path);
+            }
+            removed
+        };
+        self.functions
+            .retain(|x| !is_removed(ItemType::Functions, x.path()));
+        self.constants
+            .filter(|x| is_removed(ItemType::Constants, x.path()));
+        self.globals
+            .filter(|x| is_removed(ItemType::Globals, x.path()));
+        self.enums.filter(|x| is_removed(ItemType::Enums, x.path()));
+        self.structs
+            .filter(|x| is_removed(ItemType::Structs, x.path()));
+        self.unions
+            .filter(|x| is_removed(ItemType::Unions, x.path()));
+        self.opaque_items
+            .filter(|x| is_removed(ItemType::OpaqueItems, x.path()));
+        self.typedefs
+            .filter(|x| is_removed(ItemType::Typedefs, x.path()));
+    }
+
     fn rename_items(&mut self) {
+        // Types are renamed where they're used too, where only their name is
+        // known.
+        let mut type_item_types = HashMap::new();
+        {
+            let mut add = |path: &Path, item_type: ItemType| {
+                type_item_types.insert(path.name().to_owned(), item_type);
+            };
+            self.enums.for_all_items(|x| add(x.path(), ItemType::Enums));
+            self.structs
+                .for_all_items(|x| add(x.path(), ItemType::Structs));
+            self.unions
+                .for_all_items(|x| add(x.path(), ItemType::Unions));
+            self.opaque_items
+                .for_all_items(|x| add(x.path(), ItemType::OpaqueItems));
+            self.typedefs
+                .for_all_items(|x| add(x.path(), ItemType::Typedefs));
+        }
+        self.config.export.type_item_types = type_item_types;
+
         let config = &self.config;
 
         self.globals
@@ -289,6 +674,62 @@ impl Library {
         }
     }
 
+    /// Marks the structs that are fields of other structs or unions, or
+    /// elements of arrays, by value, so that they keep their trailing
+    /// zero-length array rather than having a flexible array member, which C
+    /// doesn't allow there.
+    fn find_embedded_structs(&mut self) {
+        let mut embedded = HashSet::new();
+        {
+            let mut add = |ty: &Type, by_value: bool| {
+                if let Some(name) = embedded_name(ty, by_value) {
+                    embedded.insert(name);
+                }
+            };
+            self.structs.for_all_items(|x| {
+                for field in &x.fields {
+                    add(&field.1, true);
+                }
+            });
+            self.unions.for_all_items(|x| {
+                for field in &x.fields {
+                    add(&field.1, true);
+                }
+            });
+            self.enums.for_all_items(|x| {
+                for variant in &x.variants {
+                    if let Some((_, ref body)) = variant.body {
+                        for field in &body.fields {
+                            add(&field.1, true);
+                        }
+                    }
+                }
+            });
+            self.globals.for_all_items(|x| add(&x.ty, false));
+            self.typedefs.for_all_items(|x| add(&x.aliased, false));
+            for function in &self.functions {
+                for arg in &function.args {
+                    add(&arg.1, false);
+                }
+            }
+        }
+
+        let flexible = self.config.structure.flexible_array(&self.config.language)
+            == FlexibleArrayStyle::Flexible;
+        self.structs.for_all_items_mut(|x| {
+            if !embedded.contains(x.path.name()) {
+                return;
+            }
+            if flexible && x.has_flexible_array() {
+                warn!(
+                    "{} is embedded in another struct or in an array, so its trailing zero-length array is declared with a length of 0.",
+                    x.path
+                );
+            }
+            x.is_embedded = true;
+        });
+    }
+
     fn resolve_declaration_types(&mut self) {
         if self.config.style.generate_typedef() {
             return;
@@ -339,6 +780,9 @@ impl Library {
         self.unions.for_all_items_mut(|x| {
             x.simplify_standard_types();
         });
+        self.enums.for_all_items_mut(|x| {
+            x.simplify_standard_types();
+        });
         self.globals.for_all_items_mut(|x| {
             x.simplify_standard_types();
         });
@@ -350,6 +794,42 @@ impl Library {
         }
     }
 
+    fn lower_fat_pointers(&mut self) {
+        let config = &self.config;
+        let mut fat_pointers = FatPointers::default();
+
+        self.structs.for_all_items_mut(|x| {
+            x.lower_fat_pointers(config, &mut fat_pointers);
+        });
+        self.unions.for_all_items_mut(|x| {
+            x.lower_fat_pointers(config, &mut fat_pointers);
+        });
+        self.enums.for_all_items_mut(|x| {
+            x.lower_fat_pointers(config, &mut fat_pointers);
+        });
+        self.typedefs.for_all_items_mut(|x| {
+            x.lower_fat_pointers(config, &mut fat_pointers);
+        });
+        self.globals.for_all_items_mut(|x| {
+            x.lower_fat_pointers(config, &mut fat_pointers);
+        });
+        for x in &mut self.functions {
+            x.lower_fat_pointers(config, &mut fat_pointers);
+        }
+
+        self.origins.extend(fat_pointers.origins());
+        for fat_pointer in fat_pointers.drain_structs() {
+            let path = fat_pointer.path.clone();
+            if !self.structs.try_insert(fat_pointer) {
+                error!(
+                    "Can't generate fat pointer struct {} as there's already a struct with \
+                     that name.",
+                    path
+                );
+            }
+        }
+    }
+
     fn instantiate_monomorphs(&mut self) {
         // Collect a list of monomorphs
         let mut monomorphs = Monomorphs::default();
@@ -371,6 +851,7 @@ impl Library {
         }
 
         // Insert the monomorphs into self
+        self.origins.extend(monomorphs.origins());
         for monomorph in monomorphs.drain_structs() {
             self.structs.try_insert(monomorph);
         }
@@ -408,3 +889,13 @@ impl Library {
         }
     }
 }
+
+/// The name of the item a type embeds by value, if it's a field, and if it's
+/// an array of them otherwise.
+fn embedded_name(ty: &Type, by_value: bool) -> Option<String> {
+    match *ty {
+        Type::Path(ref generic) if by_value => Some(generic.name().to_owned()),
+        Type::Array(ref ty, _) => embedded_name(ty, true),
+        _ => None,
+    }
+}
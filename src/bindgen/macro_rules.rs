@@ -0,0 +1,870 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use proc_macro2::{Delimiter, Group, Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+use syn;
+use syn::parse::{Parse, ParseStream, Parser};
+
+/// A `macro_rules!` definition, which can be expanded in item position.
+///
+/// Invocations are matched against each rule in turn, like rustc does. Unlike
+/// rustc, every way of matching a repetition is followed, so some invocations
+/// that rustc rejects as ambiguous are accepted.
+#[derive(Debug, Clone)]
+pub struct MacroRules {
+    name: String,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    matcher: Vec<Inst>,
+    transcriber: Vec<Transcriber>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RepeatOp {
+    ZeroOrMore,
+    OneOrMore,
+    ZeroOrOne,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Fragment {
+    Ident,
+    Lifetime,
+    Literal,
+    Tt,
+    Ty,
+    Path,
+    Expr,
+    Pat,
+    Vis,
+    Item,
+    Block,
+    Stmt,
+    Meta,
+}
+
+impl FromStr for Fragment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Fragment, Self::Err> {
+        match s {
+            "ident" => Ok(Fragment::Ident),
+            "lifetime" => Ok(Fragment::Lifetime),
+            "literal" => Ok(Fragment::Literal),
+            "tt" => Ok(Fragment::Tt),
+            "ty" => Ok(Fragment::Ty),
+            "path" => Ok(Fragment::Path),
+            "expr" => Ok(Fragment::Expr),
+            "pat" | "pat_param" => Ok(Fragment::Pat),
+            "vis" => Ok(Fragment::Vis),
+            "item" => Ok(Fragment::Item),
+            "block" => Ok(Fragment::Block),
+            "stmt" => Ok(Fragment::Stmt),
+            "meta" => Ok(Fragment::Meta),
+            _ => Err(format!("unsupported fragment specifier `{}`", s)),
+        }
+    }
+}
+
+impl Fragment {
+    /// Returns how many of `tokens` the fragment matches, if any.
+    fn match_len(self, tokens: &[TokenTree]) -> Option<usize> {
+        match self {
+            Fragment::Tt => {
+                if tokens.is_empty() {
+                    None
+                } else {
+                    Some(1)
+                }
+            }
+            Fragment::Ident => match tokens.first() {
+                Some(&TokenTree::Ident(..)) => Some(1),
+                _ => None,
+            },
+            Fragment::Lifetime => match (tokens.first(), tokens.get(1)) {
+                (Some(&TokenTree::Punct(ref p)), Some(&TokenTree::Ident(..)))
+                    if p.as_char() == '\'' =>
+                {
+                    Some(2)
+                }
+                _ => None,
+            },
+            Fragment::Literal => match (tokens.first(), tokens.get(1)) {
+                (Some(&TokenTree::Literal(..)), _) => Some(1),
+                (Some(&TokenTree::Ident(ref i)), _) if i == "true" || i == "false" => Some(1),
+                (Some(&TokenTree::Punct(ref p)), Some(&TokenTree::Literal(..)))
+                    if p.as_char() == '-' =>
+                {
+                    Some(2)
+                }
+                _ => None,
+            },
+            Fragment::Ty => syn_match_len_upto_comma::<syn::Type>(tokens),
+            Fragment::Path => syn_match_len_upto_comma::<syn::Path>(tokens),
+            Fragment::Expr => syn_match_len_upto_comma::<syn::Expr>(tokens),
+            Fragment::Pat => syn_match_len_upto_comma::<syn::Pat>(tokens),
+            Fragment::Vis => syn_match_len_upto_comma::<syn::Visibility>(tokens),
+            Fragment::Item => syn_match_len::<syn::Item>(tokens),
+            Fragment::Block => syn_match_len::<syn::Block>(tokens),
+            Fragment::Stmt => syn_match_len::<syn::Stmt>(tokens),
+            Fragment::Meta => syn_match_len_upto_comma::<syn::Meta>(tokens),
+        }
+    }
+}
+
+/// Returns how many of `tokens` make up a `T`, if they start with one.
+fn syn_match_len<T: Parse>(tokens: &[TokenTree]) -> Option<usize> {
+    let parser = |input: ParseStream| {
+        input.parse::<T>()?;
+        let rest: TokenStream = input.parse()?;
+        Ok(rest.into_iter().count())
+    };
+    let rest = parser.parse2(tokens.iter().cloned().collect()).ok()?;
+    Some(tokens.len() - rest)
+}
+
+/// Like `syn_match_len`, for fragments that can't end in a `,` or `;`. These
+/// are parsed up to the first one first, so that matching a long list of them
+/// doesn't parse the rest of it each time.
+fn syn_match_len_upto_comma<T: Parse>(tokens: &[TokenTree]) -> Option<usize> {
+    let end = tokens
+        .iter()
+        .position(|token| is_punct(Some(token), ',') || is_punct(Some(token), ';'));
+    match end {
+        // `Vec<A, B>` or `|a, b| a + b` need to be parsed past the comma.
+        Some(end) => syn_match_len::<T>(&tokens[..end]).or_else(|| syn_match_len::<T>(tokens)),
+        None => syn_match_len::<T>(tokens),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Token(TokenTree),
+    Group(Delimiter, Vec<Matcher>),
+    Fragment(String, Fragment),
+    Repeat(Vec<Matcher>, Option<TokenTree>, RepeatOp),
+}
+
+#[derive(Debug, Clone)]
+enum Transcriber {
+    Token(TokenTree),
+    Group(Delimiter, Vec<Transcriber>),
+    Var(String),
+    Crate,
+    Repeat(Vec<Transcriber>, Option<TokenTree>, RepeatOp),
+}
+
+#[derive(Debug, Clone)]
+enum Binding {
+    Fragment(Vec<TokenTree>),
+    Repeat(Vec<Binding>),
+}
+
+type Bindings = HashMap<String, Binding>;
+
+fn token_eq(a: &TokenTree, b: &TokenTree) -> bool {
+    match (a, b) {
+        (&TokenTree::Punct(ref a), &TokenTree::Punct(ref b)) => a.as_char() == b.as_char(),
+        (&TokenTree::Ident(ref a), &TokenTree::Ident(ref b)) => a == b,
+        (&TokenTree::Literal(ref a), &TokenTree::Literal(ref b)) => a.to_string() == b.to_string(),
+        _ => false,
+    }
+}
+
+fn is_punct(token: Option<&TokenTree>, c: char) -> bool {
+    match token {
+        Some(&TokenTree::Punct(ref p)) => p.as_char() == c,
+        _ => false,
+    }
+}
+
+fn repeat_op(token: Option<&TokenTree>) -> Option<RepeatOp> {
+    match token {
+        Some(&TokenTree::Punct(ref p)) => match p.as_char() {
+            '*' => Some(RepeatOp::ZeroOrMore),
+            '+' => Some(RepeatOp::OneOrMore),
+            '?' => Some(RepeatOp::ZeroOrOne),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parses the separator and operator following `$(...)`, returning them
+/// along with how many tokens they took up.
+fn parse_repeat_op(tokens: &[TokenTree]) -> Result<(Option<TokenTree>, RepeatOp, usize), String> {
+    if let Some(op) = repeat_op(tokens.first()) {
+        return Ok((None, op, 1));
+    }
+    match (tokens.first(), repeat_op(tokens.get(1))) {
+        (Some(&TokenTree::Group(..)), _) | (_, Some(RepeatOp::ZeroOrOne)) | (_, None) => {
+            Err("expected one of `*`, `+` or `?` after a repetition".to_owned())
+        }
+        (Some(sep), Some(op)) => Ok((Some(sep.clone()), op, 2)),
+        (None, Some(_)) => unreachable!(),
+    }
+}
+
+fn parse_matcher(tokens: TokenStream) -> Result<Vec<Matcher>, String> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut matchers = Vec::new();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i] {
+            TokenTree::Punct(ref p) if p.as_char() == '$' => match tokens.get(i + 1) {
+                Some(&TokenTree::Ident(ref name)) => {
+                    let fragment = match tokens.get(i + 3) {
+                        Some(&TokenTree::Ident(ref fragment))
+                            if is_punct(tokens.get(i + 2), ':') =>
+                        {
+                            fragment.to_string().parse()?
+                        }
+                        _ => return Err(format!("missing fragment specifier for `${}`", name)),
+                    };
+                    matchers.push(Matcher::Fragment(name.to_string(), fragment));
+                    i += 4;
+                }
+                Some(&TokenTree::Group(ref group))
+                    if group.delimiter() == Delimiter::Parenthesis =>
+                {
+                    let inner = parse_matcher(group.stream())?;
+                    let (separator, op, len) = parse_repeat_op(&tokens[i + 2..])?;
+                    matchers.push(Matcher::Repeat(inner, separator, op));
+                    i += 2 + len;
+                }
+                _ => return Err("expected a fragment or a repetition after `$`".to_owned()),
+            },
+            TokenTree::Group(ref group) => {
+                matchers.push(Matcher::Group(
+                    group.delimiter(),
+                    parse_matcher(group.stream())?,
+                ));
+                i += 1;
+            }
+            ref token => {
+                matchers.push(Matcher::Token(token.clone()));
+                i += 1;
+            }
+        }
+    }
+
+    Ok(matchers)
+}
+
+fn parse_transcriber(tokens: TokenStream) -> Result<Vec<Transcriber>, String> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut transcribers = Vec::new();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i] {
+            TokenTree::Punct(ref p) if p.as_char() == '$' => match tokens.get(i + 1) {
+                Some(&TokenTree::Ident(ref name)) => {
+                    transcribers.push(if name == "crate" {
+                        Transcriber::Crate
+                    } else {
+                        Transcriber::Var(name.to_string())
+                    });
+                    i += 2;
+                }
+                Some(&TokenTree::Group(ref group))
+                    if group.delimiter() == Delimiter::Parenthesis =>
+                {
+                    let inner = parse_transcriber(group.stream())?;
+                    let (separator, op, len) = parse_repeat_op(&tokens[i + 2..])?;
+                    transcribers.push(Transcriber::Repeat(inner, separator, op));
+                    i += 2 + len;
+                }
+                _ => return Err("expected a variable or a repetition after `$`".to_owned()),
+            },
+            TokenTree::Group(ref group) => {
+                transcribers.push(Transcriber::Group(
+                    group.delimiter(),
+                    parse_transcriber(group.stream())?,
+                ));
+                i += 1;
+            }
+            ref token => {
+                transcribers.push(Transcriber::Token(token.clone()));
+                i += 1;
+            }
+        }
+    }
+
+    Ok(transcribers)
+}
+
+fn matcher_vars(matchers: &[Matcher], out: &mut Vec<String>) {
+    for matcher in matchers {
+        match *matcher {
+            Matcher::Token(..) => {}
+            Matcher::Group(_, ref inner) | Matcher::Repeat(ref inner, ..) => {
+                matcher_vars(inner, out)
+            }
+            Matcher::Fragment(ref name, _) => out.push(name.clone()),
+        }
+    }
+}
+
+fn transcriber_vars(transcribers: &[Transcriber], out: &mut Vec<String>) {
+    for transcriber in transcribers {
+        match *transcriber {
+            Transcriber::Token(..) | Transcriber::Crate => {}
+            Transcriber::Group(_, ref inner) | Transcriber::Repeat(ref inner, ..) => {
+                transcriber_vars(inner, out)
+            }
+            Transcriber::Var(ref name) => out.push(name.clone()),
+        }
+    }
+}
+
+/// A matcher compiled to a program, which is run over the tokens of the
+/// invocation without backtracking.
+#[derive(Debug, Clone)]
+enum Inst {
+    Token(TokenTree),
+    /// Matches a group whose tokens all match the program.
+    Group(Delimiter, Vec<Inst>),
+    Fragment(String, Fragment),
+    /// Starts a repetition, whose `RepeatEnd` is at `end`.
+    RepeatStart {
+        end: usize,
+        op: RepeatOp,
+        vars: Rc<Vec<String>>,
+    },
+    /// Ends an iteration of the repetition started at `start`.
+    RepeatEnd {
+        start: usize,
+        separator: Option<TokenTree>,
+    },
+}
+
+fn compile(matchers: &[Matcher], out: &mut Vec<Inst>) {
+    for matcher in matchers {
+        match *matcher {
+            Matcher::Token(ref token) => out.push(Inst::Token(token.clone())),
+            Matcher::Group(delimiter, ref inner) => {
+                let mut program = Vec::new();
+                compile(inner, &mut program);
+                out.push(Inst::Group(delimiter, program));
+            }
+            Matcher::Fragment(ref name, fragment) => {
+                out.push(Inst::Fragment(name.clone(), fragment))
+            }
+            Matcher::Repeat(ref inner, ref separator, op) => {
+                let mut vars = Vec::new();
+                matcher_vars(inner, &mut vars);
+                let start = out.len();
+                out.push(Inst::RepeatStart {
+                    end: 0,
+                    op,
+                    vars: Rc::new(vars),
+                });
+                compile(inner, out);
+                let end = out.len();
+                out.push(Inst::RepeatEnd {
+                    start,
+                    separator: separator.clone(),
+                });
+                if let Inst::RepeatStart { end: ref mut x, .. } = out[start] {
+                    *x = end;
+                }
+            }
+        }
+    }
+}
+
+/// A persistent list, so that the threads of a match can share what they
+/// matched so far rather than copy it.
+type List<T> = Option<Rc<Node<T>>>;
+
+struct Node<T> {
+    value: T,
+    next: List<T>,
+}
+
+impl<T> Drop for Node<T> {
+    // Long lists would overflow the stack if dropped recursively.
+    fn drop(&mut self) {
+        let mut next = self.next.take();
+        while let Some(node) = next {
+            match Rc::try_unwrap(node) {
+                Ok(mut node) => next = node.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+fn push<T>(list: &List<T>, value: T) -> List<T> {
+    Some(Rc::new(Node {
+        value,
+        next: list.clone(),
+    }))
+}
+
+fn iter<'a, T>(list: &'a List<T>) -> ListIter<'a, T> {
+    ListIter(list.as_ref())
+}
+
+struct ListIter<'a, T: 'a>(Option<&'a Rc<Node<T>>>);
+
+impl<'a, T> Iterator for ListIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.0?;
+        self.0 = node.next.as_ref();
+        Some(&node.value)
+    }
+}
+
+/// What a thread matched, last first.
+type Matched = List<Bound>;
+
+enum Bound {
+    Var(String, Binding),
+    /// A repetition of `vars`, with what each iteration matched, last first.
+    /// Its bindings are only made once the whole invocation matches, so that
+    /// leaving a repetition after each iteration doesn't copy the iterations.
+    Repeat(Rc<Vec<String>>, List<Matched>),
+}
+
+/// Makes the bindings of the variables that `matched` binds.
+fn make_bindings(matched: &Matched) -> Bindings {
+    let mut bindings = Bindings::new();
+    for bound in iter(matched) {
+        match *bound {
+            Bound::Var(ref name, ref binding) => {
+                bindings
+                    .entry(name.clone())
+                    .or_insert_with(|| binding.clone());
+            }
+            Bound::Repeat(ref vars, ref iterations) => {
+                let mut iterations: Vec<Bindings> = iter(iterations).map(make_bindings).collect();
+                iterations.reverse();
+                for var in vars.iter() {
+                    let values = iterations
+                        .iter_mut()
+                        .filter_map(|iteration| iteration.remove(var))
+                        .collect();
+                    bindings
+                        .entry(var.clone())
+                        .or_insert(Binding::Repeat(values));
+                }
+            }
+        }
+    }
+    bindings
+}
+
+/// A repetition being matched by a thread.
+#[derive(Clone)]
+struct Frame {
+    /// The bindings of the enclosing matcher.
+    outer: Matched,
+    /// The bindings of each iteration so far, last first.
+    iterations: List<Matched>,
+    /// Where the current iteration started.
+    start: usize,
+}
+
+/// One way of matching the invocation so far, at an instruction.
+#[derive(Clone)]
+struct Thread {
+    pc: usize,
+    matched: Matched,
+    frames: List<Frame>,
+}
+
+/// Matches all of `tokens` against `program`, returning the bindings of the
+/// first way it matches. Every way is followed at once, token by token, and
+/// only the first thread to reach an instruction at a token is kept, so this
+/// takes time linear in the number of tokens, for a given program.
+fn match_program(program: &[Inst], tokens: &[TokenTree]) -> Option<Bindings> {
+    // The threads waiting at each token, and the instructions they're at.
+    let mut queues: Vec<Vec<Thread>> = vec![Vec::new(); tokens.len() + 1];
+    let mut seen: HashSet<(usize, usize)> = HashSet::new();
+    queues[0].push(Thread {
+        pc: 0,
+        matched: None,
+        frames: None,
+    });
+
+    for pos in 0..=tokens.len() {
+        // Threads are handled first to last, so that earlier ones win.
+        let mut queue = Vec::new();
+        mem::swap(&mut queue, &mut queues[pos]);
+        queue.reverse();
+        while let Some(thread) = queue.pop() {
+            if !seen.insert((thread.pc, pos)) {
+                continue;
+            }
+            let inst = match program.get(thread.pc) {
+                Some(inst) => inst,
+                None if pos == tokens.len() => {
+                    return Some(make_bindings(&thread.matched));
+                }
+                None => continue,
+            };
+            // Threads that don't consume a token go back on the queue, first
+            // ones last.
+            let mut now = Vec::new();
+            match *inst {
+                Inst::Token(ref token) => match tokens.get(pos) {
+                    Some(next) if token_eq(token, next) => queues[pos + 1].push(Thread {
+                        pc: thread.pc + 1,
+                        ..thread
+                    }),
+                    _ => {}
+                },
+                Inst::Group(delimiter, ref inner) => match tokens.get(pos) {
+                    Some(&TokenTree::Group(ref group)) if group.delimiter() == delimiter => {
+                        let group_tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+                        if let Some(matched) = match_program(inner, &group_tokens) {
+                            let mut bound = thread.matched.clone();
+                            for (name, binding) in matched {
+                                bound = push(&bound, Bound::Var(name, binding));
+                            }
+                            queues[pos + 1].push(Thread {
+                                pc: thread.pc + 1,
+                                matched: bound,
+                                frames: thread.frames,
+                            });
+                        }
+                    }
+                    _ => {}
+                },
+                Inst::Fragment(ref name, fragment) => {
+                    // Like in rustc, fragments other than `tt` are parsed
+                    // greedily.
+                    if let Some(len) = fragment.match_len(&tokens[pos..]) {
+                        let binding = Binding::Fragment(tokens[pos..pos + len].to_vec());
+                        queues[pos + len].push(Thread {
+                            pc: thread.pc + 1,
+                            matched: push(&thread.matched, Bound::Var(name.clone(), binding)),
+                            frames: thread.frames,
+                        });
+                    }
+                }
+                Inst::RepeatStart { end, op, ref vars } => {
+                    // As many iterations as possible are tried first.
+                    let frame = Frame {
+                        outer: thread.matched.clone(),
+                        iterations: None,
+                        start: pos,
+                    };
+                    now.push(Thread {
+                        pc: thread.pc + 1,
+                        matched: None,
+                        frames: push(&thread.frames, frame),
+                    });
+                    if op != RepeatOp::OneOrMore {
+                        now.push(Thread {
+                            pc: end + 1,
+                            matched: push(&thread.matched, Bound::Repeat(vars.clone(), None)),
+                            frames: thread.frames,
+                        });
+                    }
+                }
+                Inst::RepeatEnd {
+                    start,
+                    ref separator,
+                } => {
+                    let frame = &thread.frames.as_ref().unwrap().value;
+                    // An iteration that matches nothing would repeat forever.
+                    if frame.start == pos {
+                        continue;
+                    }
+                    let iterations = push(&frame.iterations, thread.matched.clone());
+                    let outer_frames = &thread.frames.as_ref().unwrap().next;
+                    let (op, vars) = match program[start] {
+                        Inst::RepeatStart { op, ref vars, .. } => (op, vars),
+                        _ => unreachable!(),
+                    };
+
+                    if op != RepeatOp::ZeroOrOne {
+                        let again = |from| Thread {
+                            pc: start + 1,
+                            matched: None,
+                            frames: push(
+                                outer_frames,
+                                Frame {
+                                    outer: frame.outer.clone(),
+                                    iterations: iterations.clone(),
+                                    start: from,
+                                },
+                            ),
+                        };
+                        match *separator {
+                            None => now.push(again(pos)),
+                            Some(ref separator) => match tokens.get(pos) {
+                                Some(next) if token_eq(separator, next) => {
+                                    queues[pos + 1].push(again(pos + 1))
+                                }
+                                _ => {}
+                            },
+                        }
+                    }
+                    now.push(Thread {
+                        pc: thread.pc + 1,
+                        matched: push(&frame.outer, Bound::Repeat(vars.clone(), iterations)),
+                        frames: outer_frames.clone(),
+                    });
+                }
+            }
+            while let Some(thread) = now.pop() {
+                queue.push(thread);
+            }
+        }
+    }
+
+    None
+}
+
+fn transcribe(
+    transcribers: &[Transcriber],
+    bindings: &mut Bindings,
+    out: &mut TokenStream,
+) -> Result<(), String> {
+    for transcriber in transcribers {
+        match *transcriber {
+            Transcriber::Token(ref token) => out.extend(Some(token.clone())),
+            Transcriber::Group(delimiter, ref inner) => {
+                let mut tokens = TokenStream::new();
+                transcribe(inner, bindings, &mut tokens)?;
+                out.extend(Some(TokenTree::Group(Group::new(delimiter, tokens))));
+            }
+            Transcriber::Var(ref name) => match bindings.get(name) {
+                Some(&Binding::Fragment(ref tokens)) => out.extend(tokens.iter().cloned()),
+                Some(&Binding::Repeat(..)) => {
+                    return Err(format!(
+                        "variable `{}` is still repeating at this depth",
+                        name
+                    ));
+                }
+                // Not a variable of this macro, e.g. in a nested `macro_rules!`.
+                None => out.extend(vec![
+                    TokenTree::Punct(Punct::new('$', Spacing::Alone)),
+                    TokenTree::Ident(Ident::new(name, Span::call_site())),
+                ]),
+            },
+            Transcriber::Crate => out.extend(Some(TokenTree::Ident(Ident::new(
+                "crate",
+                Span::call_site(),
+            )))),
+            Transcriber::Repeat(ref inner, ref separator, op) => {
+                let mut vars = Vec::new();
+                transcriber_vars(inner, &mut vars);
+
+                let mut count = None;
+                for var in &vars {
+                    if let Some(&Binding::Repeat(ref values)) = bindings.get(var) {
+                        match count {
+                            Some(count) if count != values.len() => {
+                                return Err(format!(
+                                    "variable `{}` repeats {} times, but others repeat {} times",
+                                    var,
+                                    values.len(),
+                                    count
+                                ));
+                            }
+                            _ => count = Some(values.len()),
+                        }
+                    }
+                }
+                let count = match count {
+                    Some(count) => count,
+                    // Not a repetition of this macro, e.g. in a nested
+                    // `macro_rules!`, so it's kept as is.
+                    None if vars.iter().all(|var| !bindings.contains_key(var)) => {
+                        let mut tokens = TokenStream::new();
+                        transcribe(inner, bindings, &mut tokens)?;
+                        out.extend(vec![
+                            TokenTree::Punct(Punct::new('$', Spacing::Alone)),
+                            TokenTree::Group(Group::new(Delimiter::Parenthesis, tokens)),
+                        ]);
+                        out.extend(separator.clone());
+                        let op = match op {
+                            RepeatOp::ZeroOrMore => '*',
+                            RepeatOp::OneOrMore => '+',
+                            RepeatOp::ZeroOrOne => '?',
+                        };
+                        out.extend(Some(TokenTree::Punct(Punct::new(op, Spacing::Alone))));
+                        continue;
+                    }
+                    None => {
+                        return Err(
+                            "repetition without any variables repeating at this depth".to_owned()
+                        );
+                    }
+                };
+
+                if count == 0 && op == RepeatOp::OneOrMore {
+                    return Err("repetition with `+` matched nothing".to_owned());
+                }
+
+                // The repeating variables are bound to each of their values in
+                // turn, and then put back.
+                let mut repeats = Vec::new();
+                for var in vars {
+                    if let Some(Binding::Repeat(values)) = bindings.remove(&var) {
+                        repeats.push((var, values));
+                    }
+                }
+                for i in 0..count {
+                    if i != 0 {
+                        out.extend(separator.clone());
+                    }
+                    for &(ref var, ref values) in &repeats {
+                        bindings.insert(var.clone(), values[i].clone());
+                    }
+                    transcribe(inner, bindings, out)?;
+                }
+                for (var, values) in repeats {
+                    bindings.insert(var, Binding::Repeat(values));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl MacroRules {
+    pub fn load(item: &syn::ItemMacro) -> Result<MacroRules, String> {
+        let name = match item.ident {
+            Some(ref ident) => ident.to_string(),
+            None => return Err("`macro_rules!` without a name".to_owned()),
+        };
+
+        let tokens: Vec<TokenTree> = item.mac.tts.clone().into_iter().collect();
+        let mut rules = Vec::new();
+
+        let mut i = 0;
+        while i < tokens.len() {
+            let (matcher, transcriber) = match (tokens.get(i), tokens.get(i + 3)) {
+                (
+                    Some(&TokenTree::Group(ref matcher)),
+                    Some(&TokenTree::Group(ref transcriber)),
+                ) if is_punct(tokens.get(i + 1), '=') && is_punct(tokens.get(i + 2), '>') => {
+                    (matcher.stream(), transcriber.stream())
+                }
+                _ => return Err(format!("invalid rule in `{}!`", name)),
+            };
+            let matcher = parse_matcher(matcher).map_err(|e| format!("in `{}!`: {}", name, e))?;
+            let mut program = Vec::new();
+            compile(&matcher, &mut program);
+            rules.push(Rule {
+                matcher: program,
+                transcriber: parse_transcriber(transcriber)
+                    .map_err(|e| format!("in `{}!`: {}", name, e))?,
+            });
+
+            i += 4;
+            if is_punct(tokens.get(i), ';') {
+                i += 1;
+            }
+        }
+
+        Ok(MacroRules { name, rules })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Expands an invocation of the macro into the items it produces.
+    pub fn expand(&self, input: TokenStream) -> Result<Vec<syn::Item>, String> {
+        let tokens: Vec<TokenTree> = input.into_iter().collect();
+
+        for rule in &self.rules {
+            let mut bindings = match match_program(&rule.matcher, &tokens) {
+                Some(bindings) => bindings,
+                None => continue,
+            };
+
+            let mut expanded = TokenStream::new();
+            transcribe(&rule.transcriber, &mut bindings, &mut expanded)
+                .map_err(|e| format!("in `{}!`: {}", self.name, e))?;
+            let file: syn::File = syn::parse2(expanded).map_err(|e| {
+                format!(
+                    "the expansion of `{}!` isn't a list of items: {}",
+                    self.name, e
+                )
+            })?;
+            return Ok(file.items);
+        }
+
+        Err(format!("no rules of `{}!` match the invocation", self.name))
+    }
+}
+
+#[cfg(test)]
+fn expand(macro_rules: &str, invocation: &str) -> Result<Vec<syn::Item>, String> {
+    let item: syn::ItemMacro = syn::parse_str(macro_rules).unwrap();
+    let invocation: syn::ItemMacro = syn::parse_str(invocation).unwrap();
+    MacroRules::load(&item)?.expand(invocation.mac.tts)
+}
+
+#[test]
+fn long_invocation() {
+    let mut invocation = "consts! {".to_owned();
+    for i in 0..20000 {
+        invocation.push_str(&format!(" C{} = {},", i, i));
+    }
+    invocation.push_str(" }");
+
+    let items = expand(
+        "macro_rules! consts {
+            ($($name:ident = $value:expr),* $(,)*) => {
+                $(pub const $name: i32 = $value;)*
+            };
+        }",
+        &invocation,
+    )
+    .unwrap();
+    assert_eq!(items.len(), 20000);
+}
+
+#[test]
+fn nested_macro_rules() {
+    let items = expand(
+        "macro_rules! outer {
+            ($name:ident) => {
+                macro_rules! $name {
+                    ($($field:ident),*) => {
+                        #[repr(C)]
+                        pub struct Inner { $(pub $field: i32),* }
+                    };
+                }
+            };
+        }",
+        "outer! { inner }",
+    )
+    .unwrap();
+    assert_eq!(items.len(), 1);
+
+    let inner = match items[0] {
+        syn::Item::Macro(ref item) => item,
+        _ => panic!("expected a `macro_rules!`"),
+    };
+    let items = MacroRules::load(inner)
+        .unwrap()
+        .expand(syn::parse_str("a, b").unwrap())
+        .unwrap();
+    match items[0] {
+        syn::Item::Struct(ref item) => assert_eq!(item.fields.iter().count(), 2),
+        _ => panic!("expected a struct"),
+    }
+}
@@ -48,7 +48,9 @@ fn internal_mangle_name(name: &str, generic_values: &[Type], last_in_parent: boo
             | &Type::ConstPtr(..)
             | &Type::Ptr(..)
             | &Type::Array(..)
-            | &Type::FuncPtr(..) => unimplemented!(),
+            | &Type::FuncPtr(..)
+            | &Type::ConstFatPtr(..)
+            | &Type::FatPtr(..) => unimplemented!(),
         }
 
         // Skip writing the trailing '>' mangling when possible
@@ -0,0 +1,568 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::collections::HashMap;
+
+/// An open code block, with the fence that closes it.
+pub struct CodeBlock {
+    fence: String,
+    language: String,
+    is_rust: bool,
+}
+
+impl CodeBlock {
+    /// Opens a code block if `line` is a fence like ```` ``` ```` or
+    /// ```` ```rust ````.
+    pub fn open(line: &str) -> Option<CodeBlock> {
+        let trimmed = line.trim_start();
+        let fence_char = match trimmed.chars().next() {
+            Some(c @ '`') | Some(c @ '~') => c,
+            _ => return None,
+        };
+        let info = trimmed.trim_start_matches(fence_char);
+        let fence = &trimmed[..trimmed.len() - info.len()];
+        if fence.len() < 3 || (fence_char == '`' && info.contains('`')) {
+            return None;
+        }
+
+        // Code blocks are rust unless they say otherwise, like ```` ```text ````.
+        let mut language = "rust";
+        for word in info
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|x| !x.is_empty())
+        {
+            match word {
+                "rust" | "ignore" | "no_run" | "should_panic" | "compile_fail" | "test_harness"
+                | "allow_fail" => {}
+                x if x.starts_with("edition") => {}
+                x => language = x,
+            }
+        }
+
+        Some(CodeBlock {
+            fence: fence.to_owned(),
+            language: language.to_owned(),
+            is_rust: language == "rust",
+        })
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Whether `line` is the fence that closes the block.
+    pub fn closes(&self, line: &str) -> bool {
+        let trimmed = line.trim();
+        let fence_char = self.fence.chars().next().unwrap();
+        trimmed.starts_with(&self.fence[..]) && trimmed.chars().all(|c| c == fence_char)
+    }
+
+    /// How a line of the block is shown. Lines of rust code starting with `#`
+    /// are hidden by rustdoc, and `##` escapes a `#`.
+    pub fn shown(&self, line: &str) -> Option<String> {
+        if !self.is_rust {
+            return Some(line.to_owned());
+        }
+        let trimmed = line.trim();
+        if trimmed == "#" || trimmed.starts_with("# ") {
+            return None;
+        }
+        if trimmed.starts_with("##") {
+            return Some(line.replacen("##", "#", 1));
+        }
+        Some(line.to_owned())
+    }
+}
+
+/// The title of a markdown heading like `# Safety`.
+pub fn heading(line: &str) -> Option<&str> {
+    heading_with_level(line).map(|(_, title)| title)
+}
+
+fn heading_with_level(line: &str) -> Option<(usize, &str)> {
+    let title = line.trim_start_matches('#');
+    let level = line.len() - title.len();
+    if level == 0 || level > 6 || !(title.is_empty() || title.starts_with(' ')) {
+        return None;
+    }
+    Some((level, title.trim()))
+}
+
+/// Splits an item of an arguments list like ``* `name` - The name.`` into the
+/// argument's name and its description.
+pub fn argument(line: &str) -> Option<(&str, &str)> {
+    let mut rest = line.trim_start();
+    if !(rest.starts_with("* ") || rest.starts_with("- ") || rest.starts_with("+ ")) {
+        return None;
+    }
+    rest = rest[2..].trim_start();
+
+    let quoted = rest.starts_with('`');
+    if quoted {
+        rest = &rest[1..];
+    }
+    let name_len = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    if name_len == 0 {
+        return None;
+    }
+    let name = &rest[..name_len];
+    rest = &rest[name_len..];
+    if quoted {
+        if !rest.starts_with('`') {
+            return None;
+        }
+        rest = &rest[1..];
+    }
+
+    let rest = rest.trim_start();
+    let description = if rest.starts_with('-') || rest.starts_with(':') {
+        rest[1..].trim_start()
+    } else if rest.starts_with('\u{2013}') || rest.starts_with('\u{2014}') {
+        rest[3..].trim_start()
+    } else if rest.is_empty() {
+        rest
+    } else {
+        return None;
+    };
+    Some((name, description))
+}
+
+/// Whether a heading starts the section describing a function's arguments.
+pub fn is_arguments_heading(title: &str) -> bool {
+    match &*title.to_lowercase() {
+        "arguments" | "args" | "parameters" | "params" => true,
+        _ => false,
+    }
+}
+
+/// Splits documentation into the arguments described by its `# Arguments`
+/// section, by name, and the rest of it.
+pub fn split_arguments(lines: &[String]) -> (Vec<String>, HashMap<String, Vec<String>>) {
+    let mut rest = Vec::new();
+    let mut arguments: HashMap<String, Vec<String>> = HashMap::new();
+    let mut in_arguments = false;
+    let mut last_argument: Option<String> = None;
+
+    for line in lines {
+        if let Some(title) = heading(line) {
+            in_arguments = is_arguments_heading(title);
+            last_argument = None;
+            if in_arguments {
+                continue;
+            }
+        }
+        if !in_arguments {
+            rest.push(line.clone());
+            continue;
+        }
+        if let Some((name, description)) = argument(line) {
+            arguments.insert(name.to_owned(), vec![description.to_owned()]);
+            last_argument = Some(name.to_owned());
+        } else if let (true, Some(name)) = (line.starts_with(' '), last_argument.as_ref()) {
+            arguments
+                .get_mut(name)
+                .unwrap()
+                .push(line.trim().to_owned());
+        }
+    }
+
+    (rest, arguments)
+}
+
+/// Whether `line` defines the target of an intra-doc reference link, like
+/// `[Foo]: crate::Foo`. Links to URLs are left to the markdown.
+pub fn is_link_definition(line: &str) -> bool {
+    link_definition(line).map_or(false, |(_, target)| !is_url(target))
+}
+
+fn link_definition(line: &str) -> Option<(&str, &str)> {
+    if !line.starts_with('[') {
+        return None;
+    }
+    let end = line.find("]:")?;
+    let target = line[end + 2..].trim();
+    if end == 1 || target.is_empty() || target.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((&line[1..end], target))
+}
+
+/// Resolves the intra-doc links of some documentation to the generated items.
+pub struct Links<'a> {
+    links: &'a HashMap<String, String>,
+    /// The targets of the reference links, by label.
+    definitions: HashMap<String, String>,
+}
+
+impl<'a> Links<'a> {
+    /// `links` maps the rust names of the generated items to the names
+    /// they're exported as.
+    pub fn new(lines: &[String], links: &'a HashMap<String, String>) -> Links<'a> {
+        let definitions = lines
+            .iter()
+            .filter_map(|line| link_definition(line))
+            .map(|(label, target)| (label.to_lowercase(), target.to_owned()))
+            .collect();
+        Links { links, definitions }
+    }
+
+    /// The name an intra-doc link target like `crate::ffi::Foo` or
+    /// `` struct@Foo `` is exported as, if it's one of the generated items.
+    fn resolve(&self, target: &str) -> Option<&'a str> {
+        if is_url(target) {
+            return None;
+        }
+        let target = target.trim_matches('`');
+        let target = match target.find('@') {
+            Some(index) => &target[index + 1..],
+            None => target,
+        };
+        let target = target.trim_end_matches("()").trim_end_matches('!');
+        let target = match target.find('<') {
+            Some(index) => &target[..index],
+            None => target,
+        };
+        let name = target.rsplit("::").next().unwrap_or(target);
+        self.links.get(name).map(|x| x.as_str())
+    }
+
+    /// Rewrites the intra-doc links of a line with `render`, which is given
+    /// the exported name of the item and the text of the link, if it isn't
+    /// just the item's name. Links to items that aren't generated are
+    /// replaced by their text.
+    pub fn translate(&self, line: &str, render: &dyn Fn(&str, Option<&str>) -> String) -> String {
+        let mut out = String::new();
+        let mut rest = line;
+        let mut in_code = false;
+
+        while let Some(index) = rest.find(&['`', '['][..]) {
+            out.push_str(&rest[..index]);
+            rest = &rest[index..];
+            if rest.starts_with('`') {
+                in_code = !in_code;
+                out.push('`');
+                rest = &rest[1..];
+                continue;
+            }
+            if in_code {
+                out.push('[');
+                rest = &rest[1..];
+                continue;
+            }
+            match self.link(rest, render) {
+                Some((text, len)) => {
+                    out.push_str(&text);
+                    rest = &rest[len..];
+                }
+                None => {
+                    out.push('[');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    /// Translates the link at the start of `s`, returning the translation
+    /// and the length of the link.
+    fn link(
+        &self,
+        s: &str,
+        render: &dyn Fn(&str, Option<&str>) -> String,
+    ) -> Option<(String, usize)> {
+        let text_end = s.find(']')?;
+        let text = &s[1..text_end];
+        if text.is_empty() {
+            return None;
+        }
+        let after = &s[text_end + 1..];
+
+        let (target, len) = if after.starts_with('(') {
+            let end = after.find(')')?;
+            (Some(&after[1..end]), text_end + 1 + end + 1)
+        } else if after.starts_with('[') && !after.starts_with("[]") {
+            let end = after.find(']')?;
+            let label = &after[1..end];
+            let target = self.definitions.get(&label.to_lowercase())?;
+            (Some(target.as_str()), text_end + 1 + end + 1)
+        } else {
+            let len = if after.starts_with("[]") {
+                text_end + 3
+            } else {
+                text_end + 1
+            };
+            let target = self
+                .definitions
+                .get(&text.to_lowercase())
+                .map(|x| x.as_str());
+            (target, len)
+        };
+
+        if target.map_or(false, is_url) {
+            return None;
+        }
+
+        let plain_text = text.trim_matches('`');
+        match self.resolve(target.unwrap_or(text)) {
+            // Links whose text is the item's name don't need to repeat it.
+            Some(name)
+                if target.map_or(true, |target| {
+                    target.trim_matches('`').rsplit("::").next() == Some(plain_text)
+                }) =>
+            {
+                Some((render(name, None), len))
+            }
+            Some(name) => Some((render(name, Some(plain_text)), len)),
+            // A path to something that isn't generated.
+            None if target.is_some() => Some((text.to_owned(), len)),
+            // Something in code that isn't generated.
+            None if text.starts_with('`') && text.ends_with('`') => Some((text.to_owned(), len)),
+            // Probably not a link at all.
+            None => None,
+        }
+    }
+}
+
+fn is_url(target: &str) -> bool {
+    target.contains("://")
+        || target.starts_with('#')
+        || target.starts_with("mailto:")
+        || target.ends_with(".html")
+        || target.contains(".html#")
+        || target.ends_with(".md")
+}
+
+/// The block of markdown being rendered to HTML.
+enum Block {
+    None,
+    Paragraph(Vec<String>),
+    List,
+    Table,
+    Code(CodeBlock),
+}
+
+impl Block {
+    fn close(&mut self, html: &mut String) {
+        match *self {
+            Block::None => {}
+            Block::Paragraph(ref lines) => {
+                html.push_str(&format!("<p>{}</p>\n", inline_html(&lines.join("\n"))));
+            }
+            Block::List => html.push_str("</ul>\n"),
+            Block::Table => html.push_str("</tbody></table>\n"),
+            Block::Code(..) => html.push_str("</code></pre>\n"),
+        }
+        *self = Block::None;
+    }
+}
+
+/// Renders markdown to HTML. Only what documentation commonly uses is
+/// supported: headings, paragraphs, lists, quotes, tables, code blocks, code
+/// spans, links and emphasis.
+pub fn to_html(lines: &[String]) -> String {
+    let mut html = String::new();
+    let mut block = Block::None;
+
+    let mut lines = lines.iter().peekable();
+    while let Some(line) = lines.next() {
+        if let Block::Code(ref code_block) = block {
+            if !code_block.closes(line) {
+                if let Some(line) = code_block.shown(line) {
+                    html.push_str(&escape_html(&line));
+                    html.push('\n');
+                }
+                continue;
+            }
+        }
+        if let Block::Code(..) = block {
+            block.close(&mut html);
+            continue;
+        }
+
+        let trimmed = line.trim();
+        if let Some(code_block) = CodeBlock::open(line) {
+            block.close(&mut html);
+            html.push_str(&format!(
+                "<pre><code class=\"language-{}\">",
+                escape_html(code_block.language())
+            ));
+            block = Block::Code(code_block);
+        } else if trimmed.is_empty() {
+            block.close(&mut html);
+        } else if let Some((level, title)) = heading_with_level(line) {
+            block.close(&mut html);
+            html.push_str(&format!("<h{0}>{1}</h{0}>\n", level, inline_html(title)));
+        } else if trimmed.starts_with('|') {
+            let cell = if let Block::Table = block {
+                "td"
+            } else {
+                block.close(&mut html);
+                html.push_str("<table>\n<thead>\n");
+                "th"
+            };
+            html.push_str("<tr>");
+            for cell_text in table_cells(trimmed) {
+                html.push_str(&format!("<{0}>{1}</{0}>", cell, inline_html(&cell_text)));
+            }
+            html.push_str("</tr>\n");
+            if cell == "th" {
+                html.push_str("</thead>\n<tbody>\n");
+                // Skip the row separating the header.
+                if lines.peek().map_or(false, |x| x.trim().starts_with("|-")) {
+                    lines.next();
+                }
+                block = Block::Table;
+            }
+        } else if trimmed.starts_with('>') {
+            block.close(&mut html);
+            html.push_str(&format!(
+                "<blockquote><p>{}</p></blockquote>\n",
+                inline_html(trimmed[1..].trim())
+            ));
+        } else if trimmed.starts_with("* ") || trimmed.starts_with("- ") {
+            if let Block::List = block {
+            } else {
+                block.close(&mut html);
+                html.push_str("<ul>\n");
+                block = Block::List;
+            }
+            html.push_str(&format!("<li>{}</li>\n", inline_html(&trimmed[2..])));
+        } else if trimmed == "---" {
+            block.close(&mut html);
+            html.push_str("<hr>\n");
+        } else {
+            match block {
+                Block::Paragraph(ref mut lines) => lines.push(trimmed.to_owned()),
+                Block::List if line.starts_with(' ') => {
+                    // The continuation of a list item.
+                    let end = html.len() - "</li>\n".len();
+                    html.insert_str(end, &format!(" {}", inline_html(trimmed)));
+                }
+                _ => {
+                    block.close(&mut html);
+                    block = Block::Paragraph(vec![trimmed.to_owned()]);
+                }
+            }
+        }
+    }
+    block.close(&mut html);
+
+    html
+}
+
+fn table_cells(row: &str) -> Vec<String> {
+    let row = row.trim().trim_start_matches('|');
+    let row = if row.ends_with('|') && !row.ends_with("\\|") {
+        &row[..row.len() - 1]
+    } else {
+        row
+    };
+
+    let mut cells = vec![String::new()];
+    let mut chars = row.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('|') => cells.last_mut().unwrap().push('|'),
+                Some(c) => {
+                    cells.last_mut().unwrap().push('\\');
+                    cells.last_mut().unwrap().push(c);
+                }
+                None => cells.last_mut().unwrap().push('\\'),
+            },
+            '|' => cells.push(String::new()),
+            c => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells.iter().map(|x| x.trim().to_owned()).collect()
+}
+
+/// Renders code spans, links and emphasis.
+fn inline_html(text: &str) -> String {
+    let mut html = String::new();
+    let mut rest = text;
+    let mut strong = false;
+    let mut emphasis = false;
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '`' => {
+                let ticks = rest.len() - rest.trim_start_matches('`').len();
+                let fence = &rest[..ticks];
+                if let Some(end) = rest[ticks..].find(fence) {
+                    let code = rest[ticks..ticks + end].trim();
+                    html.push_str(&format!("<code>{}</code>", escape_html(code)));
+                    rest = &rest[ticks + end + ticks..];
+                } else {
+                    html.push_str(fence);
+                    rest = &rest[ticks..];
+                }
+            }
+            '[' => match inline_link(rest) {
+                Some((text, url, len)) => {
+                    html.push_str(&format!(
+                        "<a href=\"{}\">{}</a>",
+                        escape_html(url),
+                        inline_html(text)
+                    ));
+                    rest = &rest[len..];
+                }
+                None => {
+                    html.push('[');
+                    rest = &rest[1..];
+                }
+            },
+            '*' if rest.starts_with("**") => {
+                html.push_str(if strong { "</strong>" } else { "<strong>" });
+                strong = !strong;
+                rest = &rest[2..];
+            }
+            '*' | '_' if emphasis || rest[1..].starts_with(|c: char| !c.is_whitespace()) => {
+                // Only emphasis at the start of words, not in identifiers.
+                if !emphasis && c == '_' && html.ends_with(|c: char| c.is_alphanumeric()) {
+                    html.push(c);
+                } else {
+                    html.push_str(if emphasis { "</em>" } else { "<em>" });
+                    emphasis = !emphasis;
+                }
+                rest = &rest[1..];
+            }
+            '\\' if rest[1..].starts_with(|c: char| c.is_ascii_punctuation()) => {
+                html.push_str(&escape_html(&rest[1..2]));
+                rest = &rest[2..];
+            }
+            c => {
+                html.push_str(&escape_html(&rest[..c.len_utf8()]));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if emphasis {
+        html.push_str("</em>");
+    }
+    if strong {
+        html.push_str("</strong>");
+    }
+    html
+}
+
+/// The text, URL and length of a link like `[text](url)`.
+fn inline_link(s: &str) -> Option<(&str, &str, usize)> {
+    let text_end = s.find("](")?;
+    if s[1..text_end].contains(']') {
+        return None;
+    }
+    let url_end = s[text_end..].find(')')? + text_end;
+    Some((&s[1..text_end], &s[text_end + 2..url_end], url_end + 1))
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
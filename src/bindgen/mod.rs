@@ -39,26 +39,49 @@ macro_rules! deserialize_enum_str {
 mod bindings;
 mod bitflags;
 mod builder;
+mod cache;
 mod cargo;
 mod cdecl;
 mod config;
+mod config_keys;
 mod declarationtyperesolver;
 mod dependencies;
+mod doxygen;
 mod error;
+mod explain;
+mod fatptr;
+mod filter;
+mod graph;
 mod ir;
 mod library;
+mod macro_rules;
 mod mangle;
+mod markdown;
 mod monomorph;
 mod parser;
+mod reference;
+mod regex;
 mod rename;
 mod reserved;
+mod threadpool;
 mod utilities;
+mod visitor;
 mod writer;
 
 #[allow(unused)]
 pub(crate) use self::cargo::*;
+#[allow(unused)]
+pub(crate) use self::parser::find_source_files;
 
 pub use self::bindings::Bindings;
 pub use self::builder::Builder;
 pub use self::config::*;
 pub use self::error::Error;
+#[allow(unused)]
+pub use self::graph::{DependencyEdge, DependencyGraph, DependencyNode};
+#[allow(unused)]
+pub use self::ir::AnnotationValue;
+#[allow(unused)]
+pub use self::reference::{ApiReference, ReferenceDefinition, ReferenceMember, ReferencePage};
+#[allow(unused)]
+pub use self::visitor::{ItemVisitor, VisitedItem};
@@ -2,7 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::mem;
 
 use bindgen::ir::{Enum, GenericPath, OpaqueItem, Path, Struct, Type, Typedef, Union};
@@ -10,6 +10,7 @@ use bindgen::ir::{Enum, GenericPath, OpaqueItem, Path, Struct, Type, Typedef, Un
 #[derive(Default, Clone, Debug)]
 pub struct Monomorphs {
     replacements: HashMap<GenericPath, Path>,
+    mapped: HashSet<GenericPath>,
     opaques: Vec<OpaqueItem>,
     structs: Vec<Struct>,
     unions: Vec<Union>,
@@ -19,7 +20,16 @@ pub struct Monomorphs {
 
 impl Monomorphs {
     pub fn contains(&self, path: &GenericPath) -> bool {
-        self.replacements.contains_key(path)
+        self.replacements.contains_key(path) || self.mapped.contains(path)
+    }
+
+    /// Records a generic path to a mapped type, which is kept as is.
+    pub fn insert_mapped(&mut self, path: GenericPath) {
+        self.mapped.insert(path);
+    }
+
+    pub fn is_mapped(&self, path: &GenericPath) -> bool {
+        self.mapped.contains(path)
     }
 
     pub fn insert_struct(&mut self, generic: &Struct, monomorph: Struct, parameters: Vec<Type>) {
@@ -86,6 +96,23 @@ impl Monomorphs {
         self.replacements.get(path)
     }
 
+    /// Describes the generic item each monomorph instantiates, by its name.
+    pub fn origins(&self) -> Vec<(Path, String)> {
+        self.replacements
+            .iter()
+            .map(|(generic, path)| {
+                (
+                    path.clone(),
+                    format!(
+                        "Generated as an instantiation of the generic `{}`, as C has no \
+                         generics.",
+                        generic.path()
+                    ),
+                )
+            })
+            .collect()
+    }
+
     pub fn drain_opaques(&mut self) -> Vec<OpaqueItem> {
         mem::replace(&mut self.opaques, Vec::new())
     }
@@ -3,20 +3,27 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use std::collections::{HashMap, HashSet};
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Read;
+use std::mem;
 use std::path::{Path as FilePath, PathBuf as FilePathBuf};
 
 use syn;
 
 use bindgen::bitflags;
+use bindgen::cache::ParseCache;
+use bindgen::cargo::cargo_expand;
 use bindgen::cargo::{Cargo, PackageRef};
-use bindgen::config::MacroExpansionConfig;
+use bindgen::config::{MacroExpansionConfig, ParseExpandConfig};
 use bindgen::error::Error;
+use bindgen::explain::{ItemKind, ParseNote, ParseOutcome};
+use bindgen::filter::{self, Filter};
 use bindgen::ir::{
-    AnnotationSet, Cfg, Constant, Documentation, Enum, Function, GenericParams, ItemMap,
-    OpaqueItem, Path, Static, Struct, Type, Typedef, Union,
+    AnnotationSet, Cfg, Constant, Documentation, Enum, Function, GenericParams, GenericPath,
+    ItemMap, OpaqueItem, Path, PrimitiveType, Static, Struct, Type, Typedef, Union,
 };
+use bindgen::macro_rules::MacroRules;
+use bindgen::threadpool::ThreadPool;
 use bindgen::utilities::{SynAbiHelpers, SynItemHelpers};
 
 const STD_CRATES: &'static [&'static str] = &[
@@ -30,38 +37,102 @@ const STD_CRATES: &'static [&'static str] = &[
 
 type ParseResult = Result<Parse, Error>;
 
-/// Parses a single rust source file, not following `mod` or `extern crate`.
+/// Why functions, constants and statics of other crates are skipped.
+const NOT_BINDING_CRATE: &str = "not in the binding crate";
+
+/// How deeply macros may expand to other macros, as in rustc.
+const MACRO_RECURSION_LIMIT: usize = 128;
+
+/// A single source to parse, rather than a crate.
+#[derive(Debug, Clone)]
+pub enum Source {
+    /// A file, which is named after the crate it's in. The files of its
+    /// `mod`s are read from next to it.
+    File(FilePathBuf),
+    /// Source that's only in memory, like generated code. Its `mod`s must
+    /// have a body, as there are no files next to it.
+    Memory {
+        /// The name of the crate the items are in.
+        crate_name: String,
+        /// The path of the module the items are in, within the crate.
+        mod_path: Vec<String>,
+        source: String,
+    },
+}
+
+/// Parses a single rust source, following `mod` but not `extern crate`.
 pub fn parse_src(
-    src_file: &FilePath,
+    src: &Source,
     macro_expansion_config: &MacroExpansionConfig,
+    exclude: &[String],
+    jobs: usize,
 ) -> ParseResult {
-    let mod_name = src_file.file_stem().unwrap().to_str().unwrap();
+    let crate_name = match *src {
+        Source::File(ref path) => path.file_stem().unwrap().to_str().unwrap(),
+        Source::Memory { ref crate_name, .. } => crate_name,
+    };
+    let exclude = Filter::new("[parse] exclude", exclude)?;
 
     let mut context = Parser {
-        binding_crate_name: mod_name.to_owned(),
+        binding_crate_name: crate_name.to_owned(),
         macro_expansion_config,
         lib: None,
         parse_deps: true,
         include: None,
-        exclude: Vec::new(),
-        expand: Vec::new(),
-        expand_all_features: true,
-        expand_default_features: true,
-        expand_features: None,
+        exclude,
+        expand: ParseExpandConfig::default(),
         parsed_crates: HashSet::new(),
-        cache_src: HashMap::new(),
+        macro_items: Vec::new(),
+        cache: None,
+        cached_crates: HashMap::new(),
         cache_expanded_crate: HashMap::new(),
         cfg_stack: Vec::new(),
+        pool: ThreadPool::new(jobs),
+        requested_mods: HashSet::new(),
+        parsed_mods: HashMap::new(),
         out: Parse::new(),
     };
 
     let pkg_ref = PackageRef {
-        name: mod_name.to_owned(),
+        name: crate_name.to_owned(),
         version: "0.0.0".to_owned(),
     };
 
-    context.parse_mod(&pkg_ref, src_file)?;
-    Ok(context.out)
+    let mut parsed = ParsedCrate::new();
+    match *src {
+        Source::File(ref path) => {
+            context.parse_mod(&pkg_ref, &[], path, Vec::new(), &mut parsed)?;
+        }
+        Source::Memory {
+            ref mod_path,
+            ref source,
+            ..
+        } => {
+            if !mod_path.is_empty() && context.is_excluded_mod(&pkg_ref, mod_path) {
+                return Ok(parsed.parse);
+            }
+            let file = syn::parse_file(source).map_err(|x| Error::ParseSyntaxError {
+                crate_name: crate_name.to_owned(),
+                src_path: format!(
+                    "<{}>",
+                    filter::qualified_paths(crate_name, false, mod_path)[0]
+                ),
+                error: x,
+            })?;
+            let parsed_mod = ParsedMod::load(
+                crate_name,
+                macro_expansion_config,
+                crate_name,
+                None,
+                None,
+                &file.items,
+                &mut Vec::new(),
+            );
+            context.merge_mod(&pkg_ref, mod_path, parsed_mod, &mut parsed)?;
+        }
+    }
+    context.expand_macros(&pkg_ref, &mut parsed)?;
+    Ok(parsed.parse)
 }
 
 /// Recursively parses a rust library starting at the root crate's directory.
@@ -69,32 +140,47 @@ pub fn parse_src(
 /// Inside a crate, `mod` and `extern crate` declarations are followed
 /// and parsed. To find an external crate, the parser uses the `cargo metadata`
 /// command to find the location of dependencies.
+///
+/// Source files are read and parsed by `jobs` threads, ahead of when they
+/// are needed. Their items are still merged in the order the files are
+/// reached, so the result doesn't depend on how the work was scheduled.
 pub(crate) fn parse_lib(
     lib: Cargo,
     macro_expansion_config: &MacroExpansionConfig,
     parse_deps: bool,
     include: &Option<Vec<String>>,
     exclude: &[String],
-    expand: &[String],
-    expand_all_features: bool,
-    expand_default_features: bool,
-    expand_features: &Option<Vec<String>>,
+    expand: &ParseExpandConfig,
+    cache: Option<ParseCache>,
+    jobs: usize,
 ) -> ParseResult {
+    let include = match *include {
+        Some(ref include) => Some(Filter::new("[parse] include", include)?),
+        None => None,
+    };
+    for rule in include.iter().flat_map(|x| x.rules()) {
+        if rule.is_path() {
+            warn!("`[parse] include` only lists crates, ignoring `{}`.", rule);
+        }
+    }
+
     let mut context = Parser {
         binding_crate_name: lib.binding_crate_name().to_owned(),
         macro_expansion_config,
         lib: Some(lib),
         parse_deps: parse_deps,
-        include: include.clone(),
-        exclude: exclude.to_owned(),
-        expand: expand.to_owned(),
-        expand_all_features,
-        expand_default_features,
-        expand_features: expand_features.clone(),
+        include,
+        exclude: Filter::new("[parse] exclude", exclude)?,
+        expand: expand.clone(),
         parsed_crates: HashSet::new(),
-        cache_src: HashMap::new(),
+        macro_items: Vec::new(),
+        cache,
+        cached_crates: HashMap::new(),
         cache_expanded_crate: HashMap::new(),
         cfg_stack: Vec::new(),
+        pool: ThreadPool::new(jobs),
+        requested_mods: HashSet::new(),
+        parsed_mods: HashMap::new(),
         out: Parse::new(),
     };
 
@@ -103,29 +189,315 @@ pub(crate) fn parse_lib(
     Ok(context.out)
 }
 
-#[derive(Debug, Clone)]
+/// A source file to parse, along with everything its items depend on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ModKey {
+    crate_name: String,
+    crate_version: String,
+    path: FilePathBuf,
+    cfg_stack: Vec<Cfg>,
+}
+
+impl ModKey {
+    fn new(pkg: &PackageRef, path: &FilePath, cfg_stack: Vec<Cfg>) -> ModKey {
+        ModKey {
+            crate_name: pkg.name.clone(),
+            crate_version: pkg.version.clone(),
+            path: path.to_owned(),
+            cfg_stack,
+        }
+    }
+
+    fn pkg(&self) -> PackageRef {
+        PackageRef {
+            name: self.crate_name.clone(),
+            version: self.crate_version.clone(),
+        }
+    }
+}
+
+/// The items of a single module, parsed on its own.
+#[derive(Debug)]
+struct ParsedMod {
+    parse: Parse,
+    /// The associated constants of the module's `impl`s, which are added once
+    /// the items of the modules before it are known.
+    associated_constants: Vec<Constant>,
+    /// The modules, crates and macros the module refers to, in order.
+    children: Vec<ModChild>,
+}
+
+#[derive(Debug)]
+enum ModChild {
+    Inline(String, Box<ParsedMod>),
+    File(String, FilePathBuf, Vec<Cfg>),
+    /// A module whose file wasn't found, with the files it could be in.
+    Missing(String, Vec<FilePathBuf>),
+    ExternCrate(String, Vec<Cfg>),
+    Macro(MacroItem),
+}
+
+/// A `macro_rules!` definition or item macro invocation, which is expanded
+/// once the whole crate is loaded. Tokens can't be sent between threads, so
+/// they're kept as source.
+#[derive(Debug)]
+enum MacroItem {
+    Rules(String),
+    Call(String, Vec<Cfg>),
+}
+
+/// The macros defined so far while expanding the macros of a crate.
+struct MacroScope {
+    macros: HashMap<String, MacroRules>,
+    /// Invocations of macros that aren't defined yet, while the definitions
+    /// are still being collected.
+    deferred: Option<Vec<MacroCall>>,
+}
+
+/// An item macro invocation, along with the path of the module it's in.
+struct MacroCall {
+    mod_path: Vec<String>,
+    src: String,
+    cfg_stack: Vec<Cfg>,
+}
+
+fn macro_name(mac: &syn::Macro) -> Option<String> {
+    mac.path
+        .segments
+        .last()
+        .map(|x| x.value().ident.to_string())
+}
+
+impl ParsedMod {
+    fn load(
+        binding_crate_name: &str,
+        macro_expansion_config: &MacroExpansionConfig,
+        crate_name: &str,
+        mod_dir: Option<&FilePath>,
+        src_file: Option<&FilePath>,
+        items: &[syn::Item],
+        cfg_stack: &mut Vec<Cfg>,
+    ) -> ParsedMod {
+        let mut parse = Parse::new();
+        let associated_constants = parse.load_syn_crate_mod(
+            macro_expansion_config,
+            binding_crate_name,
+            crate_name,
+            Cfg::join(cfg_stack).as_ref(),
+            items,
+        );
+        for note in &mut parse.notes {
+            note.file = src_file.map(|x| x.to_owned());
+        }
+        let mut children = Vec::new();
+        let expand_macro_rules =
+            macro_expansion_config.macro_rules && crate_name == binding_crate_name;
+
+        for item in items {
+            if item.has_test_attr() {
+                continue;
+            }
+            match *item {
+                syn::Item::Macro(ref item) if expand_macro_rules => {
+                    let src = quote!(#item).to_string();
+                    if macro_name(&item.mac).as_deref() == Some("macro_rules") {
+                        children.push(ModChild::Macro(MacroItem::Rules(src)));
+                    } else {
+                        let mut cfg_stack = cfg_stack.clone();
+                        cfg_stack.extend(Cfg::load(&item.attrs));
+                        children.push(ModChild::Macro(MacroItem::Call(src, cfg_stack)));
+                    }
+                }
+                syn::Item::Mod(ref item) => {
+                    let next_mod_name = item.ident.to_string();
+
+                    let cfg = Cfg::load(&item.attrs);
+                    if let &Some(ref cfg) = &cfg {
+                        cfg_stack.push(cfg.clone());
+                    }
+
+                    if let Some((_, ref inline_items)) = item.content {
+                        let next_mod_dir = mod_dir.map(|x| x.join(&next_mod_name));
+                        let child = ParsedMod::load(
+                            binding_crate_name,
+                            macro_expansion_config,
+                            crate_name,
+                            next_mod_dir.as_deref(),
+                            src_file,
+                            inline_items,
+                            cfg_stack,
+                        );
+                        children.push(ModChild::Inline(next_mod_name, Box::new(child)));
+                    } else if let Some(mod_dir) = mod_dir {
+                        children.push(find_mod_file(mod_dir, item, cfg_stack));
+                    } else {
+                        // Only possible in the output of a macro.
+                        children.push(ModChild::Missing(next_mod_name, Vec::new()));
+                    }
+
+                    if cfg.is_some() {
+                        cfg_stack.pop();
+                    }
+                }
+                syn::Item::ExternCrate(ref item) => {
+                    let cfg = Cfg::load(&item.attrs);
+                    if let &Some(ref cfg) = &cfg {
+                        cfg_stack.push(cfg.clone());
+                    }
+
+                    children.push(ModChild::ExternCrate(
+                        item.ident.to_string(),
+                        cfg_stack.clone(),
+                    ));
+
+                    if cfg.is_some() {
+                        cfg_stack.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        ParsedMod {
+            parse,
+            associated_constants,
+            children,
+        }
+    }
+
+    fn load_file(
+        binding_crate_name: &str,
+        macro_expansion_config: &MacroExpansionConfig,
+        key: &ModKey,
+    ) -> Result<ParsedMod, Error> {
+        let mut s = String::new();
+        let mut f = File::open(&key.path).map_err(|_| Error::ParseCannotOpenFile {
+            crate_name: key.crate_name.clone(),
+            src_path: key.path.to_str().unwrap().to_owned(),
+        })?;
+        f.read_to_string(&mut s)
+            .map_err(|_| Error::ParseCannotOpenFile {
+                crate_name: key.crate_name.clone(),
+                src_path: key.path.to_str().unwrap().to_owned(),
+            })?;
+
+        let i = syn::parse_file(&s).map_err(|x| Error::ParseSyntaxError {
+            crate_name: key.crate_name.clone(),
+            src_path: key.path.to_string_lossy().into(),
+            error: x,
+        })?;
+
+        Ok(ParsedMod::load(
+            binding_crate_name,
+            macro_expansion_config,
+            &key.crate_name,
+            key.path.parent(),
+            Some(&key.path),
+            &i.items,
+            &mut key.cfg_stack.clone(),
+        ))
+    }
+}
+
+/// Finds the rust source files in a crate's directory, skipping any build
+/// output and hidden directories.
+pub fn find_source_files(dir: &FilePath, out: &mut Vec<FilePathBuf>) {
+    let mut entries: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|x| x.ok()).map(|x| x.path()).collect(),
+        Err(_) => return,
+    };
+    entries.sort();
+
+    for path in entries {
+        let is_rust_src = match path.file_name().and_then(|x| x.to_str()) {
+            Some(name) if name.starts_with('.') || name == "target" => continue,
+            Some(name) => name.ends_with(".rs"),
+            None => continue,
+        };
+        if path.is_dir() {
+            find_source_files(&path, out);
+        } else if is_rust_src {
+            out.push(path);
+        }
+    }
+}
+
+/// Finds the file for a `mod` declared without a body.
+fn find_mod_file(mod_dir: &FilePath, item: &syn::ItemMod, cfg_stack: &[Cfg]) -> ModChild {
+    let next_mod_name = item.ident.to_string();
+    let next_mod_path1 = mod_dir.join(next_mod_name.clone() + ".rs");
+    let next_mod_path2 = mod_dir.join(next_mod_name.clone()).join("mod.rs");
+
+    if next_mod_path1.exists() {
+        return ModChild::File(next_mod_name, next_mod_path1, cfg_stack.to_owned());
+    }
+    if next_mod_path2.exists() {
+        return ModChild::File(next_mod_name, next_mod_path2, cfg_stack.to_owned());
+    }
+
+    // Last chance to find a module path
+    for attr in &item.attrs {
+        match attr.interpret_meta() {
+            Some(syn::Meta::NameValue(syn::MetaNameValue { ident, lit, .. })) => match lit {
+                syn::Lit::Str(ref path) if ident == "path" => {
+                    let path = mod_dir.join(path.value());
+                    return ModChild::File(next_mod_name, path, cfg_stack.to_owned());
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+
+    ModChild::Missing(next_mod_name, vec![next_mod_path1, next_mod_path2])
+}
+
 struct Parser<'a> {
     binding_crate_name: String,
     macro_expansion_config: &'a MacroExpansionConfig,
     lib: Option<Cargo>,
     parse_deps: bool,
 
-    include: Option<Vec<String>>,
-    exclude: Vec<String>,
-    expand: Vec<String>,
-    expand_all_features: bool,
-    expand_default_features: bool,
-    expand_features: Option<Vec<String>>,
+    include: Option<Filter>,
+    exclude: Filter,
+    expand: ParseExpandConfig,
 
     parsed_crates: HashSet<String>,
-    cache_src: HashMap<FilePathBuf, Vec<syn::Item>>,
+    /// The macros found while merging the current crate's modules, with the
+    /// path of the module they're in.
+    macro_items: Vec<(Vec<String>, MacroItem)>,
+    cache: Option<ParseCache>,
+    /// Crates found in the parse cache ahead of being needed, by cache key.
+    cached_crates: HashMap<String, ParsedCrate>,
     cache_expanded_crate: HashMap<String, Vec<syn::Item>>,
 
     cfg_stack: Vec<Cfg>,
 
+    pool: ThreadPool<ModKey, Result<ParsedMod, Error>>,
+    requested_mods: HashSet<ModKey>,
+    parsed_mods: HashMap<ModKey, Result<ParsedMod, Error>>,
+
     out: Parse,
 }
 
+/// The items of a single crate, along with what's needed to parse the crates
+/// it depends on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedCrate {
+    pub parse: Parse,
+    /// The names of the `extern crate`s, with the `#[cfg]`s they are under.
+    pub dependencies: Vec<(String, Vec<Cfg>)>,
+}
+
+impl ParsedCrate {
+    pub(crate) fn new() -> ParsedCrate {
+        ParsedCrate {
+            parse: Parse::new(),
+            dependencies: Vec::new(),
+        }
+    }
+}
+
 impl<'a> Parser<'a> {
     fn should_parse_dependency(&self, pkg_name: &String) -> bool {
         if self.parsed_crates.contains(pkg_name) {
@@ -137,19 +509,30 @@ impl<'a> Parser<'a> {
         }
 
         // Skip any whitelist or blacklist for expand
-        if self.expand.contains(&pkg_name) {
+        if self.expand.crates.contains(&pkg_name) {
             return true;
         }
 
         // If we have a whitelist, check it
         if let Some(ref include) = self.include {
-            if !include.contains(&pkg_name) {
+            if include.find(pkg_name, &[]).is_none() {
                 return false;
             }
         }
 
+        if STD_CRATES.contains(&pkg_name.as_ref()) {
+            return false;
+        }
+
         // Check the blacklist
-        return !STD_CRATES.contains(&pkg_name.as_ref()) && !self.exclude.contains(&pkg_name);
+        if let Some(rule) = self.exclude.find(pkg_name, &[]) {
+            info!(
+                "Skipping crate `{}`, as it matches `{}` in `[parse] exclude`.",
+                pkg_name, rule
+            );
+            return false;
+        }
+        true
     }
 
     fn parse_crate(&mut self, pkg: &PackageRef) -> Result<(), Error> {
@@ -157,278 +540,490 @@ impl<'a> Parser<'a> {
         self.parsed_crates.insert(pkg.name.clone());
 
         // Check if we should use cargo expand for this crate
-        if self.expand.contains(&pkg.name) {
-            return self.parse_expand_crate(pkg);
+        let parsed = if self.expand.crates.contains(&pkg.name) {
+            self.parse_expand_crate(pkg)?
+        } else {
+            // Otherwise do our normal parse
+            let crate_src = self.lib.as_ref().unwrap().find_crate_src(pkg);
+
+            match crate_src {
+                Some(crate_src) => self.parse_crate_src(pkg, crate_src.as_path())?,
+                None => {
+                    // This should be an error, but is common enough to just elicit a warning
+                    warn!(
+                        "Parsing crate `{}`: can't find lib.rs with `cargo metadata`.",
+                        pkg.name
+                    );
+                    return Ok(());
+                }
+            }
+        };
+
+        self.out.extend_with(&parsed.parse);
+
+        // Dependencies are parsed once the whole crate is, so that its items
+        // don't depend on anything but its own source.
+        let cfg_stack = self.cfg_stack.clone();
+        for &(ref dep_pkg_name, ref dep_cfg_stack) in &parsed.dependencies {
+            self.cfg_stack = dep_cfg_stack.clone();
+            self.parse_dependency(pkg, dep_pkg_name)?;
         }
+        self.cfg_stack = cfg_stack;
 
-        // Otherwise do our normal parse
-        let crate_src = self.lib.as_ref().unwrap().find_crate_src(pkg);
+        Ok(())
+    }
 
-        match crate_src {
-            Some(crate_src) => self.parse_mod(pkg, crate_src.as_path()),
-            None => {
-                // This should be an error, but is common enough to just elicit a warning
-                warn!(
-                    "Parsing crate `{}`: can't find lib.rs with `cargo metadata`.",
-                    pkg.name
-                );
-                Ok(())
+    fn parse_dependency(&mut self, pkg: &PackageRef, dep_pkg_name: &String) -> Result<(), Error> {
+        if !self.should_parse_dependency(dep_pkg_name) {
+            return Ok(());
+        }
+
+        let dep_pkg_ref = self.lib.as_ref().unwrap().find_dep_ref(pkg, dep_pkg_name);
+
+        if let Some(dep_pkg_ref) = dep_pkg_ref {
+            self.parse_crate(&dep_pkg_ref)
+        } else {
+            error!(
+                "Parsing crate `{}`: can't find dependency version for `{}`.",
+                pkg.name, dep_pkg_name
+            );
+            Ok(())
+        }
+    }
+
+    /// The key for the parse cache, covering everything besides the source
+    /// files that affects how a crate is parsed.
+    fn cache_key(&self, pkg: &PackageRef, crate_src: &FilePath, cfg_stack: &[Cfg]) -> String {
+        let exclude: Vec<_> = self.exclude.rules().iter().map(|x| x.to_string()).collect();
+        format!(
+            "{}\n{} {}\n{:?}\n{}\n{:?}\n{:?}\n{:?}",
+            ::bindgen::VERSION,
+            pkg.name,
+            pkg.version,
+            crate_src,
+            self.binding_crate_name,
+            self.macro_expansion_config,
+            cfg_stack,
+            exclude
+        )
+    }
+
+    fn parse_crate_src(
+        &mut self,
+        pkg: &PackageRef,
+        crate_src: &FilePath,
+    ) -> Result<ParsedCrate, Error> {
+        let key = self.cache_key(pkg, crate_src, &self.cfg_stack);
+
+        if let Some(parsed) = self.cached_crates.remove(&key) {
+            info!("Using cached parse of crate `{}`.", pkg.name);
+            return Ok(parsed);
+        }
+        if let Some(ref cache) = self.cache {
+            if let Some(parsed) = cache.load(&pkg.name, &key) {
+                info!("Using cached parse of crate `{}`.", pkg.name);
+                return Ok(parsed);
             }
         }
+
+        let mut parsed = ParsedCrate::new();
+        let cfg_stack = self.cfg_stack.clone();
+        self.parse_mod(pkg, &[], crate_src, cfg_stack, &mut parsed)?;
+        self.expand_macros(pkg, &mut parsed)?;
+
+        if let Some(ref cache) = self.cache {
+            cache.store(&pkg.name, &key, &parsed);
+        }
+
+        Ok(parsed)
     }
 
-    fn parse_expand_crate(&mut self, pkg: &PackageRef) -> Result<(), Error> {
-        assert!(self.lib.is_some());
+    /// Expands a crate with `cargo rustc --pretty=expanded`, or loads the
+    /// result of an earlier expansion from the parse cache.
+    fn expand_crate_src(&self, pkg: &PackageRef) -> Result<String, Error> {
+        let lib = self.lib.as_ref().unwrap();
 
-        let mod_parsed = {
-            if !self.cache_expanded_crate.contains_key(&pkg.name) {
-                let s = self
-                    .lib
-                    .as_ref()
-                    .unwrap()
-                    .expand_crate(
-                        pkg,
-                        self.expand_all_features,
-                        self.expand_default_features,
-                        &self.expand_features,
-                    )
-                    .map_err(|x| Error::CargoExpand(pkg.name.clone(), x))?;
-                let i = syn::parse_file(&s).map_err(|x| Error::ParseSyntaxError {
-                    crate_name: pkg.name.clone(),
-                    src_path: "".to_owned(),
-                    error: x,
-                })?;
-                self.cache_expanded_crate.insert(pkg.name.clone(), i.items);
-            }
-
-            self.cache_expanded_crate.get(&pkg.name).unwrap().clone()
+        let cache = match self.cache {
+            Some(ref cache) => cache,
+            None => {
+                return lib
+                    .expand_crate(pkg, &self.expand)
+                    .map_err(|x| Error::CargoExpand(pkg.name.clone(), x));
+            }
         };
 
-        self.process_expanded_mod(pkg, &mod_parsed)
+        // The expansion depends on every crate the expanded one uses, so it
+        // is keyed on `Cargo.lock` as well as on how the crate is expanded,
+        // including the rustc flags we're run with.
+        let lock = fs::read_to_string(lib.lock_path()).unwrap_or_default();
+        let key = format!(
+            "{}\n{} {}\n{:?}\n{:?}\n{}",
+            ::bindgen::VERSION,
+            pkg.name,
+            pkg.version,
+            self.expand,
+            cargo_expand::rustflags(&self.expand.rustflags),
+            lock
+        );
+
+        if let Some(src) = cache.load_expanded(&pkg.name, &key) {
+            info!("Using cached expansion of crate `{}`.", pkg.name);
+            return Ok(src);
+        }
+
+        let src = lib
+            .expand_crate(pkg, &self.expand)
+            .map_err(|x| Error::CargoExpand(pkg.name.clone(), x))?;
+        cache.store_expanded(&pkg.name, &key, &self.expanded_crate_files(pkg), &src);
+
+        Ok(src)
+    }
+
+    /// The files an expanded crate may have been expanded from.
+    fn expanded_crate_files(&self, pkg: &PackageRef) -> Vec<FilePathBuf> {
+        let mut files = Vec::new();
+        if let Some(crate_dir) = self.lib.as_ref().unwrap().find_crate_dir(pkg) {
+            files.push(crate_dir.join("Cargo.toml"));
+            find_source_files(&crate_dir, &mut files);
+        }
+        files
     }
 
-    fn process_expanded_mod(&mut self, pkg: &PackageRef, items: &[syn::Item]) -> Result<(), Error> {
-        self.out.load_syn_crate_mod(
-            &self.macro_expansion_config,
+    fn parse_expand_crate(&mut self, pkg: &PackageRef) -> Result<ParsedCrate, Error> {
+        assert!(self.lib.is_some());
+
+        if !self.cache_expanded_crate.contains_key(&pkg.name) {
+            let s = self.expand_crate_src(pkg)?;
+            let i = syn::parse_file(&s).map_err(|x| Error::ParseSyntaxError {
+                crate_name: pkg.name.clone(),
+                src_path: "".to_owned(),
+                error: x,
+            })?;
+            self.cache_expanded_crate.insert(pkg.name.clone(), i.items);
+        }
+        let mod_parsed = self.cache_expanded_crate.get(&pkg.name).unwrap().clone();
+
+        // The expanded crate is a single file with every module inline.
+        let parsed_mod = ParsedMod::load(
             &self.binding_crate_name,
+            self.macro_expansion_config,
             &pkg.name,
-            Cfg::join(&self.cfg_stack).as_ref(),
-            items,
+            None,
+            None,
+            &mod_parsed,
+            &mut self.cfg_stack.clone(),
         );
 
-        for item in items {
-            if item.has_test_attr() {
-                continue;
-            }
-            match *item {
-                syn::Item::Mod(ref item) => {
-                    let cfg = Cfg::load(&item.attrs);
-                    if let &Some(ref cfg) = &cfg {
-                        self.cfg_stack.push(cfg.clone());
-                    }
+        let mut parsed = ParsedCrate::new();
+        parsed.parse.source_files = self.expanded_crate_files(pkg);
+        self.merge_mod(pkg, &[], parsed_mod, &mut parsed)?;
+        self.expand_macros(pkg, &mut parsed)?;
+        Ok(parsed)
+    }
 
-                    if let Some((_, ref inline_items)) = item.content {
-                        self.process_expanded_mod(pkg, inline_items)?;
-                    } else {
-                        unreachable!();
-                    }
+    fn parse_mod(
+        &mut self,
+        pkg: &PackageRef,
+        mod_path: &[String],
+        src_path: &FilePath,
+        cfg_stack: Vec<Cfg>,
+        out: &mut ParsedCrate,
+    ) -> Result<(), Error> {
+        out.parse.source_files.push(src_path.to_path_buf());
 
-                    if cfg.is_some() {
-                        self.cfg_stack.pop();
-                    }
-                }
-                syn::Item::ExternCrate(ref item) => {
-                    let dep_pkg_name = item.ident.to_string();
+        let parsed_mod = self.wait_for_mod(ModKey::new(pkg, src_path, cfg_stack))?;
+        self.merge_mod(pkg, mod_path, parsed_mod, out)
+    }
 
-                    let cfg = Cfg::load(&item.attrs);
-                    if let &Some(ref cfg) = &cfg {
-                        self.cfg_stack.push(cfg.clone());
+    /// Adds the items of a module and of its children to `out`, in the same
+    /// order as if they were all parsed one after the other. `mod_path` is
+    /// the path of the module within the crate.
+    fn merge_mod(
+        &mut self,
+        pkg: &PackageRef,
+        mod_path: &[String],
+        mut parsed_mod: ParsedMod,
+        out: &mut ParsedCrate,
+    ) -> Result<(), Error> {
+        let binding_crate = pkg.name == self.binding_crate_name;
+        for note in &mut parsed_mod.parse.notes {
+            note.binding_crate = binding_crate;
+            note.module = mod_path.to_owned();
+        }
+        out.parse.extend_with(&parsed_mod.parse);
+        out.parse
+            .add_associated_constants(&pkg.name, parsed_mod.associated_constants);
+
+        for child in parsed_mod.children {
+            let child_path = |name: &str| {
+                let mut child_path = mod_path.to_owned();
+                child_path.push(name.to_owned());
+                child_path
+            };
+            match child {
+                ModChild::Inline(name, child) => {
+                    let child_path = child_path(&name);
+                    if !self.is_excluded_mod(pkg, &child_path) {
+                        self.merge_mod(pkg, &child_path, *child, out)?;
                     }
-
-                    if self.should_parse_dependency(&dep_pkg_name) {
-                        if self.lib.is_some() {
-                            let dep_pkg_ref =
-                                self.lib.as_ref().unwrap().find_dep_ref(pkg, &dep_pkg_name);
-
-                            if let Some(dep_pkg_ref) = dep_pkg_ref {
-                                self.parse_crate(&dep_pkg_ref)?;
-                            } else {
-                                error!(
-                                    "Parsing crate `{}`: can't find dependency version for `{}`.",
-                                    pkg.name, dep_pkg_name
-                                );
-                            }
-                        } else {
-                            error!(
-                                "Parsing crate `{}`: cannot parse external crate `{}` because \
-                                 cbindgen is in single source mode. Consider specifying a crate \
-                                 directory instead of a source file.",
-                                pkg.name, dep_pkg_name
-                            );
-                        }
+                }
+                ModChild::File(name, path, cfg_stack) => {
+                    let child_path = child_path(&name);
+                    if !self.is_excluded_mod(pkg, &child_path) {
+                        self.parse_mod(pkg, &child_path, &path, cfg_stack, out)?;
                     }
-
-                    if cfg.is_some() {
-                        self.cfg_stack.pop();
+                }
+                ModChild::Missing(name, paths) => {
+                    // This should be an error, but it's common enough to
+                    // just elicit a warning
+                    warn!("Parsing crate `{}`: can't find mod {}`.", pkg.name, name);
+                    out.parse.missing_files.extend(paths);
+                }
+                ModChild::ExternCrate(dep_pkg_name, cfg_stack) => {
+                    if self.lib.is_some() {
+                        out.dependencies.push((dep_pkg_name, cfg_stack));
+                    } else if self.should_parse_dependency(&dep_pkg_name) {
+                        error!(
+                            "Parsing crate `{}`: cannot parse external crate `{}` because \
+                             cbindgen is in single source mode. Consider specifying a crate \
+                             directory instead of a source file.",
+                            pkg.name, dep_pkg_name
+                        );
                     }
                 }
-                _ => {}
+                ModChild::Macro(item) => self.macro_items.push((mod_path.to_owned(), item)),
             }
         }
 
         Ok(())
     }
 
-    fn parse_mod(&mut self, pkg: &PackageRef, mod_path: &FilePath) -> Result<(), Error> {
-        let mod_parsed = {
-            let owned_mod_path = mod_path.to_path_buf();
-
-            if !self.cache_src.contains_key(&owned_mod_path) {
-                let mut s = String::new();
-                let mut f = File::open(mod_path).map_err(|_| Error::ParseCannotOpenFile {
-                    crate_name: pkg.name.clone(),
-                    src_path: mod_path.to_str().unwrap().to_owned(),
-                })?;
-                f.read_to_string(&mut s)
-                    .map_err(|_| Error::ParseCannotOpenFile {
-                        crate_name: pkg.name.clone(),
-                        src_path: mod_path.to_str().unwrap().to_owned(),
-                    })?;
-
-                let i = syn::parse_file(&s).map_err(|x| Error::ParseSyntaxError {
-                    crate_name: pkg.name.clone(),
-                    src_path: owned_mod_path.to_string_lossy().into(),
-                    error: x,
-                })?;
-
-                self.cache_src.insert(owned_mod_path.clone(), i.items);
+    fn is_excluded_mod(&self, pkg: &PackageRef, mod_path: &[String]) -> bool {
+        let binding_crate = pkg.name == self.binding_crate_name;
+        let paths = filter::qualified_paths(&pkg.name, binding_crate, mod_path);
+        match self.exclude.find_path(&paths) {
+            Some(rule) => {
+                info!(
+                    "Skipping mod `{}`, as it matches `{}` in `[parse] exclude`.",
+                    paths[0], rule
+                );
+                true
             }
+            None => false,
+        }
+    }
 
-            self.cache_src.get(&owned_mod_path).unwrap().clone()
+    /// Expands the item macros of a crate that were defined with
+    /// `macro_rules!` in the crate.
+    ///
+    /// Each invocation uses the last definition of the macro before it in
+    /// the crate, or else the last one after it.
+    fn expand_macros(&mut self, pkg: &PackageRef, out: &mut ParsedCrate) -> Result<(), Error> {
+        let items = mem::replace(&mut self.macro_items, Vec::new());
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let mut scope = MacroScope {
+            macros: HashMap::new(),
+            deferred: Some(Vec::new()),
         };
+        self.expand_macro_items(pkg, items, &mut scope, 0, out)?;
+        for call in scope.deferred.take().unwrap() {
+            self.expand_macro_call(pkg, call, &mut scope, 0, out)?;
+        }
 
-        let mod_dir = mod_path.parent().unwrap();
+        Ok(())
+    }
 
-        self.process_mod(pkg, mod_dir, &mod_parsed)
+    fn expand_macro_items(
+        &mut self,
+        pkg: &PackageRef,
+        items: Vec<(Vec<String>, MacroItem)>,
+        scope: &mut MacroScope,
+        depth: usize,
+        out: &mut ParsedCrate,
+    ) -> Result<(), Error> {
+        for (mod_path, item) in items {
+            match item {
+                MacroItem::Rules(src) => {
+                    let item = syn::parse_str::<syn::ItemMacro>(&src).unwrap();
+                    match MacroRules::load(&item) {
+                        Ok(rules) => {
+                            scope.macros.insert(rules.name().to_owned(), rules);
+                        }
+                        Err(msg) => {
+                            warn!("Parsing crate `{}`: can't expand macro: {}", pkg.name, msg)
+                        }
+                    }
+                }
+                MacroItem::Call(src, cfg_stack) => {
+                    let call = MacroCall {
+                        mod_path,
+                        src,
+                        cfg_stack,
+                    };
+                    self.expand_macro_call(pkg, call, scope, depth, out)?
+                }
+            }
+        }
+
+        Ok(())
     }
 
-    fn process_mod(
+    fn expand_macro_call(
         &mut self,
         pkg: &PackageRef,
-        mod_dir: &FilePath,
-        items: &[syn::Item],
+        mut call: MacroCall,
+        scope: &mut MacroScope,
+        depth: usize,
+        out: &mut ParsedCrate,
     ) -> Result<(), Error> {
-        self.out.load_syn_crate_mod(
-            &self.macro_expansion_config,
+        let item = syn::parse_str::<syn::ItemMacro>(&call.src).unwrap();
+        let name = match macro_name(&item.mac) {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        if name == "bitflags" && self.macro_expansion_config.bitflags {
+            return Ok(());
+        }
+
+        let items = match scope.macros.get(&name) {
+            Some(rules) => {
+                if depth == MACRO_RECURSION_LIMIT {
+                    warn!(
+                        "Parsing crate `{}`: recursion limit reached while expanding `{}!`.",
+                        pkg.name, name
+                    );
+                    return Ok(());
+                }
+                match rules.expand(item.mac.tts.clone()) {
+                    Ok(items) => items,
+                    Err(msg) => {
+                        warn!("Parsing crate `{}`: can't expand macro: {}", pkg.name, msg);
+                        return Ok(());
+                    }
+                }
+            }
+            None => {
+                // The macro may be defined later on, or not be from this
+                // crate at all.
+                if let Some(ref mut deferred) = scope.deferred {
+                    deferred.push(call);
+                }
+                return Ok(());
+            }
+        };
+
+        let parsed_mod = ParsedMod::load(
             &self.binding_crate_name,
+            self.macro_expansion_config,
             &pkg.name,
-            Cfg::join(&self.cfg_stack).as_ref(),
-            items,
+            None,
+            None,
+            &items,
+            &mut call.cfg_stack,
         );
+        self.merge_mod(pkg, &call.mod_path, parsed_mod, out)?;
 
-        for item in items {
-            if item.has_test_attr() {
-                continue;
-            }
-            match *item {
-                syn::Item::Mod(ref item) => {
-                    let next_mod_name = item.ident.to_string();
+        // The expansion may itself define or invoke macros.
+        let items = mem::replace(&mut self.macro_items, Vec::new());
+        self.expand_macro_items(pkg, items, scope, depth + 1, out)
+    }
 
-                    let cfg = Cfg::load(&item.attrs);
-                    if let &Some(ref cfg) = &cfg {
-                        self.cfg_stack.push(cfg.clone());
-                    }
+    /// Starts parsing a source file in the background, if it isn't already.
+    fn request_mod(&mut self, key: ModKey) {
+        if !self.requested_mods.insert(key.clone()) {
+            return;
+        }
 
-                    if let Some((_, ref inline_items)) = item.content {
-                        self.process_mod(pkg, &mod_dir.join(&next_mod_name), inline_items)?;
-                    } else {
-                        let next_mod_path1 = mod_dir.join(next_mod_name.clone() + ".rs");
-                        let next_mod_path2 = mod_dir.join(next_mod_name.clone()).join("mod.rs");
-
-                        if next_mod_path1.exists() {
-                            self.parse_mod(pkg, next_mod_path1.as_path())?;
-                        } else if next_mod_path2.exists() {
-                            self.parse_mod(pkg, next_mod_path2.as_path())?;
-                        } else {
-                            // Last chance to find a module path
-                            let mut path_attr_found = false;
-                            for attr in &item.attrs {
-                                match attr.interpret_meta() {
-                                    Some(syn::Meta::NameValue(syn::MetaNameValue {
-                                        ident,
-                                        lit,
-                                        ..
-                                    })) => match lit {
-                                        syn::Lit::Str(ref path) if ident == "path" => {
-                                            path_attr_found = true;
-                                            self.parse_mod(pkg, &mod_dir.join(path.value()))?;
-                                            break;
-                                        }
-                                        _ => (),
-                                    },
-                                    _ => (),
-                                }
-                            }
-
-                            // This should be an error, but it's common enough to
-                            // just elicit a warning
-                            if !path_attr_found {
-                                warn!(
-                                    "Parsing crate `{}`: can't find mod {}`.",
-                                    pkg.name, next_mod_name
-                                );
-                            }
-                        }
-                    }
+        let binding_crate_name = self.binding_crate_name.clone();
+        let macro_expansion_config = self.macro_expansion_config.clone();
+        self.pool.submit(key.clone(), move || {
+            ParsedMod::load_file(&binding_crate_name, &macro_expansion_config, &key)
+        });
+    }
 
-                    if cfg.is_some() {
-                        self.cfg_stack.pop();
-                    }
-                }
-                syn::Item::ExternCrate(ref item) => {
-                    let dep_pkg_name = item.ident.to_string();
+    fn wait_for_mod(&mut self, key: ModKey) -> Result<ParsedMod, Error> {
+        self.request_mod(key.clone());
 
-                    let cfg = Cfg::load(&item.attrs);
-                    if let &Some(ref cfg) = &cfg {
-                        self.cfg_stack.push(cfg.clone());
-                    }
+        loop {
+            if let Some(result) = self.parsed_mods.remove(&key) {
+                // Allow the file to be parsed again if it's included twice.
+                self.requested_mods.remove(&key);
+                return result;
+            }
 
-                    if self.should_parse_dependency(&dep_pkg_name) {
-                        if self.lib.is_some() {
-                            let dep_pkg_ref =
-                                self.lib.as_ref().unwrap().find_dep_ref(pkg, &dep_pkg_name);
-
-                            if let Some(dep_pkg_ref) = dep_pkg_ref {
-                                self.parse_crate(&dep_pkg_ref)?;
-                            } else {
-                                error!(
-                                    "Parsing crate `{}`: can't find dependency version for `{}`.",
-                                    pkg.name, dep_pkg_name
-                                );
-                            }
-                        } else {
-                            error!(
-                                "Parsing crate `{}`: cannot parse external crate `{}` because \
-                                 cbindgen is in single source mode. Consider specifying a crate \
-                                 directory instead of a source file.",
-                                pkg.name, dep_pkg_name
-                            );
-                        }
-                    }
+            let (parsed_key, result) = self.pool.recv();
+            if let Ok(ref parsed_mod) = result {
+                self.prefetch(&parsed_key, parsed_mod);
+            }
+            self.parsed_mods.insert(parsed_key, result);
+        }
+    }
 
-                    if cfg.is_some() {
-                        self.cfg_stack.pop();
-                    }
+    /// Starts parsing the files and crates that a module refers to, since
+    /// they will most likely be needed next.
+    fn prefetch(&mut self, key: &ModKey, parsed_mod: &ParsedMod) {
+        for child in &parsed_mod.children {
+            match child {
+                &ModChild::Inline(_, ref child) => self.prefetch(key, child),
+                &ModChild::File(_, ref path, ref cfg_stack) => {
+                    let pkg = key.pkg();
+                    self.request_mod(ModKey::new(&pkg, path, cfg_stack.clone()));
+                }
+                &ModChild::Missing(..) | &ModChild::Macro(_) => {}
+                &ModChild::ExternCrate(ref dep_pkg_name, ref cfg_stack) => {
+                    self.prefetch_crate(&key.pkg(), dep_pkg_name, cfg_stack);
                 }
-                _ => {}
             }
         }
+    }
 
-        Ok(())
+    fn prefetch_crate(&mut self, pkg: &PackageRef, dep_pkg_name: &String, cfg_stack: &[Cfg]) {
+        if self.lib.is_none()
+            || !self.should_parse_dependency(dep_pkg_name)
+            || self.expand.crates.contains(dep_pkg_name)
+        {
+            return;
+        }
+
+        let (dep_pkg_ref, crate_src) = {
+            let lib = self.lib.as_ref().unwrap();
+            let dep_pkg_ref = match lib.find_dep_ref(pkg, dep_pkg_name) {
+                Some(dep_pkg_ref) => dep_pkg_ref,
+                None => return,
+            };
+            match lib.find_crate_src(&dep_pkg_ref) {
+                Some(crate_src) => (dep_pkg_ref, crate_src),
+                None => return,
+            }
+        };
+
+        // A crate that is cached doesn't need to be parsed, but the crates it
+        // depends on might.
+        let key = self.cache_key(&dep_pkg_ref, &crate_src, cfg_stack);
+        if self.cached_crates.contains_key(&key) {
+            return;
+        }
+        let cached = self
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.load(&dep_pkg_ref.name, &key));
+        if let Some(parsed) = cached {
+            let dependencies = parsed.dependencies.clone();
+            self.cached_crates.insert(key, parsed);
+            for &(ref name, ref cfg_stack) in &dependencies {
+                self.prefetch_crate(&dep_pkg_ref, name, cfg_stack);
+            }
+            return;
+        }
+
+        self.request_mod(ModKey::new(&dep_pkg_ref, &crate_src, cfg_stack.to_owned()));
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Parse {
     pub constants: ItemMap<Constant>,
     pub globals: ItemMap<Static>,
@@ -438,6 +1033,13 @@ pub struct Parse {
     pub opaque_items: ItemMap<OpaqueItem>,
     pub typedefs: ItemMap<Typedef>,
     pub functions: Vec<Function>,
+    /// The source files the items were parsed from.
+    pub source_files: Vec<FilePathBuf>,
+    /// The files that would have been parsed if they existed, like those of a
+    /// `mod` whose file wasn't found.
+    pub missing_files: Vec<FilePathBuf>,
+    /// What became of each item, for explaining it.
+    pub notes: Vec<ParseNote>,
 }
 
 impl Parse {
@@ -451,6 +1053,9 @@ impl Parse {
             opaque_items: ItemMap::new(),
             typedefs: ItemMap::new(),
             functions: Vec::new(),
+            source_files: Vec::new(),
+            missing_files: Vec::new(),
+            notes: Vec::new(),
         }
     }
 
@@ -481,6 +1086,46 @@ impl Parse {
         add_opaque("BTreeSet", vec!["T"]);
         add_opaque("LinkedList", vec!["T"]);
         add_opaque("VecDeque", vec!["T"]);
+
+        // These have the same layout as the type they wrap. They're normally
+        // simplified away, but can still be referred to as generic arguments.
+        let mut add_alias = |path: &str, generic_params: Vec<&str>, aliased: Type| {
+            let path = Path::new(path);
+            let generic_params: Vec<_> = generic_params.into_iter().map(|s| Path::new(s)).collect();
+            self.typedefs.try_insert(Typedef::new(
+                path,
+                GenericParams(generic_params),
+                aliased,
+                None,
+                AnnotationSet::new(),
+                Documentation::none(),
+            ))
+        };
+        let generic = || Type::Path(GenericPath::new(Path::new("T"), vec![]));
+
+        add_alias("ManuallyDrop", vec!["T"], generic());
+        add_alias("MaybeUninit", vec!["T"], generic());
+        add_alias("Cell", vec!["T"], generic());
+        add_alias("UnsafeCell", vec!["T"], generic());
+        add_alias("Wrapping", vec!["T"], generic());
+        add_alias("NonZeroU8", vec![], Type::Primitive(PrimitiveType::UInt8));
+        add_alias("NonZeroU16", vec![], Type::Primitive(PrimitiveType::UInt16));
+        add_alias("NonZeroU32", vec![], Type::Primitive(PrimitiveType::UInt32));
+        add_alias("NonZeroU64", vec![], Type::Primitive(PrimitiveType::UInt64));
+        add_alias(
+            "NonZeroUsize",
+            vec![],
+            Type::Primitive(PrimitiveType::USize),
+        );
+        add_alias("NonZeroI8", vec![], Type::Primitive(PrimitiveType::Int8));
+        add_alias("NonZeroI16", vec![], Type::Primitive(PrimitiveType::Int16));
+        add_alias("NonZeroI32", vec![], Type::Primitive(PrimitiveType::Int32));
+        add_alias("NonZeroI64", vec![], Type::Primitive(PrimitiveType::Int64));
+        add_alias(
+            "NonZeroIsize",
+            vec![],
+            Type::Primitive(PrimitiveType::ISize),
+        );
     }
 
     pub fn extend_with(&mut self, other: &Parse) {
@@ -492,8 +1137,26 @@ impl Parse {
         self.opaque_items.extend_with(&other.opaque_items);
         self.typedefs.extend_with(&other.typedefs);
         self.functions.extend_from_slice(&other.functions);
+        self.source_files.extend_from_slice(&other.source_files);
+        self.missing_files.extend_from_slice(&other.missing_files);
+        self.notes.extend_from_slice(&other.notes);
     }
 
+    fn note(
+        &mut self,
+        crate_name: &str,
+        kind: ItemKind,
+        ident: &syn::Ident,
+        cfg: Option<Cfg>,
+        outcome: ParseOutcome,
+    ) {
+        self.notes
+            .push(ParseNote::new(ident, kind, crate_name, cfg, outcome));
+    }
+
+    /// Loads the items of a module. The associated constants of its `impl`s
+    /// are returned rather than added, as the items they belong to may be in
+    /// a module that isn't loaded yet.
     pub fn load_syn_crate_mod(
         &mut self,
         macro_expansion_config: &MacroExpansionConfig,
@@ -501,7 +1164,7 @@ impl Parse {
         crate_name: &str,
         mod_cfg: Option<&Cfg>,
         items: &[syn::Item],
-    ) {
+    ) -> Vec<Constant> {
         let mut impls_with_assoc_consts = Vec::new();
 
         for item in items {
@@ -549,27 +1212,57 @@ impl Parse {
             }
         }
 
-        for item_impl in impls_with_assoc_consts {
-            self.load_syn_assoc_consts_from_impl(crate_name, mod_cfg, item_impl)
+        let notes = &mut self.notes;
+        impls_with_assoc_consts
+            .into_iter()
+            .flat_map(|item_impl| {
+                Self::load_syn_assoc_consts_from_impl(crate_name, mod_cfg, item_impl, notes)
+            })
+            .collect()
+    }
+
+    /// Adds associated constants to the structs they belong to, or as regular
+    /// constants if there's no such struct.
+    pub fn add_associated_constants(&mut self, crate_name: &str, constants: Vec<Constant>) {
+        for constant in constants {
+            let impl_path = constant.associated_to.clone().unwrap();
+            let mut any = false;
+            self.structs.for_items_mut(&impl_path, |item| {
+                any = true;
+                item.add_associated_constant(constant.clone());
+            });
+            // Handle associated constants to other item types that are
+            // not structs like enums or such as regular constants.
+            if any {
+                continue;
+            }
+            let name = constant.path.clone();
+            if !self.constants.try_insert(constant) {
+                error!(
+                    "Conflicting name for constant {}::{}::{}.",
+                    crate_name, impl_path, name,
+                );
+            }
         }
     }
 
     fn load_syn_assoc_consts_from_impl(
-        &mut self,
         crate_name: &str,
         mod_cfg: Option<&Cfg>,
         item_impl: &syn::ItemImpl,
-    ) {
+        notes: &mut Vec<ParseNote>,
+    ) -> Vec<Constant> {
         let associated_constants = item_impl.items.iter().filter_map(|item| match item {
             syn::ImplItem::Const(ref associated_constant) => Some(associated_constant),
             _ => None,
         });
-        self.load_syn_assoc_consts(
+        Self::load_syn_assoc_consts(
             crate_name,
             mod_cfg,
             &item_impl.self_ty,
             associated_constants,
-        );
+            notes,
+        )
     }
 
     /// Enters a `extern "C" { }` declaration and loads function declarations.
@@ -588,11 +1281,19 @@ impl Parse {
         for foreign_item in &item.items {
             match *foreign_item {
                 syn::ForeignItem::Fn(ref function) => {
+                    let cfg = Cfg::append(mod_cfg, Cfg::load(&function.attrs));
                     if crate_name != binding_crate_name {
                         info!(
                             "Skip {}::{} - (fn's outside of the binding crate are not used).",
                             crate_name, &function.ident
                         );
+                        self.note(
+                            crate_name,
+                            ItemKind::Fn,
+                            &function.ident,
+                            cfg,
+                            ParseOutcome::Skipped(NOT_BINDING_CRATE.to_owned()),
+                        );
                         return;
                     }
                     let path = Path::new(function.ident.to_string());
@@ -601,12 +1302,26 @@ impl Parse {
                             info!("Take {}::{}.", crate_name, &function.ident);
 
                             self.functions.push(func);
+                            self.note(
+                                crate_name,
+                                ItemKind::Fn,
+                                &function.ident,
+                                cfg,
+                                ParseOutcome::Taken,
+                            );
                         }
                         Err(msg) => {
                             error!(
                                 "Cannot use fn {}::{} ({}).",
                                 crate_name, &function.ident, msg
                             );
+                            self.note(
+                                crate_name,
+                                ItemKind::Fn,
+                                &function.ident,
+                                cfg,
+                                ParseOutcome::Skipped(msg),
+                            );
                         }
                     }
                 }
@@ -623,11 +1338,19 @@ impl Parse {
         mod_cfg: Option<&Cfg>,
         item: &syn::ItemFn,
     ) {
+        let cfg = Cfg::append(mod_cfg, Cfg::load(&item.attrs));
         if crate_name != binding_crate_name {
             info!(
                 "Skip {}::{} - (fn's outside of the binding crate are not used).",
                 crate_name, &item.ident
             );
+            self.note(
+                crate_name,
+                ItemKind::Fn,
+                &item.ident,
+                cfg,
+                ParseOutcome::Skipped(NOT_BINDING_CRATE.to_owned()),
+            );
             return;
         }
 
@@ -639,9 +1362,23 @@ impl Parse {
                         info!("Take {}::{}.", crate_name, &item.ident);
 
                         self.functions.push(func);
+                        self.note(
+                            crate_name,
+                            ItemKind::Fn,
+                            &item.ident,
+                            cfg,
+                            ParseOutcome::Taken,
+                        );
                     }
                     Err(msg) => {
                         error!("Cannot use fn {}::{} ({}).", crate_name, &item.ident, msg);
+                        self.note(
+                            crate_name,
+                            ItemKind::Fn,
+                            &item.ident,
+                            cfg,
+                            ParseOutcome::Skipped(msg),
+                        );
                     }
                 }
                 return;
@@ -649,56 +1386,81 @@ impl Parse {
         }
 
         // TODO
+        let mut reasons = Vec::new();
         if let syn::Visibility::Public(_) = item.vis {
         } else {
             warn!("Skip {}::{} - (not `pub`).", crate_name, &item.ident);
+            reasons.push("not `pub`");
         }
         if (item.abi.is_omitted() || item.abi.is_c()) && !item.is_no_mangle() {
             warn!(
                 "Skip {}::{} - (`extern` but not `no_mangle`).",
                 crate_name, &item.ident
             );
+            reasons.push("`extern` but not `no_mangle`");
         }
         if item.abi.is_some() && !(item.abi.is_omitted() || item.abi.is_c()) {
             warn!(
                 "Skip {}::{} - (non `extern \"C\"`).",
                 crate_name, &item.ident
             );
+            reasons.push("non `extern \"C\"`");
+        }
+        if item.abi.is_none() {
+            reasons.push("not `extern \"C\"`");
         }
+        self.note(
+            crate_name,
+            ItemKind::Fn,
+            &item.ident,
+            cfg,
+            ParseOutcome::Skipped(reasons.join(", ")),
+        );
     }
 
     /// Loads associated `const` declarations
     fn load_syn_assoc_consts<'a, I>(
-        &mut self,
         crate_name: &str,
         mod_cfg: Option<&Cfg>,
         impl_ty: &syn::Type,
         items: I,
-    ) where
+        notes: &mut Vec<ParseNote>,
+    ) -> Vec<Constant>
+    where
         I: IntoIterator<Item = &'a syn::ImplItemConst>,
     {
+        let mut constants = Vec::new();
+
         let ty = match Type::load(impl_ty) {
             Ok(ty) => ty,
             Err(e) => {
                 warn!("Skipping associated constants for {:?}: {:?}", impl_ty, e);
-                return;
+                return constants;
             }
         };
         if ty.is_none() {
-            return;
+            return constants;
         }
 
         let impl_path = ty.unwrap().get_root_path().unwrap();
 
         for item in items.into_iter() {
+            let cfg = Cfg::append(mod_cfg, Cfg::load(&item.attrs));
             if let syn::Visibility::Public(_) = item.vis {
             } else {
                 warn!("Skip {}::{} - (not `pub`).", crate_name, &item.ident);
-                return;
+                notes.push(ParseNote::new(
+                    &item.ident,
+                    ItemKind::Const,
+                    crate_name,
+                    cfg,
+                    ParseOutcome::Skipped("not `pub`".to_owned()),
+                ));
+                return constants;
             }
 
             let path = Path::new(item.ident.to_string());
-            match Constant::load(
+            let outcome = match Constant::load(
                 path,
                 mod_cfg,
                 &item.ty,
@@ -708,25 +1470,24 @@ impl Parse {
             ) {
                 Ok(constant) => {
                     info!("Take {}::{}::{}.", crate_name, impl_path, &item.ident);
-                    let mut any = false;
-                    self.structs.for_items_mut(&impl_path, |item| {
-                        any = true;
-                        item.add_associated_constant(constant.clone());
-                    });
-                    // Handle associated constants to other item types that are
-                    // not structs like enums or such as regular constants.
-                    if !any && !self.constants.try_insert(constant) {
-                        error!(
-                            "Conflicting name for constant {}::{}::{}.",
-                            crate_name, impl_path, &item.ident,
-                        );
-                    }
+                    constants.push(constant);
+                    ParseOutcome::Taken
                 }
                 Err(msg) => {
                     warn!("Skip {}::{} - ({})", crate_name, &item.ident, msg);
+                    ParseOutcome::Skipped(msg)
                 }
-            }
+            };
+            notes.push(ParseNote::new(
+                &item.ident,
+                ItemKind::Const,
+                crate_name,
+                cfg,
+                outcome,
+            ));
         }
+
+        constants
     }
 
     /// Loads a `const` declaration
@@ -737,22 +1498,37 @@ impl Parse {
         mod_cfg: Option<&Cfg>,
         item: &syn::ItemConst,
     ) {
+        let cfg = Cfg::append(mod_cfg, Cfg::load(&item.attrs));
         if crate_name != binding_crate_name {
             info!(
                 "Skip {}::{} - (const's outside of the binding crate are not used).",
                 crate_name, &item.ident
             );
+            self.note(
+                crate_name,
+                ItemKind::Const,
+                &item.ident,
+                cfg,
+                ParseOutcome::Skipped(NOT_BINDING_CRATE.to_owned()),
+            );
             return;
         }
 
         if let syn::Visibility::Public(_) = item.vis {
         } else {
             warn!("Skip {}::{} - (not `pub`).", crate_name, &item.ident);
+            self.note(
+                crate_name,
+                ItemKind::Const,
+                &item.ident,
+                cfg,
+                ParseOutcome::Skipped("not `pub`".to_owned()),
+            );
             return;
         }
 
         let path = Path::new(item.ident.to_string());
-        match Constant::load(path, mod_cfg, &item.ty, &item.expr, &item.attrs, None) {
+        let outcome = match Constant::load(path, mod_cfg, &item.ty, &item.expr, &item.attrs, None) {
             Ok(constant) => {
                 info!("Take {}::{}.", crate_name, &item.ident);
 
@@ -760,11 +1536,14 @@ impl Parse {
                 if !self.constants.try_insert(constant) {
                     error!("Conflicting name for constant {}", full_name);
                 }
+                ParseOutcome::Taken
             }
             Err(msg) => {
                 warn!("Skip {}::{} - ({})", crate_name, &item.ident, msg);
+                ParseOutcome::Skipped(msg)
             }
-        }
+        };
+        self.note(crate_name, ItemKind::Const, &item.ident, cfg, outcome);
     }
 
     /// Loads a `static` declaration
@@ -775,45 +1554,68 @@ impl Parse {
         mod_cfg: Option<&Cfg>,
         item: &syn::ItemStatic,
     ) {
+        let cfg = Cfg::append(mod_cfg, Cfg::load(&item.attrs));
         if crate_name != binding_crate_name {
             info!(
                 "Skip {}::{} - (static's outside of the binding crate are not used).",
                 crate_name, &item.ident
             );
+            self.note(
+                crate_name,
+                ItemKind::Static,
+                &item.ident,
+                cfg,
+                ParseOutcome::Skipped(NOT_BINDING_CRATE.to_owned()),
+            );
             return;
         }
 
         if let syn::Visibility::Public(_) = item.vis {
             if item.is_no_mangle() {
-                match Static::load(item, mod_cfg) {
+                let outcome = match Static::load(item, mod_cfg) {
                     Ok(constant) => {
                         info!("Take {}::{}.", crate_name, &item.ident);
 
                         self.globals.try_insert(constant);
+                        ParseOutcome::Taken
                     }
                     Err(msg) => {
                         warn!("Skip {}::{} - ({})", crate_name, &item.ident, msg);
+                        ParseOutcome::Skipped(msg)
                     }
-                }
+                };
+                self.note(crate_name, ItemKind::Static, &item.ident, cfg, outcome);
+                return;
             }
         }
 
         // TODO
+        let mut reasons = Vec::new();
         if let syn::Visibility::Public(_) = item.vis {
         } else {
             warn!("Skip {}::{} - (not `pub`).", crate_name, &item.ident);
+            reasons.push("not `pub`");
         }
         if !item.is_no_mangle() {
             warn!("Skip {}::{} - (not `no_mangle`).", crate_name, &item.ident);
+            reasons.push("not `no_mangle`");
         }
+        self.note(
+            crate_name,
+            ItemKind::Static,
+            &item.ident,
+            cfg,
+            ParseOutcome::Skipped(reasons.join(", ")),
+        );
     }
 
     /// Loads a `struct` declaration
     fn load_syn_struct(&mut self, crate_name: &str, mod_cfg: Option<&Cfg>, item: &syn::ItemStruct) {
-        match Struct::load(item, mod_cfg) {
+        let outcome = match Struct::load(item, mod_cfg) {
             Ok(st) => {
                 info!("Take {}::{}.", crate_name, &item.ident);
                 self.structs.try_insert(st);
+                ParseOutcome::Taken
             }
             Err(msg) => {
                 info!("Take {}::{} - opaque ({}).", crate_name, &item.ident, msg);
@@ -821,17 +1623,21 @@ impl Parse {
                 self.opaque_items.try_insert(
                     OpaqueItem::load(path, &item.generics, &item.attrs, mod_cfg).unwrap(),
                 );
+                ParseOutcome::Opaque(msg)
             }
-        }
+        };
+        let cfg = Cfg::append(mod_cfg, Cfg::load(&item.attrs));
+        self.note(crate_name, ItemKind::Struct, &item.ident, cfg, outcome);
     }
 
     /// Loads a `union` declaration
     fn load_syn_union(&mut self, crate_name: &str, mod_cfg: Option<&Cfg>, item: &syn::ItemUnion) {
-        match Union::load(item, mod_cfg) {
+        let outcome = match Union::load(item, mod_cfg) {
             Ok(st) => {
                 info!("Take {}::{}.", crate_name, &item.ident);
 
                 self.unions.try_insert(st);
+                ParseOutcome::Taken
             }
             Err(msg) => {
                 info!("Take {}::{} - opaque ({}).", crate_name, &item.ident, msg);
@@ -839,8 +1645,11 @@ impl Parse {
                 self.opaque_items.try_insert(
                     OpaqueItem::load(path, &item.generics, &item.attrs, mod_cfg).unwrap(),
                 );
+                ParseOutcome::Opaque(msg)
             }
-        }
+        };
+        let cfg = Cfg::append(mod_cfg, Cfg::load(&item.attrs));
+        self.note(crate_name, ItemKind::Union, &item.ident, cfg, outcome);
     }
 
     /// Loads a `enum` declaration
@@ -850,13 +1659,21 @@ impl Parse {
                 "Skip {}::{} - (has generics or lifetimes or where bounds).",
                 crate_name, &item.ident
             );
+            self.note(
+                crate_name,
+                ItemKind::Enum,
+                &item.ident,
+                Cfg::append(mod_cfg, Cfg::load(&item.attrs)),
+                ParseOutcome::Skipped("has lifetimes".to_owned()),
+            );
             return;
         }
 
-        match Enum::load(item, mod_cfg) {
+        let outcome = match Enum::load(item, mod_cfg) {
             Ok(en) => {
                 info!("Take {}::{}.", crate_name, &item.ident);
                 self.enums.try_insert(en);
+                ParseOutcome::Taken
             }
             Err(msg) => {
                 info!("Take {}::{} - opaque ({}).", crate_name, &item.ident, msg);
@@ -864,17 +1681,21 @@ impl Parse {
                 self.opaque_items.try_insert(
                     OpaqueItem::load(path, &item.generics, &item.attrs, mod_cfg).unwrap(),
                 );
+                ParseOutcome::Opaque(msg)
             }
-        }
+        };
+        let cfg = Cfg::append(mod_cfg, Cfg::load(&item.attrs));
+        self.note(crate_name, ItemKind::Enum, &item.ident, cfg, outcome);
     }
 
     /// Loads a `type` declaration
     fn load_syn_ty(&mut self, crate_name: &str, mod_cfg: Option<&Cfg>, item: &syn::ItemType) {
-        match Typedef::load(item, mod_cfg) {
+        let outcome = match Typedef::load(item, mod_cfg) {
             Ok(st) => {
                 info!("Take {}::{}.", crate_name, &item.ident);
 
                 self.typedefs.try_insert(st);
+                ParseOutcome::Taken
             }
             Err(msg) => {
                 info!("Take {}::{} - opaque ({}).", crate_name, &item.ident, msg);
@@ -882,8 +1703,11 @@ impl Parse {
                 self.opaque_items.try_insert(
                     OpaqueItem::load(path, &item.generics, &item.attrs, mod_cfg).unwrap(),
                 );
+                ParseOutcome::Opaque(msg)
             }
-        }
+        };
+        let cfg = Cfg::append(mod_cfg, Cfg::load(&item.attrs));
+        self.note(crate_name, ItemKind::Type, &item.ident, cfg, outcome);
     }
 
     fn load_builtin_macro(
@@ -915,6 +1739,8 @@ impl Parse {
         // We know that the expansion will only reference `struct_`, so it's
         // fine to just do it here instead of deferring it like we do with the
         // other calls to this function.
-        self.load_syn_assoc_consts_from_impl(crate_name, mod_cfg, &impl_);
+        let constants =
+            Self::load_syn_assoc_consts_from_impl(crate_name, mod_cfg, &impl_, &mut self.notes);
+        self.add_associated_constants(crate_name, constants);
     }
 }
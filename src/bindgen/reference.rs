@@ -0,0 +1,366 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+use bindgen::config::Language;
+use bindgen::markdown::{self, CodeBlock, Links};
+
+/// A reference of the items in a set of bindings, with a page for each item.
+#[derive(Debug, Clone)]
+pub struct ApiReference {
+    pub pages: Vec<ReferencePage>,
+    /// The language of the declarations, for highlighting.
+    language: &'static str,
+    /// The names the items are exported as, by their name in rust, to link
+    /// to their pages from the documentation.
+    links: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReferencePage {
+    /// The name of the item in the bindings, after renaming.
+    pub name: String,
+    /// The definitions of the item, of which there are several when it's
+    /// defined differently under different `#[cfg]`s.
+    pub definitions: Vec<ReferenceDefinition>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReferenceDefinition {
+    /// The kind of the item, like `struct` or `fn`.
+    pub kind: String,
+    /// The declaration of the item, as written in the bindings.
+    pub declaration: String,
+    /// The `#[cfg]` the item is under, if any.
+    pub cfg: Option<String>,
+    /// The preprocessor condition the item is written under, if any.
+    pub condition: Option<String>,
+    /// The note of `#[deprecated]`, which is empty if there's none.
+    pub deprecated: Option<String>,
+    /// The markdown documentation of the item.
+    pub documentation: Vec<String>,
+    /// The fields of a struct or union, the variants of an enum or the
+    /// parameters of a function.
+    pub members: Vec<ReferenceMember>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReferenceMember {
+    pub name: String,
+    /// The type of a field or parameter, or the value of a variant.
+    pub value: Option<String>,
+    pub documentation: Vec<String>,
+}
+
+impl ApiReference {
+    pub fn new(language: &Language, links: HashMap<String, String>) -> ApiReference {
+        ApiReference {
+            pages: Vec::new(),
+            language: match *language {
+                Language::C => "c",
+                Language::Cxx => "cpp",
+            },
+            links,
+        }
+    }
+
+    /// Adds a definition to the page of the item called `name`.
+    pub fn add(&mut self, name: &str, definition: ReferenceDefinition) {
+        match self.pages.iter_mut().find(|x| x.name == name) {
+            Some(page) => page.definitions.push(definition),
+            None => self.pages.push(ReferencePage {
+                name: name.to_owned(),
+                definitions: vec![definition],
+            }),
+        }
+    }
+
+    /// Writes an `index.md` and a markdown page `item.<name>.md` for each item
+    /// to `dir`.
+    pub fn write_markdown<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        write_lines(&dir.join("index.md"), &self.index("md"))?;
+        for page in &self.pages {
+            let path = dir.join(item_page(&page.name, "md"));
+            write_lines(&path, &self.page(page, "md"))?;
+        }
+        Ok(())
+    }
+
+    /// Writes an `index.html` and an HTML page `item.<name>.html` for each item
+    /// to `dir`.
+    pub fn write_html<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        write_html_page(
+            &dir.join("index.html"),
+            "API reference",
+            &self.index("html"),
+        )?;
+        for page in &self.pages {
+            let path = dir.join(item_page(&page.name, "html"));
+            write_html_page(&path, &page.name, &self.page(page, "html"))?;
+        }
+        Ok(())
+    }
+
+    /// The markdown of the index, listing the items by kind with the first
+    /// line of their documentation.
+    fn index(&self, extension: &str) -> Vec<String> {
+        let mut lines = vec!["# API reference".to_owned()];
+
+        let mut write_section = |title: &str, filter: &dyn Fn(&str) -> bool| {
+            let mut pages: Vec<_> = self
+                .pages
+                .iter()
+                .filter(|x| filter(&x.definitions[0].kind))
+                .collect();
+            pages.sort_by(|x, y| x.name.cmp(&y.name));
+            if pages.is_empty() {
+                return;
+            }
+            lines.push(String::new());
+            lines.push(format!("## {}", title));
+            lines.push(String::new());
+            for page in pages {
+                let summary = page
+                    .definitions
+                    .iter()
+                    .flat_map(|x| summary(&x.documentation))
+                    .next();
+                let link = format!("[`{}`]({})", page.name, item_page(&page.name, extension));
+                lines.push(match summary {
+                    Some(summary) => {
+                        format!("* {}: {}", link, self.translate(&[summary], extension)[0])
+                    }
+                    None => format!("* {}", link),
+                });
+            }
+        };
+
+        write_section("Constants", &|kind| kind == "const");
+        write_section("Types", &|kind| !["const", "static", "fn"].contains(&kind));
+        write_section("Globals", &|kind| kind == "static");
+        write_section("Functions", &|kind| kind == "fn");
+        lines
+    }
+
+    /// The markdown of an item's page.
+    fn page(&self, page: &ReferencePage, extension: &str) -> Vec<String> {
+        let mut lines = vec![
+            format!("[API reference](index.{})", extension),
+            String::new(),
+            format!("# `{}`", page.name),
+        ];
+
+        for (index, definition) in page.definitions.iter().enumerate() {
+            if index > 0 {
+                lines.push(String::new());
+                lines.push("---".to_owned());
+            }
+
+            lines.push(String::new());
+            lines.push(format!("*{}*", definition.kind));
+            if let Some(ref note) = definition.deprecated {
+                lines.push(String::new());
+                if note.is_empty() {
+                    lines.push("> **Deprecated.**".to_owned());
+                } else {
+                    lines.push(format!("> **Deprecated:** {}", note));
+                }
+            }
+            match (&definition.condition, &definition.cfg) {
+                (&Some(ref condition), &Some(ref cfg)) => {
+                    lines.push(String::new());
+                    lines.push(format!(
+                        "Only defined `{}`, from `#[cfg({})]`.",
+                        condition, cfg
+                    ));
+                }
+                (&None, &Some(ref cfg)) => {
+                    lines.push(String::new());
+                    lines.push(format!(
+                        "Only parsed under `#[cfg({})]`, which has no `[defines]` entry.",
+                        cfg
+                    ));
+                }
+                _ => {}
+            }
+
+            lines.push(String::new());
+            lines.push(format!("```{}", self.language));
+            lines.extend(definition.declaration.lines().map(|x| x.to_owned()));
+            lines.push("```".to_owned());
+
+            // The arguments of functions are described in their table.
+            let (documentation, arguments) = if definition.kind == "fn" {
+                markdown::split_arguments(&definition.documentation)
+            } else {
+                (definition.documentation.clone(), HashMap::new())
+            };
+            let documentation = self.translate(&documentation, extension);
+            if !documentation.is_empty() {
+                lines.push(String::new());
+                lines.extend(documentation);
+            }
+
+            if definition.members.is_empty() {
+                continue;
+            }
+            let (title, columns) = match &*definition.kind {
+                "enum" => ("Variants", "| Variant | Value | Description |"),
+                "fn" => ("Parameters", "| Parameter | Type | Description |"),
+                _ => ("Fields", "| Field | Type | Description |"),
+            };
+            lines.push(String::new());
+            lines.push(format!("## {}", title));
+            lines.push(String::new());
+            lines.push(columns.to_owned());
+            lines.push("|---|---|---|".to_owned());
+            for member in &definition.members {
+                let documentation = arguments.get(&member.name).unwrap_or(&member.documentation);
+                let description = self
+                    .translate(documentation, extension)
+                    .into_iter()
+                    .filter(|x| !x.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                lines.push(format!(
+                    "| `{}` | {} | {} |",
+                    member.name,
+                    member
+                        .value
+                        .as_ref()
+                        .map_or(String::new(), |x| format!("`{}`", x)),
+                    description.replace('|', "\\|")
+                ));
+            }
+        }
+        lines
+    }
+
+    /// Translates the intra-doc links of documentation to links to the pages
+    /// of the items, and makes its headings part of the page's.
+    fn translate(&self, documentation: &[String], extension: &str) -> Vec<String> {
+        let links = Links::new(documentation, &self.links);
+        let render = |name: &str, text: Option<&str>| match text {
+            Some(text) => format!("[{}]({})", text, item_page(name, extension)),
+            None => format!("[`{}`]({})", name, item_page(name, extension)),
+        };
+
+        let mut lines = Vec::new();
+        let mut code_block: Option<CodeBlock> = None;
+        for line in documentation {
+            if let Some(block) = code_block.take() {
+                if block.closes(line) {
+                    lines.push("```".to_owned());
+                    continue;
+                }
+                lines.extend(block.shown(line));
+                code_block = Some(block);
+                continue;
+            }
+            if let Some(block) = CodeBlock::open(line) {
+                lines.push(format!("```{}", block.language()));
+                code_block = Some(block);
+                continue;
+            }
+            if markdown::is_link_definition(line) {
+                continue;
+            }
+            if line.is_empty() {
+                // Drop the blank lines around removed lines.
+                if lines.last().map_or(false, |x: &String| !x.is_empty()) {
+                    lines.push(String::new());
+                }
+                continue;
+            }
+            if markdown::heading(line).is_some() {
+                lines.push(format!("#{}", links.translate(line, &render)));
+                continue;
+            }
+            lines.push(links.translate(line, &render));
+        }
+        if code_block.is_some() {
+            lines.push("```".to_owned());
+        }
+        while lines.last().map_or(false, |x| x.is_empty()) {
+            lines.pop();
+        }
+        lines
+    }
+}
+
+/// The file name of the page of an item. The prefix keeps an item named e.g.
+/// `index` from replacing the index, as no C name has a dot.
+fn item_page(name: &str, extension: &str) -> String {
+    format!("item.{}.{}", name, extension)
+}
+
+/// The first line of the first paragraph of some documentation, unless it's
+/// something else like a heading or a code block.
+fn summary(documentation: &[String]) -> Option<String> {
+    let first = documentation.first()?;
+    if markdown::heading(first).is_some() || CodeBlock::open(first).is_some() {
+        return None;
+    }
+    Some(first.clone())
+}
+
+fn write_lines(path: &Path, lines: &[String]) -> io::Result<()> {
+    let mut out = io::BufWriter::new(File::create(path)?);
+    for line in lines {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+fn write_html_page(path: &Path, title: &str, lines: &[String]) -> io::Result<()> {
+    let mut out = io::BufWriter::new(File::create(path)?);
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>{}</title>", markdown::escape_html(title))?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    write!(out, "{}", markdown::to_html(lines))?;
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+}
+
+#[test]
+fn item_named_index() {
+    extern crate tempfile;
+    use bindgen::{Builder, Language};
+
+    let bindings = Builder::new()
+        .with_language(Language::C)
+        .with_source_str(
+            "demo",
+            "ffi",
+            r#"
+            /// See [`other`].
+            #[repr(C)]
+            pub struct index { x: i32 }
+
+            #[no_mangle]
+            pub extern "C" fn other(i: index) {}
+            "#,
+        )
+        .generate()
+        .unwrap();
+    let dir = tempfile::Builder::new().tempdir().unwrap();
+    bindings.api_reference().write_markdown(dir.path()).unwrap();
+
+    let read = |name: &str| ::std::fs::read_to_string(dir.path().join(name)).unwrap();
+    assert!(read("index.md").contains("* [`index`](item.index.md): See [`other`](item.other.md)."));
+    assert!(read("item.index.md").contains("[API reference](index.md)"));
+    assert!(read("item.other.md").contains("index i"));
+}
@@ -0,0 +1,100 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The regular expressions that item, module and crate names are matched
+//! against in the config, using the syntax of the `regex` crate, which
+//! matches in linear time.
+
+use std::fmt;
+
+use regex;
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    regex: regex::Regex,
+}
+
+impl Regex {
+    pub fn new(source: &str) -> Result<Regex, String> {
+        regex::Regex::new(source)
+            .map(|regex| Regex { regex })
+            .map_err(|e| format!("invalid regex `{}`: {}", source, e))
+    }
+
+    /// Whether the regex matches anywhere in `text`. Use `^` and `$` to match
+    /// the whole of it.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.regex.is_match(text)
+    }
+
+    /// Replaces the first match of the regex in `text` with `replacement`,
+    /// expanded like in the `regex` crate: `$1` or `${1}` stand for what the
+    /// first group matched, `$name` for a named group, `$0` for the whole
+    /// match and `$$` for a dollar sign. Returns `None` if the regex doesn't
+    /// match.
+    pub fn replace(&self, text: &str, replacement: &str) -> Option<String> {
+        let captures = self.regex.captures(text)?;
+        let whole = captures.get(0).unwrap();
+        let mut result = text[..whole.start()].to_owned();
+        captures.expand(replacement, &mut result);
+        result.push_str(&text[whole.end()..]);
+        Some(result)
+    }
+}
+
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.regex.as_str())
+    }
+}
+
+#[test]
+fn matching() {
+    let regex = Regex::new("^(.*)Ffi$").unwrap();
+    assert!(regex.is_match("FooFfi"));
+    assert!(!regex.is_match("FooFfiBar"));
+    assert!(Regex::new("Ffi").unwrap().is_match("FooFfiBar"));
+    assert!(Regex::new(r"^\w+_\d$").unwrap().is_match("foo_1"));
+    assert!(Regex::new("^(?:Foo|Bar)[A-Z]?$").unwrap().is_match("BarX"));
+    assert!(Regex::new("(").is_err());
+    assert_eq!(regex.to_string(), "^(.*)Ffi$");
+}
+
+#[test]
+fn nested_quantifiers() {
+    // A backtracking engine takes exponential time on these.
+    let regex = Regex::new(r"^(\w+_?)*Ffi$").unwrap();
+    let name = "a_very_long_name_that_doesnt_end_with_the_suffix_at_all_";
+    assert!(!regex.is_match(name));
+    assert!(regex.is_match("a_very_long_nameFfi"));
+}
+
+#[test]
+fn replace() {
+    let regex = Regex::new("^(.*)Ffi(.*)$").unwrap();
+    assert_eq!(regex.replace("FooFfi", "$1"), Some("Foo".to_owned()));
+    assert_eq!(
+        regex.replace("FooFfiBar", "${2}_$1"),
+        Some("Bar_Foo".to_owned())
+    );
+    assert_eq!(regex.replace("FooFfi", "$0$$"), Some("FooFfi$".to_owned()));
+    assert_eq!(regex.replace("Foo", "$1"), None);
+
+    // Only the match is replaced.
+    let regex = Regex::new("Ffi").unwrap();
+    assert_eq!(
+        regex.replace("FooFfiBar", "Sys"),
+        Some("FooSysBar".to_owned())
+    );
+    assert_eq!(regex.replace("FooFfiBar", "$9"), Some("FooBar".to_owned()));
+
+    // Group names are as long as possible, so `$1a` is the group `1a`.
+    let regex = Regex::new("^(?P<name>.*)Ffi$").unwrap();
+    assert_eq!(regex.replace("FooFfi", "$1a"), Some("".to_owned()));
+    assert_eq!(regex.replace("FooFfi", "${1}a"), Some("Fooa".to_owned()));
+    assert_eq!(
+        regex.replace("FooFfi", "${name}_t"),
+        Some("Foo_t".to_owned())
+    );
+}
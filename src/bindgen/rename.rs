@@ -13,6 +13,8 @@ pub enum IdentifierType<'a> {
     EnumVariant(&'a Enum),
     FunctionArg,
     Enum,
+    /// The name of a function, constant or global.
+    Item,
 }
 
 impl<'a> IdentifierType<'a> {
@@ -22,12 +24,13 @@ impl<'a> IdentifierType<'a> {
             IdentifierType::EnumVariant(..) => "",
             IdentifierType::FunctionArg => "a",
             IdentifierType::Enum => "",
+            IdentifierType::Item => "",
         }
     }
 }
 
 /// A rule to apply to an identifier when generating bindings.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub enum RenameRule {
     /// Do not apply any renaming. The default.
     None,
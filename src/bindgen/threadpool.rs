@@ -0,0 +1,92 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+type Job<R> = Box<dyn FnOnce() -> R + Send>;
+
+/// A fixed number of threads running jobs in the background.
+///
+/// Each job is submitted with a key, and results are handed back along with
+/// their key in the order they complete. A job that panics resumes the panic
+/// on the thread receiving its result.
+pub struct ThreadPool<K, R> {
+    jobs: Option<Sender<(K, Job<R>)>>,
+    results: Receiver<(K, thread::Result<R>)>,
+    threads: Vec<JoinHandle<()>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl<K: Send + 'static, R: Send + 'static> ThreadPool<K, R> {
+    pub fn new(num_threads: usize) -> ThreadPool<K, R> {
+        let (jobs, job_receiver) = mpsc::channel::<(K, Job<R>)>();
+        let (result_sender, results) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let threads = (0..num_threads.max(1))
+            .map(|_| {
+                let job_receiver = job_receiver.clone();
+                let result_sender = result_sender.clone();
+                let shutdown = shutdown.clone();
+                thread::spawn(move || loop {
+                    // The lock is only held while waiting for the next job.
+                    let next = job_receiver.lock().unwrap().recv();
+                    let (key, job) = match next {
+                        Ok(next) => next,
+                        Err(_) => break,
+                    };
+                    if shutdown.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let result = panic::catch_unwind(AssertUnwindSafe(job));
+                    if result_sender.send((key, result)).is_err() {
+                        break;
+                    }
+                })
+            })
+            .collect();
+
+        ThreadPool {
+            jobs: Some(jobs),
+            results,
+            threads,
+            shutdown,
+        }
+    }
+
+    pub fn submit<F>(&self, key: K, job: F)
+    where
+        F: FnOnce() -> R + Send + 'static,
+    {
+        self.jobs
+            .as_ref()
+            .unwrap()
+            .send((key, Box::new(job)))
+            .unwrap();
+    }
+
+    /// Waits for the next job to complete. There must be a job pending.
+    pub fn recv(&self) -> (K, R) {
+        match self.results.recv().unwrap() {
+            (key, Ok(result)) => (key, result),
+            (_, Err(payload)) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+impl<K, R> Drop for ThreadPool<K, R> {
+    fn drop(&mut self) {
+        // Any jobs still queued are skipped, since nobody is waiting for them.
+        self.shutdown.store(true, Ordering::Relaxed);
+        self.jobs = None;
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
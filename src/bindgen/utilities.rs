@@ -200,9 +200,11 @@ impl SynAbiHelpers for syn::Abi {
 
 pub trait SynAttributeHelpers {
     fn get_comment_lines(&self) -> Vec<String>;
+    fn get_deprecated_note(&self) -> Option<String>;
     fn has_attr_word(&self, name: &str) -> bool;
     fn has_attr_list(&self, name: &str, args: &[&str]) -> bool;
     fn has_attr_name_value(&self, name: &str, value: &str) -> bool;
+    fn get_attr_list(&self, name: &str) -> Option<Vec<syn::NestedMeta>>;
 }
 
 impl SynAttributeHelpers for [syn::Attribute] {
@@ -251,6 +253,58 @@ impl SynAttributeHelpers for [syn::Attribute] {
         })
     }
 
+    /// The arguments of the first `#[name(...)]` attribute.
+    fn get_attr_list(&self, name: &str) -> Option<Vec<syn::NestedMeta>> {
+        self.iter()
+            .filter_map(|x| x.interpret_meta())
+            .filter_map(|attr| match attr {
+                syn::Meta::List(syn::MetaList { ident, nested, .. }) => {
+                    if ident == name {
+                        Some(nested.into_iter().collect())
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            })
+            .next()
+    }
+
+    /// The note of a `#[deprecated]` attribute, which is empty if there's none.
+    fn get_deprecated_note(&self) -> Option<String> {
+        for attr in self.iter().filter_map(|x| x.interpret_meta()) {
+            match attr {
+                syn::Meta::Word(ref ident) if ident == "deprecated" => {
+                    return Some(String::new());
+                }
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    ref ident,
+                    lit: syn::Lit::Str(ref note),
+                    ..
+                }) if ident == "deprecated" => {
+                    return Some(note.value());
+                }
+                syn::Meta::List(syn::MetaList {
+                    ref ident,
+                    ref nested,
+                    ..
+                }) if ident == "deprecated" => {
+                    let note = nested.iter().filter_map(|x| match *x {
+                        syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                            ref ident,
+                            lit: syn::Lit::Str(ref note),
+                            ..
+                        })) if ident == "note" => Some(note.value()),
+                        _ => None,
+                    });
+                    return Some(note.last().unwrap_or_default());
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
     fn get_comment_lines(&self) -> Vec<String> {
         let mut comment_lines = Vec::new();
 
@@ -262,27 +316,97 @@ impl SynAttributeHelpers for [syn::Attribute] {
                     ..
                 })) = attr.interpret_meta()
                 {
-                    let name = ident.to_string();
-                    let comment = comment.value();
-
-                    if &*name == "doc" {
-                        for raw in comment.lines() {
-                            let line = raw
-                                .trim_start_matches(" ")
-                                .trim_start_matches("//")
-                                .trim_start_matches("///")
-                                .trim_start_matches("/**")
-                                .trim_start_matches("/*")
-                                .trim_start_matches("*/")
-                                .trim_start_matches("*")
-                                .trim_end();
-                            comment_lines.push(line.to_owned());
-                        }
+                    if ident == "doc" {
+                        comment_lines.extend(split_doc_comment(&comment.value()));
                     }
                 }
             }
         }
 
+        // Like rustdoc, remove the indentation all the lines have in common,
+        // which keeps the indentation of lists and code blocks. Only spaces
+        // and tabs count, so that the indentation is never cut in the middle
+        // of a character.
+        let indent = comment_lines
+            .iter()
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| x.len() - x.trim_start_matches(&[' ', '\t'][..]).len())
+            .min()
+            .unwrap_or(0);
+        for line in &mut comment_lines {
+            *line = if line.trim().is_empty() {
+                String::new()
+            } else {
+                line[indent..].to_owned()
+            };
+        }
+
         comment_lines
     }
 }
+
+/// Splits the value of a `doc` attribute into lines, removing the decoration
+/// of block comments like `/** ... */`.
+fn split_doc_comment(comment: &str) -> Vec<String> {
+    // Older versions of rustc kept the comment markers in the value.
+    let comment = if comment.starts_with("///") || comment.starts_with("//!") {
+        &comment[3..]
+    } else if (comment.starts_with("/**") || comment.starts_with("/*!")) && comment.ends_with("*/")
+    {
+        &comment[3..comment.len() - 2]
+    } else {
+        comment
+    };
+
+    let mut lines: Vec<&str> = comment.split('\n').map(|x| x.trim_end()).collect();
+    if lines.len() > 1 {
+        if lines.first().map_or(false, |x| x.trim().is_empty()) {
+            lines.remove(0);
+        }
+        if lines.last().map_or(false, |x| x.trim().is_empty()) {
+            lines.pop();
+        }
+        // Block comments often start every line with a `*`.
+        let starred = lines
+            .iter()
+            .all(|x| x.trim().is_empty() || x.trim_start().starts_with('*'));
+        if starred {
+            for line in &mut lines {
+                let trimmed = line.trim_start();
+                if trimmed.starts_with('*') {
+                    *line = &trimmed[1..];
+                }
+            }
+        }
+    }
+
+    lines.into_iter().map(|x| x.to_owned()).collect()
+}
+
+#[test]
+fn comment_lines() {
+    let item: syn::ItemStruct = syn::parse_str(
+        "/// Foo:\n\
+         ///  - bar\n\
+         ///\n\
+         ///   \u{3000}baz\n\
+         struct Foo;",
+    )
+    .unwrap();
+    assert_eq!(
+        item.attrs.get_comment_lines(),
+        vec![
+            "Foo:".to_owned(),
+            " - bar".to_owned(),
+            String::new(),
+            "  \u{3000}baz".to_owned(),
+        ]
+    );
+
+    // Other whitespace isn't part of the indentation.
+    let item: syn::ItemStruct = syn::parse_str("/// \u{3000}text\n///  text\nstruct Foo;").unwrap();
+    assert_eq!(
+        item.attrs.get_comment_lines(),
+        vec!["\u{3000}text".to_owned(), " text".to_owned()]
+    );
+}
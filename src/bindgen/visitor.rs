@@ -0,0 +1,238 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use bindgen::config::ItemType;
+use bindgen::ir::{AnnotationSet, AnnotationValue, Documentation, Path};
+
+/// A hook to inspect and modify the items of the bindings from code, which is
+/// registered with `Builder::with_item_visitor`.
+///
+/// Items are visited once their annotations are known, and before they're
+/// renamed by the config, so they have their names in rust. Any closure
+/// taking a `&mut VisitedItem` is an item visitor. Visitors must be `Send` to
+/// be added, so that a `Builder` can be moved to another thread.
+pub trait ItemVisitor {
+    fn visit_item(&mut self, item: &mut VisitedItem);
+}
+
+impl<F: FnMut(&mut VisitedItem)> ItemVisitor for F {
+    fn visit_item(&mut self, item: &mut VisitedItem) {
+        self(item)
+    }
+}
+
+/// The item visitors of a `Builder`, which are shared by the sets of bindings
+/// it generates.
+#[derive(Clone, Default)]
+pub(crate) struct ItemVisitors(Vec<Arc<Mutex<dyn ItemVisitor + Send>>>);
+
+impl ItemVisitors {
+    pub fn push<V: ItemVisitor + Send + 'static>(&mut self, visitor: V) {
+        self.0.push(Arc::new(Mutex::new(visitor)));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn visit(&self, item: &mut VisitedItem) {
+        for visitor in &self.0 {
+            visitor.lock().unwrap().visit_item(item);
+        }
+    }
+}
+
+impl fmt::Debug for ItemVisitors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ItemVisitors({})", self.0.len())
+    }
+}
+
+/// An item of the bindings, as seen by an `ItemVisitor`.
+#[allow(unused)]
+pub struct VisitedItem<'a> {
+    item_type: ItemType,
+    path: &'a Path,
+    annotations: &'a mut AnnotationSet,
+    documentation: &'a mut Documentation,
+    pub(crate) new_name: Option<String>,
+    pub(crate) removed: bool,
+}
+
+#[allow(unused)]
+impl<'a> VisitedItem<'a> {
+    pub(crate) fn new(
+        item_type: ItemType,
+        path: &'a Path,
+        annotations: &'a mut AnnotationSet,
+        documentation: &'a mut Documentation,
+    ) -> VisitedItem<'a> {
+        VisitedItem {
+            item_type,
+            path,
+            annotations,
+            documentation,
+            new_name: None,
+            removed: false,
+        }
+    }
+
+    /// The type of the item, like `ItemType::Structs`.
+    pub fn item_type(&self) -> &ItemType {
+        &self.item_type
+    }
+
+    /// The name of the item in rust.
+    pub fn name(&self) -> &str {
+        self.path.name()
+    }
+
+    /// Renames the item, as if by `[export.rename]`. Every definition of an
+    /// item with this name is renamed, including those under other `#[cfg]`s.
+    pub fn rename<S: Into<String>>(&mut self, name: S) {
+        self.new_name = Some(name.into());
+    }
+
+    /// The name the item was given with `rename`, if any.
+    pub fn new_name(&self) -> Option<&str> {
+        self.new_name.as_ref().map(|x| x.as_str())
+    }
+
+    /// Removes the item from the bindings, as if by `[export] exclude`, with
+    /// all its definitions.
+    pub fn remove(&mut self) {
+        self.removed = true;
+    }
+
+    pub fn is_removed(&self) -> bool {
+        self.removed
+    }
+
+    /// The value of the `cbindgen:` annotation called `name`.
+    pub fn annotation(&self, name: &str) -> Option<&AnnotationValue> {
+        self.annotations.get(name)
+    }
+
+    /// Sets an annotation, as if by a `cbindgen:name=value` line in the
+    /// documentation of the item.
+    pub fn set_annotation(&mut self, name: &str, value: AnnotationValue) {
+        self.annotations.set(name, value);
+    }
+
+    /// The lines of the documentation of the item, in markdown.
+    pub fn documentation(&self) -> &[String] {
+        &self.documentation.doc_comment
+    }
+
+    pub fn set_documentation(&mut self, lines: Vec<String>) {
+        self.documentation.doc_comment = lines;
+    }
+
+    /// Adds raw text to write before the item, after the text added before.
+    pub fn insert_before<S: Into<String>>(&mut self, text: S) {
+        self.annotations.text_before.push(text.into());
+    }
+
+    /// Adds raw text to write after the item, after the text added before.
+    pub fn insert_after<S: Into<String>>(&mut self, text: S) {
+        self.annotations.text_after.push(text.into());
+    }
+}
+
+#[cfg(test)]
+fn generate_with<V: ItemVisitor + Send + 'static>(visitor: V) -> String {
+    use bindgen::{Builder, Language};
+
+    let bindings = Builder::new()
+        .with_language(Language::C)
+        .with_source_str(
+            "demo",
+            "ffi",
+            r#"
+            /// A point.
+            #[repr(C)]
+            pub struct Point { x: i32, y: i32 }
+            #[repr(C)]
+            pub struct Hidden { x: i32 }
+            #[no_mangle]
+            pub extern "C" fn move_point(p: Point) {}
+            "#,
+        )
+        .with_item_visitor(visitor)
+        .generate()
+        .unwrap();
+    let mut out = Vec::new();
+    bindings.write(&mut out);
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn builder_is_send() {
+    fn assert_send<T: Send>() {}
+    assert_send::<::bindgen::Builder>();
+}
+
+#[test]
+fn rename_and_remove() {
+    let out = generate_with(|item: &mut VisitedItem| match item.name() {
+        "Point" => item.rename("demo_point"),
+        "Hidden" => item.remove(),
+        _ => {}
+    });
+    assert!(out.contains("} demo_point;"));
+    assert!(out.contains("void move_point(demo_point p);"));
+    assert!(!out.contains("Point"));
+    assert!(!out.contains("Hidden"));
+}
+
+#[test]
+fn annotations_and_documentation() {
+    let out = generate_with(|item: &mut VisitedItem| {
+        if item.name() == "Point" {
+            assert_eq!(item.documentation(), &["A point.".to_owned()]);
+            item.set_documentation(vec!["A point on the plane.".to_owned()]);
+            let names = vec!["left".to_owned(), "top".to_owned()];
+            item.set_annotation("field-names", AnnotationValue::List(names));
+        }
+    });
+    assert!(out.contains("A point on the plane."));
+    assert!(!out.contains("A point."));
+    assert!(out.contains("int32_t left;"));
+    assert!(out.contains("int32_t top;"));
+}
+
+#[test]
+fn text_injection() {
+    let out = generate_with(|item: &mut VisitedItem| {
+        if *item.item_type() == ItemType::Functions {
+            item.insert_before("#if defined(DEMO_MOVE)");
+            item.insert_after("#endif");
+        }
+    });
+    assert!(out.contains("#if defined(DEMO_MOVE)\nvoid move_point(Point p);\n#endif\n"));
+}
+
+#[test]
+fn visitors_run_in_order() {
+    let mut visitors = ItemVisitors::default();
+    visitors.push(|item: &mut VisitedItem| item.rename("first"));
+    visitors.push(|item: &mut VisitedItem| {
+        let name = format!("{}_second", item.new_name().unwrap());
+        item.rename(name);
+    });
+    let path = Path::new("Point");
+    let mut annotations = AnnotationSet::new();
+    let mut documentation = Documentation::none();
+    let mut item = VisitedItem::new(
+        ItemType::Structs,
+        &path,
+        &mut annotations,
+        &mut documentation,
+    );
+    visitors.visit(&mut item);
+    assert_eq!(item.new_name(), Some("first_second"));
+}
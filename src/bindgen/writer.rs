@@ -59,6 +59,9 @@ impl<'a, 'b, F: Write> Write for InnerWriter<'a, 'b, F> {
 pub struct SourceWriter<'a, F: Write> {
     out: F,
     bindings: &'a Bindings,
+    /// The namespace of a rust module the writer is in, within the root
+    /// namespaces.
+    namespace: Vec<String>,
     spaces: Vec<usize>,
     line_started: bool,
     line_length: usize,
@@ -72,6 +75,7 @@ impl<'a, F: Write> SourceWriter<'a, F> {
         SourceWriter {
             out,
             bindings,
+            namespace: Vec::new(),
             spaces: vec![0],
             line_started: false,
             line_length: 0,
@@ -80,8 +84,16 @@ impl<'a, F: Write> SourceWriter<'a, F> {
         }
     }
 
-    pub fn bindings(&self) -> &Bindings {
-        &self.bindings
+    pub fn bindings(&self) -> &'a Bindings {
+        self.bindings
+    }
+
+    pub fn namespace(&self) -> &[String] {
+        &self.namespace
+    }
+
+    pub fn set_namespace(&mut self, namespace: Vec<String>) {
+        self.namespace = namespace;
     }
 
     /// Takes a function that writes source and returns the maximum line length
@@ -93,6 +105,7 @@ impl<'a, F: Write> SourceWriter<'a, F> {
         let mut measurer = SourceWriter {
             out: NullFile,
             bindings: self.bindings,
+            namespace: self.namespace.clone(),
             spaces: self.spaces.clone(),
             line_started: self.line_started,
             line_length: self.line_length,
@@ -10,6 +10,7 @@ extern crate serde;
 extern crate serde_json;
 #[macro_use]
 extern crate quote;
+extern crate regex;
 #[macro_use]
 extern crate syn;
 extern crate toml;
@@ -3,7 +3,9 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use std::env;
-use std::io;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
 
 extern crate clap;
@@ -15,14 +17,16 @@ extern crate serde;
 extern crate serde_json;
 #[macro_use]
 extern crate quote;
+extern crate regex;
 #[macro_use]
 extern crate syn;
 extern crate toml;
 
-use clap::{App, Arg, ArgMatches};
+use clap::{App, Arg, ArgMatches, SubCommand};
 
 mod bindgen;
 mod logging;
+mod watch;
 
 use bindgen::{Bindings, Builder, Cargo, Config, Error, Language, Style};
 
@@ -60,9 +64,20 @@ fn apply_config_overrides<'a>(config: &mut Config, matches: &ArgMatches<'a>) {
     if matches.is_present("d") {
         config.parse.parse_deps = true;
     }
+
+    if let Some(jobs) = matches.value_of("jobs") {
+        match jobs.parse() {
+            Ok(jobs) => config.parse.jobs = Some(jobs),
+            Err(_) => error!("Invalid number of jobs specified."),
+        }
+    }
+
+    if matches.is_present("no-parse-cache") {
+        config.parse.cache.enabled = false;
+    }
 }
 
-fn load_bindings<'a>(input: &Path, matches: &ArgMatches<'a>) -> Result<Bindings, Error> {
+fn load_builder<'a>(input: &Path, matches: &ArgMatches<'a>) -> Result<Builder, Error> {
     // If a file is specified then we load it as a single source
     if !input.is_dir() {
         // Load any config specified or search in the input directory
@@ -73,10 +88,7 @@ fn load_bindings<'a>(input: &Path, matches: &ArgMatches<'a>) -> Result<Bindings,
 
         apply_config_overrides(&mut config, &matches);
 
-        return Builder::new()
-            .with_config(config)
-            .with_src(input)
-            .generate();
+        return Ok(Builder::new().with_config(config).with_src(input));
     }
 
     // We have to load a whole crate, so we use cargo to gather metadata
@@ -105,10 +117,180 @@ fn load_bindings<'a>(input: &Path, matches: &ArgMatches<'a>) -> Result<Bindings,
 
     apply_config_overrides(&mut config, &matches);
 
-    Builder::new()
-        .with_config(config)
-        .with_cargo(lib)
-        .generate()
+    Ok(Builder::new().with_config(config).with_cargo(lib))
+}
+
+fn load_bindings<'a>(input: &Path, matches: &ArgMatches<'a>) -> Result<Bindings, Error> {
+    load_builder(input, matches)?.generate()
+}
+
+/// Generates the bindings to write, with the paths to write them to. These
+/// are those of the `[[output]]`s of the config, unless `--output` is given.
+fn generate_outputs<'a>(
+    builder: Builder,
+    matches: &ArgMatches<'a>,
+) -> Result<Vec<(Option<PathBuf>, Bindings)>, Error> {
+    if builder.has_outputs() && !matches.is_present("out") && !matches.is_present("explain") {
+        let outputs = builder.generate_outputs()?;
+        return Ok(outputs.into_iter().map(|(x, y)| (Some(x), y)).collect());
+    }
+    let path = matches.value_of("out").map(PathBuf::from);
+    Ok(vec![(path, builder.generate()?)])
+}
+
+/// The config files that may affect the bindings, besides the source files.
+fn config_files<'a>(input: &Path, matches: &ArgMatches<'a>) -> Vec<PathBuf> {
+    match matches.value_of("config") {
+        Some(c) => vec![PathBuf::from(c)],
+        None if input.is_dir() => vec![input.join("cbindgen.toml"), input.join("Cargo.toml")],
+        None => Vec::new(),
+    }
+}
+
+/// The config file of a subcommand: the one given with `--config`, or the
+/// `cbindgen.toml` of the crate.
+fn config_file<'a>(input: &Path, matches: &ArgMatches<'a>) -> PathBuf {
+    match matches.value_of("config") {
+        Some(c) => PathBuf::from(c),
+        None => input.join("cbindgen.toml"),
+    }
+}
+
+/// Writes a commented `cbindgen.toml` for the crate in `input`.
+fn init_config<'a>(input: &Path, matches: &ArgMatches<'a>) -> Result<PathBuf, String> {
+    let path = config_file(input, matches);
+    if path.exists() {
+        return Err(format!("{} already exists.", path.display()));
+    }
+
+    let manifest_path = input.join("Cargo.toml");
+    let mut manifest = String::new();
+    File::open(&manifest_path)
+        .and_then(|mut x| x.read_to_string(&mut manifest))
+        .map_err(|e| format!("Couldn't read {}: {}.", manifest_path.display(), e))?;
+    let crate_name = match toml::from_str::<toml::Value>(&manifest) {
+        Ok(ref manifest) => match manifest.get("package").and_then(|x| x.get("name")) {
+            Some(&toml::Value::String(ref name)) => name.clone(),
+            _ => return Err(format!("{} has no package name.", manifest_path.display())),
+        },
+        Err(e) => {
+            return Err(format!(
+                "Couldn't parse {}: {}.",
+                manifest_path.display(),
+                e
+            ))
+        }
+    };
+
+    let mut config = Config::default();
+    apply_config_overrides(&mut config, matches);
+    let template = Config::template(&crate_name, &config.language);
+    File::create(&path)
+        .and_then(|mut x| x.write_all(template.as_bytes()))
+        .map_err(|e| format!("Couldn't write {}: {}.", path.display(), e))?;
+    Ok(path)
+}
+
+/// Checks the config of the crate in `input`, returning its errors.
+fn validate_config<'a>(input: &Path, matches: &ArgMatches<'a>) -> Vec<String> {
+    let path = config_file(input, matches);
+    let mut text = String::new();
+    if let Err(e) = File::open(&path).and_then(|mut x| x.read_to_string(&mut text)) {
+        return vec![format!("Couldn't read {}: {}.", path.display(), e)];
+    }
+    match Config::from_toml(&text) {
+        Ok(_) => Vec::new(),
+        Err(errors) => errors
+            .into_iter()
+            .map(|x| format!("{}: {}", path.display(), x))
+            .collect(),
+    }
+}
+
+/// The config bindings for the crate in `input` would be generated with,
+/// after applying the command line flags.
+fn effective_config<'a>(input: &Path, matches: &ArgMatches<'a>) -> Result<Config, String> {
+    let mut config = match matches.value_of("config") {
+        Some(c) => Config::from_file(c)?,
+        None if input.is_dir() => {
+            let path = input.join("cbindgen.toml");
+            if path.exists() {
+                Config::from_file(path)?
+            } else {
+                Config::default()
+            }
+        }
+        None => Config::default(),
+    };
+    apply_config_overrides(&mut config, matches);
+    Ok(config)
+}
+
+fn write_dependency_graph(bindings: &Bindings, path: &Path) -> io::Result<()> {
+    let graph = bindings.dependency_graph();
+    let out = io::BufWriter::new(File::create(path)?);
+    match path.extension().and_then(|x| x.to_str()) {
+        Some("json") => graph.write_json(out),
+        _ => graph.write_dot(out),
+    }
+}
+
+/// Regenerates the bindings whenever one of the files they were generated
+/// from changes, printing which items changed.
+fn watch<'a>(input: &Path, matches: &ArgMatches<'a>, out: &str) -> ! {
+    let mut watcher = watch::Watcher::new();
+    let mut declarations = None;
+    let mut source_files = Vec::new();
+
+    loop {
+        // Keep watching if generating panics, the message has been printed.
+        match panic::catch_unwind(AssertUnwindSafe(|| load_bindings(input, matches))) {
+            Ok(Ok(bindings)) => {
+                bindings.write_to_file(out);
+
+                let new_declarations = bindings.declarations();
+                match declarations {
+                    Some(ref old_declarations) => {
+                        let changes = watch::summarize_changes(old_declarations, &new_declarations);
+                        if changes.is_empty() {
+                            println!("No items changed in {}.", out);
+                        } else {
+                            println!("{} items changed in {}:", changes.len(), out);
+                            for line in changes {
+                                println!("{}", line);
+                            }
+                        }
+                    }
+                    None => println!("Wrote {} items to {}.", new_declarations.len(), out),
+                }
+                declarations = Some(new_declarations);
+                source_files = bindings.source_files().to_vec();
+                source_files.extend(bindings.missing_files().iter().cloned());
+            }
+            Ok(Err(msg)) => {
+                error!("{}", msg);
+                error!("Couldn't generate bindings for {}.", input.display());
+            }
+            Err(_) => error!("Couldn't generate bindings for {}.", input.display()),
+        }
+
+        // Without a successful run there's no telling which files are used.
+        if source_files.is_empty() {
+            if input.is_dir() {
+                bindgen::find_source_files(input, &mut source_files);
+            } else {
+                source_files.push(input.to_owned());
+            }
+        }
+
+        let mut files = config_files(input, matches);
+        files.extend(source_files.iter().cloned());
+        watcher.set_files(files);
+
+        for path in watcher.wait() {
+            info!("{} changed.", path.display());
+        }
+    }
 }
 
 fn main() {
@@ -126,8 +308,44 @@ fn main() {
                 .long("verify")
                 .help("Generate bindings and compare it to the existing bindings file and error if they are different"),
         )
+        .arg(
+            Arg::with_name("watch")
+                .long("watch")
+                .conflicts_with("verify")
+                .help("Regenerate the bindings whenever a source file or config file they depend on changes"),
+        )
+        .arg(
+            Arg::with_name("explain")
+                .long("explain")
+                .value_name("ITEM")
+                .multiple(true)
+                .number_of_values(1)
+                .conflicts_with_all(&["verify", "watch"])
+                .help("Instead of writing the bindings, explain where the given item was parsed, which settings affected it, and why it was or wasn't included"),
+        )
+        .arg(
+            Arg::with_name("dependency-graph")
+                .long("dependency-graph")
+                .value_name("PATH")
+                .help("Also write the graph of the items in the bindings and the items they refer to, as JSON if the path ends with `.json` and as Graphviz DOT otherwise"),
+        )
+        .arg(
+            Arg::with_name("reference")
+                .long("reference")
+                .value_name("DIR")
+                .help("Also write an API reference of the bindings to the given directory, with a page for each item"),
+        )
+        .arg(
+            Arg::with_name("reference-format")
+                .long("reference-format")
+                .value_name("FORMAT")
+                .requires("reference")
+                .possible_values(&["markdown", "html"])
+                .help("The format of the API reference, markdown by default"),
+        )
         .arg(
             Arg::with_name("config")
+                .global(true)
                 .short("c")
                 .long("config")
                 .value_name("PATH")
@@ -135,6 +353,7 @@ fn main() {
         )
         .arg(
             Arg::with_name("lang")
+                .global(true)
                 .short("l")
                 .long("lang")
                 .value_name("LANGUAGE")
@@ -143,6 +362,7 @@ fn main() {
         )
         .arg(
             Arg::with_name("style")
+                .global(true)
                 .short("s")
                 .long("style")
                 .value_name("STYLE")
@@ -151,10 +371,26 @@ fn main() {
         )
         .arg(
             Arg::with_name("d")
+                .global(true)
                 .short("d")
                 .long("parse-dependencies")
                 .help("Whether to parse dependencies when generating bindings"),
         )
+        .arg(
+            Arg::with_name("no-parse-cache")
+                .global(true)
+                .long("no-parse-cache")
+                .help("Parse every crate again rather than using the parse cache, even if it's enabled in the config"),
+        )
+        .arg(
+            Arg::with_name("jobs")
+                .global(true)
+                .short("j")
+                .long("jobs")
+                .value_name("N")
+                .help("The number of threads to parse source files with")
+                .required(false),
+        )
         .arg(
             Arg::with_name("clean")
                 .long("clean")
@@ -201,15 +437,23 @@ fn main() {
                     projects that use workspaces.")
                 .required(false),
         )
+        .subcommand(
+            SubCommand::with_name("init")
+                .about("Write a commented `cbindgen.toml` for a crate, with the settings at their defaults")
+                .arg(Arg::with_name("INPUT").help("The crate directory, the current one by default").index(1)),
+        )
+        .subcommand(
+            SubCommand::with_name("validate")
+                .about("Check a `cbindgen.toml` for misspelled keys and invalid values")
+                .arg(Arg::with_name("INPUT").help("The crate directory, the current one by default").index(1)),
+        )
+        .subcommand(
+            SubCommand::with_name("dump")
+                .about("Print the effective config, from the `cbindgen.toml`, the command line flags and the defaults")
+                .arg(Arg::with_name("INPUT").help("The crate directory, the current one by default").index(1)),
+        )
         .get_matches();
 
-    if !matches.is_present("out") && matches.is_present("verify") {
-        error!(
-            "Cannot verify bindings against `stdout`, please specify a file to compare against."
-        );
-        std::process::exit(2);
-    }
-
     // Initialize logging
     match matches.occurrences_of("v") {
         0 => logging::WarnLogger::init().unwrap(),
@@ -217,32 +461,122 @@ fn main() {
         _ => logging::TraceLogger::init().unwrap(),
     }
 
+    if let (name, Some(matches)) = matches.subcommand() {
+        let input = match matches.value_of("INPUT") {
+            Some(input) => PathBuf::from(input),
+            None => env::current_dir().unwrap(),
+        };
+        match name {
+            "init" => match init_config(&input, matches) {
+                Ok(path) => println!("Wrote {}.", path.display()),
+                Err(msg) => {
+                    error!("{}", msg);
+                    std::process::exit(1);
+                }
+            },
+            "validate" => {
+                let errors = validate_config(&input, matches);
+                if !errors.is_empty() {
+                    for msg in errors {
+                        error!("{}", msg);
+                    }
+                    std::process::exit(1);
+                }
+                println!("{} is valid.", config_file(&input, matches).display());
+            }
+            "dump" => match effective_config(&input, matches) {
+                Ok(config) => print!("{}", config.to_toml()),
+                Err(msg) => {
+                    error!("{}", msg);
+                    std::process::exit(1);
+                }
+            },
+            _ => unreachable!(),
+        }
+        return;
+    }
+
     // Find the input directory
     let input = match matches.value_of("INPUT") {
         Some(input) => PathBuf::from(input),
         None => env::current_dir().unwrap(),
     };
 
-    let bindings = match load_bindings(&input, &matches) {
-        Ok(bindings) => bindings,
+    if matches.is_present("watch") {
+        match matches.value_of("out") {
+            Some(file) => watch(&input, &matches, file),
+            None => {
+                error!(
+                    "Cannot watch bindings written to `stdout`, please specify a file to write to."
+                );
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let builder = match load_builder(&input, &matches) {
+        Ok(builder) => builder,
+        Err(msg) => {
+            error!("{}", msg);
+            error!("Couldn't generate bindings for {}.", input.display());
+            std::process::exit(1);
+        }
+    };
+
+    // Check this before parsing the crate, which can take a while.
+    if !matches.is_present("out") && matches.is_present("verify") && !builder.has_outputs() {
+        error!(
+            "Cannot verify bindings against `stdout`, please specify a file to compare against."
+        );
+        std::process::exit(2);
+    }
+
+    let outputs = match generate_outputs(builder, &matches) {
+        Ok(outputs) => outputs,
         Err(msg) => {
             error!("{}", msg);
             error!("Couldn't generate bindings for {}.", input.display());
             std::process::exit(1);
         }
     };
+    // The graph, reference and explanations are of the first set of bindings.
+    let bindings = &outputs[0].1;
 
-    // Write the bindings file
-    match matches.value_of("out") {
-        Some(file) => {
-            let changed = bindings.write_to_file(file);
+    if let Some(path) = matches.value_of("dependency-graph") {
+        if let Err(e) = write_dependency_graph(bindings, Path::new(path)) {
+            error!("Couldn't write the dependency graph to {}: {}", path, e);
+            std::process::exit(1);
+        }
+    }
 
-            if matches.is_present("verify") && changed {
-                std::process::exit(2);
-            }
+    if let Some(dir) = matches.value_of("reference") {
+        let reference = bindings.api_reference();
+        let result = match matches.value_of("reference-format") {
+            Some("html") => reference.write_html(dir),
+            _ => reference.write_markdown(dir),
+        };
+        if let Err(e) = result {
+            error!("Couldn't write the API reference to {}: {}", dir, e);
+            std::process::exit(1);
         }
-        _ => {
-            bindings.write(io::stdout());
+    }
+
+    if let Some(names) = matches.values_of("explain") {
+        for name in names {
+            print!("{}", bindings.explain(name));
         }
+        return;
+    }
+
+    // Write the bindings files
+    let mut changed = false;
+    for &(ref path, ref bindings) in &outputs {
+        match *path {
+            Some(ref file) => changed |= bindings.write_to_file(file),
+            None => bindings.write(io::stdout()),
+        }
+    }
+    if matches.is_present("verify") && changed {
+        std::process::exit(2);
     }
 }
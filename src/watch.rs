@@ -0,0 +1,174 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How long files have to stay unchanged before regenerating, so that an
+/// editor saving several files at once only causes a single run.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileState {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileState {
+    fn load(path: &Path) -> Option<FileState> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(FileState {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+/// Watches a set of files for changes by polling them, which works on any
+/// filesystem.
+pub struct Watcher {
+    files: BTreeMap<PathBuf, Option<FileState>>,
+}
+
+impl Watcher {
+    pub fn new() -> Watcher {
+        Watcher {
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Replaces the files to watch. Files that were already watched keep
+    /// their last known state, so changes made in the meantime aren't missed.
+    pub fn set_files<I: IntoIterator<Item = PathBuf>>(&mut self, files: I) {
+        let mut old_files = mem::replace(&mut self.files, BTreeMap::new());
+        for path in files {
+            let state = match old_files.remove(&path) {
+                Some(state) => state,
+                None => FileState::load(&path),
+            };
+            self.files.insert(path, state);
+        }
+    }
+
+    /// Checks every file, returning the ones that changed since last time.
+    fn poll(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, state) in &mut self.files {
+            let new_state = FileState::load(path);
+            if new_state != *state {
+                *state = new_state;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+
+    /// Blocks until some files change and then stay unchanged for a while,
+    /// returning the files that changed.
+    pub fn wait(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        loop {
+            thread::sleep(if changed.is_empty() {
+                POLL_INTERVAL
+            } else {
+                DEBOUNCE
+            });
+
+            let newly_changed = self.poll();
+            if newly_changed.is_empty() && !changed.is_empty() {
+                return changed;
+            }
+            for path in newly_changed {
+                if !changed.contains(&path) {
+                    changed.push(path);
+                }
+            }
+        }
+    }
+}
+
+/// Describes which declarations were added, changed or removed, one per line.
+pub fn summarize_changes(
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (name, declaration) in new {
+        match old.get(name) {
+            None => lines.push(format!("  + {}", name)),
+            Some(old_declaration) if old_declaration != declaration => {
+                lines.push(format!("  ~ {}", name))
+            }
+            Some(_) => {}
+        }
+    }
+    for name in old.keys() {
+        if !new.contains_key(name) {
+            lines.push(format!("  - {}", name));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+fn declarations(items: &[(&str, &str)]) -> BTreeMap<String, String> {
+    items
+        .iter()
+        .map(|&(name, declaration)| (name.to_owned(), declaration.to_owned()))
+        .collect()
+}
+
+#[test]
+fn summarize() {
+    let old = declarations(&[("A", "struct A;"), ("B", "struct B;"), ("C", "int c;")]);
+    let new = declarations(&[("A", "struct A;"), ("C", "long c;"), ("D", "struct D;")]);
+    assert_eq!(summarize_changes(&old, &new), ["  ~ C", "  + D", "  - B"]);
+    assert!(summarize_changes(&old, &old).is_empty());
+}
+
+#[test]
+fn watch_files() {
+    extern crate tempfile;
+
+    let dir = tempfile::Builder::new().tempdir().unwrap();
+    let a = dir.path().join("a.rs");
+    let b = dir.path().join("b.rs");
+    fs::write(&a, "struct A;").unwrap();
+
+    let mut watcher = Watcher::new();
+    watcher.set_files(vec![a.clone(), b.clone()]);
+    assert!(watcher.poll().is_empty());
+
+    // A file that's created is a change too, like the file of a missing mod.
+    fs::write(&a, "struct A { x: i32 }").unwrap();
+    fs::write(&b, "struct B;").unwrap();
+    assert_eq!(watcher.poll(), [a.clone(), b.clone()]);
+    assert!(watcher.poll().is_empty());
+
+    // Changes made while the files aren't watched are still noticed.
+    watcher.set_files(vec![a.clone()]);
+    fs::write(&a, "struct A;").unwrap();
+    watcher.set_files(vec![a.clone(), b.clone()]);
+    assert_eq!(watcher.poll(), vec![a.clone()]);
+
+    // Files that keep changing are all returned once they settle.
+    let writer = {
+        let (a, b) = (a.clone(), b.clone());
+        thread::spawn(move || {
+            fs::write(&a, "struct A { x: i32 }").unwrap();
+            thread::sleep(POLL_INTERVAL + DEBOUNCE / 2);
+            fs::write(&b, "struct B { y: i32 }").unwrap();
+        })
+    };
+    let mut changed = watcher.wait();
+    writer.join().unwrap();
+    changed.sort();
+    assert_eq!(changed, [a, b]);
+}
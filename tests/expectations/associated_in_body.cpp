@@ -3,6 +3,7 @@
 #include <cstdlib>
 
 /// Constants shared by multiple CSS Box Alignment properties
+///
 /// These constants match Gecko's `NS_STYLE_ALIGN_*` constants.
 struct StyleAlignFlags {
   uint8_t bits;
@@ -0,0 +1,26 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct Mismatched Mismatched;
+
+typedef struct {
+  /**
+   * Whether it's enabled.
+   */
+  uint32_t enabled : 1;
+  uint32_t level : 3;
+  uint32_t offset : 12;
+  uint32_t id : 16;
+} Flags;
+
+/**
+ * Not bitfields, as each field has storage of its own.
+ */
+typedef struct {
+  uint8_t low;
+  uint8_t high;
+} Unpacked;
+
+void root(Flags a, const Mismatched *b, Unpacked c);
@@ -0,0 +1,25 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+struct Mismatched;
+
+struct Flags {
+  /// Whether it's enabled.
+  uint32_t enabled : 1;
+  uint32_t level : 3;
+  uint32_t offset : 12;
+  uint32_t id : 16;
+};
+
+/// Not bitfields, as each field has storage of its own.
+struct Unpacked {
+  uint8_t low;
+  uint8_t high;
+};
+
+extern "C" {
+
+void root(Flags a, const Mismatched *b, Unpacked c);
+
+} // extern "C"
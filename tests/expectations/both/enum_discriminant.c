@@ -0,0 +1,43 @@
+#define EXTERNAL_BASE 4
+
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define BASE 10
+
+#define SHIFT 2 + 1
+
+enum Evaluated {
+  Shifted = 8,
+  Added = 11,
+  Constant = 10,
+  Byte = 120,
+  Negated = -20,
+  Inverted = -16,
+  Truncated = 44,
+  Qualified = 9,
+};
+typedef int8_t Evaluated;
+
+/**
+ * Discriminants that refer to a constant defined elsewhere.
+ */
+typedef enum External {
+  First = EXTERNAL_BASE + 1,
+  Second = ((uint32_t)EXTERNAL_BASE) << 2,
+} External;
+
+/**
+ * Evaluated in the repr type, so inverting doesn't make them negative.
+ */
+enum Unsigned {
+  Low = 240,
+  High = 254,
+  Masked = 15,
+};
+typedef uint8_t Unsigned;
+
+void root(Evaluated e, Unsigned u, External x);
@@ -0,0 +1,28 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct Hiddenish {
+  int32_t x;
+} Hiddenish;
+
+typedef struct Point {
+  int32_t x;
+  int32_t y;
+} Point;
+
+typedef struct ExtraColor {
+  uint8_t r;
+  uint8_t g;
+  uint8_t b;
+} ExtraColor;
+
+typedef struct ExtraSize {
+  uint32_t w;
+  uint32_t h;
+} ExtraSize;
+
+void hiddenish(Hiddenish h);
+
+Point point_new(int32_t x, int32_t y);
@@ -0,0 +1,135 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A Rust string slice, made of a pointer to its first byte and the number of
+ * bytes. The string is UTF-8 and is not nul-terminated.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+typedef struct Str {
+  const char *ptr;
+  uintptr_t len;
+} Str;
+
+typedef struct Point {
+  float x;
+  float y;
+} Point;
+
+/**
+ * A Rust slice, made of a pointer to its first element and the number of
+ * elements.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+typedef struct SlicePoint {
+  const Point *ptr;
+  uintptr_t len;
+} SlicePoint;
+
+typedef struct Path {
+  Str name;
+  SlicePoint points;
+} Path;
+
+/**
+ * A Rust slice, made of a pointer to its first element and the number of
+ * elements.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+typedef struct SliceU8 {
+  const uint8_t *ptr;
+  uintptr_t len;
+} SliceU8;
+
+/**
+ * A Rust slice, made of a pointer to its first element and the number of
+ * elements.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+typedef struct SliceMutU8 {
+  uint8_t *ptr;
+  uintptr_t len;
+} SliceMutU8;
+
+/**
+ * A Rust slice, made of a pointer to its first element and the number of
+ * elements.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+typedef struct SliceMutU16 {
+  uint16_t *ptr;
+  uintptr_t len;
+} SliceMutU16;
+
+typedef struct Buffer_u16 {
+  SliceMutU16 data;
+} Buffer_u16;
+
+/**
+ * A Rust slice, made of a pointer to its first element and the number of
+ * elements.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+typedef struct SliceSliceU8 {
+  const SliceU8 *ptr;
+  uintptr_t len;
+} SliceSliceU8;
+
+/**
+ * A Rust string slice, made of a pointer to its first byte and the number of
+ * bytes. The string is UTF-8 and is not nul-terminated.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+typedef struct StrMut {
+  char *ptr;
+  uintptr_t len;
+} StrMut;
+
+/**
+ * A Rust `dyn Visitor` trait object, made of a pointer to its data and a
+ * pointer to its vtable.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+typedef struct DynMutVisitor {
+  void *data;
+  const void *vtable;
+} DynMutVisitor;
+
+/**
+ * A Rust `dyn Visitor` trait object, made of a pointer to its data and a
+ * pointer to its vtable.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+typedef struct DynVisitor {
+  const void *data;
+  const void *vtable;
+} DynVisitor;
+
+void root(Path path,
+          SliceU8 bytes,
+          SliceMutU8 bytes_mut,
+          Buffer_u16 buffer,
+          SliceSliceU8 nested,
+          StrMut name,
+          DynMutVisitor visitor,
+          DynVisitor shared_visitor);
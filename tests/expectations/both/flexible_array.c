@@ -0,0 +1,58 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A packet followed by its data.
+ */
+typedef struct Packet {
+  uint32_t len;
+  uint8_t data[];
+} Packet;
+
+typedef struct Point {
+  float x;
+  float y;
+} Point;
+
+typedef struct Points {
+  uintptr_t count;
+  Point points[];
+} Points;
+
+/**
+ * A marker that isn't a flexible array member, since it's the only field.
+ */
+typedef struct Marker {
+  uint8_t _private[0];
+} Marker;
+
+typedef struct Misplaced {
+  uint8_t data[0];
+  uint32_t len;
+} Misplaced;
+
+/**
+ * Embedded by value, so its array can't be a flexible array member.
+ */
+typedef struct Header {
+  uint32_t len;
+  uint32_t tail[0];
+} Header;
+
+typedef struct Message {
+  Header header;
+  uint32_t body;
+} Message;
+
+typedef struct Frame {
+  uint16_t len;
+  uint8_t data[0];
+} Frame;
+
+typedef struct Frames {
+  Frame frames[2];
+} Frames;
+
+void root(const Packet *a, Points *b, const Marker *c, Misplaced d, Message e, Frames f);
@@ -0,0 +1,41 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define FOUR 1 << 2
+
+#define ONE 1
+
+#define TWO 2
+
+/**
+ * A point.
+ */
+typedef struct Point {
+  float x;
+  float y;
+} Point;
+
+typedef struct Rect {
+  Point origin;
+  Point size;
+} Rect;
+
+typedef struct Inner {
+  Rect rect;
+} Inner;
+
+typedef struct Later {
+  Inner inner;
+} Later;
+
+typedef struct Wrapped {
+  int32_t value;
+} Wrapped;
+
+Point origin(void);
+
+void root(Rect r, Later l);
+
+Wrapped wrapped(void);
@@ -0,0 +1,28 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#if defined(MOD_TREE_EXTRA)
+typedef struct Extra {
+  uint32_t flags;
+} Extra;
+#endif
+
+typedef struct Point {
+  int32_t x;
+  int32_t y;
+} Point;
+#define Point_ORIGIN_X 0
+#define Point_ORIGIN_Y 0
+
+typedef struct Delta {
+  int32_t dx;
+  int32_t dy;
+} Delta;
+
+#if defined(MOD_TREE_EXTRA)
+void extra_point(Extra extra);
+#endif
+
+void move_point(Point *point, Delta delta);
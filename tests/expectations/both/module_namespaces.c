@@ -0,0 +1,33 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define MAX_CHANNELS 8
+
+typedef struct Samples {
+  const float *data;
+  uintptr_t len;
+} Samples;
+
+typedef struct Stream {
+  Samples samples;
+  uint8_t channels;
+} Stream;
+
+typedef struct Track {
+  uint32_t id;
+} Track;
+
+typedef struct Picture {
+  const uint8_t *pixels;
+  const Samples *sound;
+  Track track;
+} Picture;
+
+typedef struct Packet {
+  Stream stream;
+  Picture picture;
+} Packet;
+
+Stream decode(const Packet *packet, Picture *out);
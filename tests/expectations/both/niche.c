@@ -0,0 +1,31 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct Opaque Opaque;
+
+typedef struct Option_Cell_NonZeroU32 Option_Cell_NonZeroU32;
+
+typedef struct Foo {
+  uint32_t a;
+  uint64_t b;
+  int64_t c;
+  Opaque *d;
+  Opaque *e;
+  const Opaque *f;
+  int32_t (*g)(int32_t);
+  uint8_t h;
+  uint16_t i;
+  uint32_t j;
+  Opaque *k;
+  int8_t l;
+  const float *m;
+  Opaque *n;
+} Foo;
+
+typedef struct Bar {
+  const Option_Cell_NonZeroU32 *a;
+} Bar;
+
+Opaque *root(Foo foo, Bar *bar, uint32_t count);
@@ -0,0 +1,14 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct ExtType {
+  uint32_t data;
+} ExtType;
+
+typedef struct Other {
+  uint8_t value;
+} Other;
+
+void consume_ext(ExtType _ext, Other _other);
@@ -0,0 +1,35 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define MYLIB_DEFAULT_SIZE 8
+
+#define MYLIB_MAX_POINTS 64
+
+enum MylibColor {
+  Red,
+  Green,
+  Blue,
+};
+typedef uint8_t MylibColor;
+
+typedef struct MylibPoint {
+  int32_t x;
+  int32_t y;
+} MylibPoint;
+
+typedef struct mylib_handle_t {
+  uint64_t id;
+} mylib_handle_t;
+
+#define MYLIB_ORIGIN (MylibPoint){ .x = 0, .y = 0 }
+
+extern uint32_t point_count;
+#define mylib_pointCount point_count
+
+MylibPoint make_point(int32_t x, int32_t y, MylibColor color);
+#define mylib_make_point make_point
+
+mylib_handle_t *open_handle(uint64_t id);
+#define mylib_open_handle open_handle
@@ -0,0 +1,20 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <time.h>
+
+typedef void (*Callback)(void *user_data);
+
+typedef struct Foo {
+  int32_t a;
+} Foo;
+
+typedef struct Event {
+  struct timespec time;
+  Callback callback;
+  Foo* foo;
+  uint8_t* bytes;
+} Event;
+
+void root(const Event *event, struct timespec *time);
@@ -0,0 +1,35 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+inline constexpr int32_t FOO = 10;
+
+enum : uint32_t { LARGE = 70000 };
+
+#define LIMIT ((int16_t)7)
+
+inline constexpr uint64_t MAX = 4294967296;
+
+static const double RATIO = 0.5;
+
+enum : uint8_t { SMALL = 3 };
+
+inline constexpr float ZOM = 3.14;
+
+struct Point {
+  int32_t x;
+  int32_t y;
+  static const Point ORIGIN;
+  static const int32_t DIMENSIONS;
+  static constexpr int32_t MAX_X = 100;
+  enum : uint8_t { AXES = 2 };
+};
+inline const Point Point::ORIGIN = (Point){ .x = 0, .y = 0 };
+inline const int32_t Point::DIMENSIONS = 2;
+#define Point_MAX_Y ((int32_t)50)
+
+extern "C" {
+
+void root(Point x, int32_t y[FOO]);
+
+} // extern "C"
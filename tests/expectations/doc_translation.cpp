@@ -0,0 +1,52 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+/// Shapes that can be drawn.
+///
+/// See \ref LibPoint and [the docs](https://example.com/shapes.html).
+enum class LibShape {
+  /// Draws a single \ref LibPoint.
+  Dot,
+  Line,
+};
+
+/// A point, made by \ref lib_make_point.
+struct LibPoint {
+  /// The horizontal coordinate, see `Point::y` for the other one.
+  int32_t x;
+  int32_t y;
+};
+
+/// A handle to a \ref LibPoint.
+///
+/// * It's never null.
+/// * It's owned by \ref LibPoint "the library".
+struct LibHandle {
+  LibPoint *point;
+};
+
+extern "C" {
+
+/// Draws a \ref LibShape at every point of a \ref LibHandle.
+void draw(LibShape shape, LibHandle handle);
+#define lib_draw draw
+
+/// Creates a \ref LibPoint, see \ref LibHandle for owning one.
+///
+/// @param x The horizontal coordinate.
+/// Negative values are to the left.
+/// @param y The vertical coordinate.
+///
+/// @return A new \ref LibPoint, never a `Missing`.
+///
+/// @warning Must be called on the main thread.
+///
+/// @par Examples
+/// \code
+/// let point = make_point(1, 2);
+/// \endcode
+LibPoint make_point(int32_t x, int32_t y);
+#define lib_make_point make_point
+
+} // extern "C"
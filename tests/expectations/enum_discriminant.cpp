@@ -0,0 +1,40 @@
+#define EXTERNAL_BASE 4
+
+
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+static const int8_t BASE = 10;
+
+static const uint32_t SHIFT = 2 + 1;
+
+enum class Evaluated : int8_t {
+  Shifted = 8,
+  Added = 11,
+  Constant = 10,
+  Byte = 120,
+  Negated = -20,
+  Inverted = -16,
+  Truncated = 44,
+  Qualified = 9,
+};
+
+/// Discriminants that refer to a constant defined elsewhere.
+enum class External {
+  First = EXTERNAL_BASE + 1,
+  Second = ((uint32_t)EXTERNAL_BASE) << 2,
+};
+
+/// Evaluated in the repr type, so inverting doesn't make them negative.
+enum class Unsigned : uint8_t {
+  Low = 240,
+  High = 254,
+  Masked = 15,
+};
+
+extern "C" {
+
+void root(Evaluated e, Unsigned u, External x);
+
+} // extern "C"
@@ -0,0 +1,31 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+struct Hiddenish {
+  int32_t x;
+};
+
+struct Point {
+  int32_t x;
+  int32_t y;
+};
+
+struct ExtraColor {
+  uint8_t r;
+  uint8_t g;
+  uint8_t b;
+};
+
+struct ExtraSize {
+  uint32_t w;
+  uint32_t h;
+};
+
+extern "C" {
+
+void hiddenish(Hiddenish h);
+
+Point point_new(int32_t x, int32_t y);
+
+} // extern "C"
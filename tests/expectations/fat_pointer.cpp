@@ -0,0 +1,122 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+/// A Rust string slice, made of a pointer to its first byte and the number of
+/// bytes. The string is UTF-8 and is not nul-terminated.
+///
+/// Rust doesn't guarantee the layout of fat pointers, so this struct is not
+/// ABI-stable and may not match what a Rust compiler actually passes.
+struct Str {
+  const char *ptr;
+  uintptr_t len;
+};
+
+struct Point {
+  float x;
+  float y;
+};
+
+/// A Rust slice, made of a pointer to its first element and the number of
+/// elements.
+///
+/// Rust doesn't guarantee the layout of fat pointers, so this struct is not
+/// ABI-stable and may not match what a Rust compiler actually passes.
+struct SlicePoint {
+  const Point *ptr;
+  uintptr_t len;
+};
+
+struct Path {
+  Str name;
+  SlicePoint points;
+};
+
+/// A Rust slice, made of a pointer to its first element and the number of
+/// elements.
+///
+/// Rust doesn't guarantee the layout of fat pointers, so this struct is not
+/// ABI-stable and may not match what a Rust compiler actually passes.
+struct SliceU8 {
+  const uint8_t *ptr;
+  uintptr_t len;
+};
+
+/// A Rust slice, made of a pointer to its first element and the number of
+/// elements.
+///
+/// Rust doesn't guarantee the layout of fat pointers, so this struct is not
+/// ABI-stable and may not match what a Rust compiler actually passes.
+struct SliceMutU8 {
+  uint8_t *ptr;
+  uintptr_t len;
+};
+
+/// A Rust slice, made of a pointer to its first element and the number of
+/// elements.
+///
+/// Rust doesn't guarantee the layout of fat pointers, so this struct is not
+/// ABI-stable and may not match what a Rust compiler actually passes.
+template<typename T>
+struct SliceMutT {
+  T *ptr;
+  uintptr_t len;
+};
+
+template<typename T>
+struct Buffer {
+  SliceMutT<T> data;
+};
+
+/// A Rust slice, made of a pointer to its first element and the number of
+/// elements.
+///
+/// Rust doesn't guarantee the layout of fat pointers, so this struct is not
+/// ABI-stable and may not match what a Rust compiler actually passes.
+struct SliceSliceU8 {
+  const SliceU8 *ptr;
+  uintptr_t len;
+};
+
+/// A Rust string slice, made of a pointer to its first byte and the number of
+/// bytes. The string is UTF-8 and is not nul-terminated.
+///
+/// Rust doesn't guarantee the layout of fat pointers, so this struct is not
+/// ABI-stable and may not match what a Rust compiler actually passes.
+struct StrMut {
+  char *ptr;
+  uintptr_t len;
+};
+
+/// A Rust `dyn Visitor` trait object, made of a pointer to its data and a
+/// pointer to its vtable.
+///
+/// Rust doesn't guarantee the layout of fat pointers, so this struct is not
+/// ABI-stable and may not match what a Rust compiler actually passes.
+struct DynMutVisitor {
+  void *data;
+  const void *vtable;
+};
+
+/// A Rust `dyn Visitor` trait object, made of a pointer to its data and a
+/// pointer to its vtable.
+///
+/// Rust doesn't guarantee the layout of fat pointers, so this struct is not
+/// ABI-stable and may not match what a Rust compiler actually passes.
+struct DynVisitor {
+  const void *data;
+  const void *vtable;
+};
+
+extern "C" {
+
+void root(Path path,
+          SliceU8 bytes,
+          SliceMutU8 bytes_mut,
+          Buffer<uint16_t> buffer,
+          SliceSliceU8 nested,
+          StrMut name,
+          DynMutVisitor visitor,
+          DynVisitor shared_visitor);
+
+} // extern "C"
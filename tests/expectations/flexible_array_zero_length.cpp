@@ -0,0 +1,14 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+struct Packet {
+  uint32_t len;
+  uint8_t data[0];
+};
+
+extern "C" {
+
+void root(const Packet *a);
+
+} // extern "C"
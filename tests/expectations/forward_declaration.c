@@ -0,0 +1,50 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct ChildList ChildList;
+
+typedef struct TreeNode TreeNode;
+
+typedef struct Graph Graph;
+
+typedef struct Cell Cell;
+
+struct ChildList {
+  TreeNode *node;
+  ChildList *next;
+};
+
+/**
+ * A tree node, which refers to its parent and to the list of its children.
+ */
+struct TreeNode {
+  TreeNode *parent;
+  ChildList *children;
+  int32_t value;
+};
+
+typedef struct {
+  const Graph *graph;
+  int32_t value;
+} Vertex;
+
+/**
+ * Contains a `Vertex` by value, which points back to the graph, so the
+ * graph has to be declared before the vertex.
+ */
+struct Graph {
+  Vertex root;
+};
+
+typedef union {
+  int32_t value;
+  Cell *cell;
+} Payload;
+
+struct Cell {
+  Payload payload;
+};
+
+void root(TreeNode *tree, Graph graph, Cell cell);
@@ -0,0 +1,49 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+struct ChildList;
+
+struct TreeNode;
+
+struct Graph;
+
+struct Cell;
+
+struct ChildList {
+  TreeNode *node;
+  ChildList *next;
+};
+
+/// A tree node, which refers to its parent and to the list of its children.
+struct TreeNode {
+  TreeNode *parent;
+  ChildList *children;
+  int32_t value;
+};
+
+struct Vertex {
+  const Graph *graph;
+  int32_t value;
+};
+
+/// Contains a `Vertex` by value, which points back to the graph, so the
+/// graph has to be declared before the vertex.
+struct Graph {
+  Vertex root;
+};
+
+union Payload {
+  int32_t value;
+  Cell *cell;
+};
+
+struct Cell {
+  Payload payload;
+};
+
+extern "C" {
+
+void root(TreeNode *tree, Graph graph, Cell cell);
+
+} // extern "C"
@@ -0,0 +1,18 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct Node Node;
+
+typedef struct {
+  Node *ptr;
+  uintptr_t len;
+} Buffer_Node;
+
+struct Node {
+  Buffer_Node children;
+  int32_t value;
+};
+
+void root(Node node);
@@ -0,0 +1,22 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+struct Node;
+
+template<typename T>
+struct Buffer {
+  T *ptr;
+  uintptr_t len;
+};
+
+struct Node {
+  Buffer<Node> children;
+  int32_t value;
+};
+
+extern "C" {
+
+void root(Node node);
+
+} // extern "C"
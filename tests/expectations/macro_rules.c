@@ -0,0 +1,41 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define FOUR 1 << 2
+
+#define ONE 1
+
+#define TWO 2
+
+/**
+ * A point.
+ */
+typedef struct {
+  float x;
+  float y;
+} Point;
+
+typedef struct {
+  Point origin;
+  Point size;
+} Rect;
+
+typedef struct {
+  Rect rect;
+} Inner;
+
+typedef struct {
+  Inner inner;
+} Later;
+
+typedef struct {
+  int32_t value;
+} Wrapped;
+
+Point origin(void);
+
+void root(Rect r, Later l);
+
+Wrapped wrapped(void);
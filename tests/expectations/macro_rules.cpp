@@ -0,0 +1,42 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+static const uint32_t FOUR = 1 << 2;
+
+static const uint32_t ONE = 1;
+
+static const uint32_t TWO = 2;
+
+/// A point.
+struct Point {
+  float x;
+  float y;
+};
+
+struct Rect {
+  Point origin;
+  Point size;
+};
+
+struct Inner {
+  Rect rect;
+};
+
+struct Later {
+  Inner inner;
+};
+
+struct Wrapped {
+  int32_t value;
+};
+
+extern "C" {
+
+Point origin();
+
+void root(Rect r, Later l);
+
+Wrapped wrapped();
+
+} // extern "C"
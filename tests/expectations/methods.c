@@ -0,0 +1,31 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Opaque, with member functions of its own.
+ */
+typedef struct Registry Registry;
+
+typedef struct {
+  int32_t value;
+} Counter;
+#define Counter_MAX 100
+
+bool counter_at_max(const Counter *counter);
+
+/**
+ * The value of the counter.
+ */
+int32_t mylib_counter_get(const Counter *counter);
+
+void mylib_counter_increment(Counter *counter, int32_t by);
+
+void mylib_counter_reset(Counter *counter);
+
+Counter *mylib_registry_add(Registry *registry, Counter counter);
+
+Registry *mylib_registry_new(void);
+
+uintptr_t registry_len(const Registry *registry);
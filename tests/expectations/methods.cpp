@@ -0,0 +1,73 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+namespace mylib {
+
+struct Counter;
+
+/// Opaque, with member functions of its own.
+struct Registry {
+  Registry() = delete;
+  Registry(const Registry&) = delete;
+  Registry& operator=(const Registry&) = delete;
+  ~Registry() = delete;
+
+  Counter *add(Counter counter);
+  uintptr_t registry_len() const;
+};
+
+struct Counter {
+  int32_t value;
+
+  bool operator==(const Counter& other) const {
+    return value == other.value;
+  }
+  bool is_maxed() const;
+  /// The value of the counter.
+  int32_t get() const;
+  void increment(int32_t by);
+  static const int32_t MAX;
+};
+inline const int32_t Counter::MAX = 100;
+
+extern "C" {
+
+bool counter_at_max(const Counter *counter);
+
+/// The value of the counter.
+int32_t mylib_counter_get(const Counter *counter);
+
+void mylib_counter_increment(Counter *counter, int32_t by);
+
+void mylib_counter_reset(Counter *counter);
+
+Counter *mylib_registry_add(Registry *registry, Counter counter);
+
+Registry *mylib_registry_new();
+
+uintptr_t registry_len(const Registry *registry);
+
+} // extern "C"
+
+inline Counter *Registry::add(Counter counter) {
+  return ::mylib::mylib_registry_add(this, counter);
+}
+
+inline uintptr_t Registry::registry_len() const {
+  return ::mylib::registry_len(this);
+}
+
+inline bool Counter::is_maxed() const {
+  return ::mylib::counter_at_max(this);
+}
+
+inline int32_t Counter::get() const {
+  return ::mylib::mylib_counter_get(this);
+}
+
+inline void Counter::increment(int32_t by) {
+  ::mylib::mylib_counter_increment(this, by);
+}
+
+} // namespace mylib
@@ -0,0 +1,31 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+#if defined(MOD_TREE_EXTRA)
+struct Extra {
+  uint32_t flags;
+};
+#endif
+
+struct Point {
+  int32_t x;
+  int32_t y;
+};
+static const int32_t Point_ORIGIN_X = 0;
+static const int32_t Point_ORIGIN_Y = 0;
+
+struct Delta {
+  int32_t dx;
+  int32_t dy;
+};
+
+extern "C" {
+
+#if defined(MOD_TREE_EXTRA)
+void extra_point(Extra extra);
+#endif
+
+void move_point(Point *point, Delta delta);
+
+} // extern "C"
@@ -0,0 +1,33 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define MAX_CHANNELS 8
+
+typedef struct {
+  const float *data;
+  uintptr_t len;
+} Samples;
+
+typedef struct {
+  Samples samples;
+  uint8_t channels;
+} Stream;
+
+typedef struct {
+  uint32_t id;
+} Track;
+
+typedef struct {
+  const uint8_t *pixels;
+  const Samples *sound;
+  Track track;
+} Picture;
+
+typedef struct {
+  Stream stream;
+  Picture picture;
+} Packet;
+
+Stream decode(const Packet *packet, Picture *out);
@@ -0,0 +1,57 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+namespace media {
+
+namespace audio {
+namespace codec {
+
+static const uint8_t MAX_CHANNELS = 8;
+
+struct Samples {
+  const float *data;
+  uintptr_t len;
+};
+
+} // namespace codec
+
+struct Stream {
+  codec::Samples samples;
+  uint8_t channels;
+};
+
+} // namespace audio
+
+namespace video {
+namespace audio {
+
+struct Track {
+  uint32_t id;
+};
+
+} // namespace audio
+
+namespace codec {
+
+struct Picture {
+  const uint8_t *pixels;
+  const ::media::audio::codec::Samples *sound;
+  audio::Track track;
+};
+
+} // namespace codec
+} // namespace video
+
+struct Packet {
+  audio::Stream stream;
+  video::codec::Picture picture;
+};
+
+extern "C" {
+
+audio::Stream decode(const Packet *packet, video::codec::Picture *out);
+
+} // extern "C"
+
+} // namespace media
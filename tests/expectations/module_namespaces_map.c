@@ -0,0 +1,25 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct {
+  uint64_t id;
+} Handle;
+
+typedef struct {
+  Handle handle;
+  uint16_t port;
+} Socket;
+
+typedef struct {
+  Socket *socket;
+} Address;
+
+extern uint32_t SOCKET_COUNT;
+
+Address address_of(Socket *socket);
+
+Handle legacy_open(void);
+
+Socket socket_open(uint16_t port);
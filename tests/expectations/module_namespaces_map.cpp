@@ -0,0 +1,55 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+namespace org {
+namespace ffi {
+
+struct Handle {
+  uint64_t id;
+};
+
+namespace network {
+namespace v1 {
+namespace tcp {
+
+struct Socket {
+  Handle handle;
+  uint16_t port;
+};
+
+} // namespace tcp
+
+struct Address {
+  tcp::Socket *socket;
+};
+
+namespace tcp {
+
+extern "C" {
+
+extern uint32_t SOCKET_COUNT;
+
+Socket socket_open(uint16_t port);
+
+} // extern "C"
+
+} // namespace tcp
+
+extern "C" {
+
+Address address_of(tcp::Socket *socket);
+
+} // extern "C"
+
+} // namespace v1
+} // namespace network
+
+extern "C" {
+
+Handle legacy_open();
+
+} // extern "C"
+
+} // namespace ffi
+} // namespace org
@@ -0,0 +1,40 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+struct Opaque;
+
+template<typename T>
+struct Option;
+
+struct Foo {
+  uint32_t a;
+  uint64_t b;
+  int64_t c;
+  Opaque *d;
+  Opaque *e;
+  const Opaque *f;
+  int32_t (*g)(int32_t);
+  uint8_t h;
+  uint16_t i;
+  uint32_t j;
+  Opaque *k;
+  int8_t l;
+  const float *m;
+  Opaque *n;
+};
+
+using NonZeroU32 = uint32_t;
+
+template<typename T>
+using Cell = T;
+
+struct Bar {
+  const Option<Cell<NonZeroU32>> *a;
+};
+
+extern "C" {
+
+Opaque *root(Foo foo, Bar *bar, uint32_t count);
+
+} // extern "C"
@@ -0,0 +1,14 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct {
+  uint32_t data;
+} ExtType;
+
+typedef struct {
+  uint8_t value;
+} Other;
+
+void consume_ext(ExtType _ext, Other _other);
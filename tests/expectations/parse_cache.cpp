@@ -0,0 +1,17 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+struct ExtType {
+  uint32_t data;
+};
+
+struct Other {
+  uint8_t value;
+};
+
+extern "C" {
+
+void consume_ext(ExtType _ext, Other _other);
+
+} // extern "C"
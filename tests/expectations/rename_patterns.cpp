@@ -0,0 +1,37 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+static const uint32_t MYLIB_DEFAULT_SIZE = 8;
+
+static const uint32_t MYLIB_MAX_POINTS = 64;
+
+enum class MylibColor : uint8_t {
+  Red,
+  Green,
+  Blue,
+};
+
+struct MylibPoint {
+  int32_t x;
+  int32_t y;
+};
+
+struct mylib_handle_t {
+  uint64_t id;
+};
+
+static const MylibPoint MYLIB_ORIGIN = (MylibPoint){ .x = 0, .y = 0 };
+
+extern "C" {
+
+extern uint32_t point_count;
+#define mylib_pointCount point_count
+
+MylibPoint make_point(int32_t x, int32_t y, MylibColor color);
+#define mylib_make_point make_point
+
+mylib_handle_t *open_handle(uint64_t id);
+#define mylib_open_handle open_handle
+
+} // extern "C"
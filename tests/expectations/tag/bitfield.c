@@ -0,0 +1,26 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+struct Mismatched;
+
+struct Flags {
+  /**
+   * Whether it's enabled.
+   */
+  uint32_t enabled : 1;
+  uint32_t level : 3;
+  uint32_t offset : 12;
+  uint32_t id : 16;
+};
+
+/**
+ * Not bitfields, as each field has storage of its own.
+ */
+struct Unpacked {
+  uint8_t low;
+  uint8_t high;
+};
+
+void root(struct Flags a, const struct Mismatched *b, struct Unpacked c);
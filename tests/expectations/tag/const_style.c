@@ -0,0 +1,30 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define FOO 10
+
+#define LARGE 70000
+
+#define LIMIT ((int16_t)7)
+
+#define MAX 4294967296
+
+static const double RATIO = 0.5;
+
+enum { SMALL = 3 };
+
+#define ZOM 3.14
+
+struct Point {
+  int32_t x;
+  int32_t y;
+};
+#define Point_ORIGIN (Point){ .x = 0, .y = 0 }
+static const int32_t Point_DIMENSIONS = 2;
+#define Point_MAX_X 100
+enum { Point_AXES = 2 };
+#define Point_MAX_Y ((int32_t)50)
+
+void root(struct Point x, int32_t y[FOO]);
@@ -0,0 +1,28 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+struct Hiddenish {
+  int32_t x;
+};
+
+struct Point {
+  int32_t x;
+  int32_t y;
+};
+
+struct ExtraColor {
+  uint8_t r;
+  uint8_t g;
+  uint8_t b;
+};
+
+struct ExtraSize {
+  uint32_t w;
+  uint32_t h;
+};
+
+void hiddenish(struct Hiddenish h);
+
+struct Point point_new(int32_t x, int32_t y);
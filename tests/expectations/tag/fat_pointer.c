@@ -0,0 +1,135 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A Rust string slice, made of a pointer to its first byte and the number of
+ * bytes. The string is UTF-8 and is not nul-terminated.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+struct Str {
+  const char *ptr;
+  uintptr_t len;
+};
+
+struct Point {
+  float x;
+  float y;
+};
+
+/**
+ * A Rust slice, made of a pointer to its first element and the number of
+ * elements.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+struct SlicePoint {
+  const struct Point *ptr;
+  uintptr_t len;
+};
+
+struct Path {
+  struct Str name;
+  struct SlicePoint points;
+};
+
+/**
+ * A Rust slice, made of a pointer to its first element and the number of
+ * elements.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+struct SliceU8 {
+  const uint8_t *ptr;
+  uintptr_t len;
+};
+
+/**
+ * A Rust slice, made of a pointer to its first element and the number of
+ * elements.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+struct SliceMutU8 {
+  uint8_t *ptr;
+  uintptr_t len;
+};
+
+/**
+ * A Rust slice, made of a pointer to its first element and the number of
+ * elements.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+struct SliceMutU16 {
+  uint16_t *ptr;
+  uintptr_t len;
+};
+
+struct Buffer_u16 {
+  struct SliceMutU16 data;
+};
+
+/**
+ * A Rust slice, made of a pointer to its first element and the number of
+ * elements.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+struct SliceSliceU8 {
+  const struct SliceU8 *ptr;
+  uintptr_t len;
+};
+
+/**
+ * A Rust string slice, made of a pointer to its first byte and the number of
+ * bytes. The string is UTF-8 and is not nul-terminated.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+struct StrMut {
+  char *ptr;
+  uintptr_t len;
+};
+
+/**
+ * A Rust `dyn Visitor` trait object, made of a pointer to its data and a
+ * pointer to its vtable.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+struct DynMutVisitor {
+  void *data;
+  const void *vtable;
+};
+
+/**
+ * A Rust `dyn Visitor` trait object, made of a pointer to its data and a
+ * pointer to its vtable.
+ *
+ * Rust doesn't guarantee the layout of fat pointers, so this struct is not
+ * ABI-stable and may not match what a Rust compiler actually passes.
+ */
+struct DynVisitor {
+  const void *data;
+  const void *vtable;
+};
+
+void root(struct Path path,
+          struct SliceU8 bytes,
+          struct SliceMutU8 bytes_mut,
+          struct Buffer_u16 buffer,
+          struct SliceSliceU8 nested,
+          struct StrMut name,
+          struct DynMutVisitor visitor,
+          struct DynVisitor shared_visitor);
@@ -0,0 +1,63 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A packet followed by its data.
+ */
+struct Packet {
+  uint32_t len;
+  uint8_t data[];
+};
+
+struct Point {
+  float x;
+  float y;
+};
+
+struct Points {
+  uintptr_t count;
+  struct Point points[];
+};
+
+/**
+ * A marker that isn't a flexible array member, since it's the only field.
+ */
+struct Marker {
+  uint8_t _private[0];
+};
+
+struct Misplaced {
+  uint8_t data[0];
+  uint32_t len;
+};
+
+/**
+ * Embedded by value, so its array can't be a flexible array member.
+ */
+struct Header {
+  uint32_t len;
+  uint32_t tail[0];
+};
+
+struct Message {
+  struct Header header;
+  uint32_t body;
+};
+
+struct Frame {
+  uint16_t len;
+  uint8_t data[0];
+};
+
+struct Frames {
+  struct Frame frames[2];
+};
+
+void root(const struct Packet *a,
+          struct Points *b,
+          const struct Marker *c,
+          struct Misplaced d,
+          struct Message e,
+          struct Frames f);
@@ -0,0 +1,11 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+struct Packet {
+  uint32_t len;
+  uint8_t data[];
+};
+
+void root(const struct Packet *a);
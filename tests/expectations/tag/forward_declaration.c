@@ -0,0 +1,50 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+struct ChildList;
+
+struct TreeNode;
+
+struct Graph;
+
+struct Cell;
+
+struct ChildList {
+  struct TreeNode *node;
+  struct ChildList *next;
+};
+
+/**
+ * A tree node, which refers to its parent and to the list of its children.
+ */
+struct TreeNode {
+  struct TreeNode *parent;
+  struct ChildList *children;
+  int32_t value;
+};
+
+struct Vertex {
+  const struct Graph *graph;
+  int32_t value;
+};
+
+/**
+ * Contains a `Vertex` by value, which points back to the graph, so the
+ * graph has to be declared before the vertex.
+ */
+struct Graph {
+  struct Vertex root;
+};
+
+union Payload {
+  int32_t value;
+  struct Cell *cell;
+};
+
+struct Cell {
+  union Payload payload;
+};
+
+void root(struct TreeNode *tree, struct Graph graph, struct Cell cell);
@@ -0,0 +1,18 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+struct Node;
+
+struct Buffer_Node {
+  struct Node *ptr;
+  uintptr_t len;
+};
+
+struct Node {
+  struct Buffer_Node children;
+  int32_t value;
+};
+
+void root(struct Node node);
@@ -0,0 +1,41 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define FOUR 1 << 2
+
+#define ONE 1
+
+#define TWO 2
+
+/**
+ * A point.
+ */
+struct Point {
+  float x;
+  float y;
+};
+
+struct Rect {
+  struct Point origin;
+  struct Point size;
+};
+
+struct Inner {
+  struct Rect rect;
+};
+
+struct Later {
+  struct Inner inner;
+};
+
+struct Wrapped {
+  int32_t value;
+};
+
+struct Point origin(void);
+
+void root(struct Rect r, struct Later l);
+
+struct Wrapped wrapped(void);
@@ -0,0 +1,31 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Opaque, with member functions of its own.
+ */
+struct Registry;
+
+struct Counter {
+  int32_t value;
+};
+#define Counter_MAX 100
+
+bool counter_at_max(const struct Counter *counter);
+
+/**
+ * The value of the counter.
+ */
+int32_t mylib_counter_get(const struct Counter *counter);
+
+void mylib_counter_increment(struct Counter *counter, int32_t by);
+
+void mylib_counter_reset(struct Counter *counter);
+
+struct Counter *mylib_registry_add(struct Registry *registry, struct Counter counter);
+
+struct Registry *mylib_registry_new(void);
+
+uintptr_t registry_len(const struct Registry *registry);
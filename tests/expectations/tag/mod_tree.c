@@ -0,0 +1,28 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#if defined(MOD_TREE_EXTRA)
+struct Extra {
+  uint32_t flags;
+};
+#endif
+
+struct Point {
+  int32_t x;
+  int32_t y;
+};
+#define Point_ORIGIN_X 0
+#define Point_ORIGIN_Y 0
+
+struct Delta {
+  int32_t dx;
+  int32_t dy;
+};
+
+#if defined(MOD_TREE_EXTRA)
+void extra_point(struct Extra extra);
+#endif
+
+void move_point(struct Point *point, struct Delta delta);
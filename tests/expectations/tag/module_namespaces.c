@@ -0,0 +1,33 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define MAX_CHANNELS 8
+
+struct Samples {
+  const float *data;
+  uintptr_t len;
+};
+
+struct Stream {
+  struct Samples samples;
+  uint8_t channels;
+};
+
+struct Track {
+  uint32_t id;
+};
+
+struct Picture {
+  const uint8_t *pixels;
+  const struct Samples *sound;
+  struct Track track;
+};
+
+struct Packet {
+  struct Stream stream;
+  struct Picture picture;
+};
+
+struct Stream decode(const struct Packet *packet, struct Picture *out);
@@ -0,0 +1,25 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+struct Handle {
+  uint64_t id;
+};
+
+struct Socket {
+  struct Handle handle;
+  uint16_t port;
+};
+
+struct Address {
+  struct Socket *socket;
+};
+
+extern uint32_t SOCKET_COUNT;
+
+struct Address address_of(struct Socket *socket);
+
+struct Handle legacy_open(void);
+
+struct Socket socket_open(uint16_t port);
@@ -0,0 +1,31 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+struct Opaque;
+
+struct Option_Cell_NonZeroU32;
+
+struct Foo {
+  uint32_t a;
+  uint64_t b;
+  int64_t c;
+  struct Opaque *d;
+  struct Opaque *e;
+  const struct Opaque *f;
+  int32_t (*g)(int32_t);
+  uint8_t h;
+  uint16_t i;
+  uint32_t j;
+  struct Opaque *k;
+  int8_t l;
+  const float *m;
+  struct Opaque *n;
+};
+
+struct Bar {
+  const struct Option_Cell_NonZeroU32 *a;
+};
+
+struct Opaque *root(struct Foo foo, struct Bar *bar, uint32_t count);
@@ -0,0 +1,14 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+struct ExtType {
+  uint32_t data;
+};
+
+struct Other {
+  uint8_t value;
+};
+
+void consume_ext(struct ExtType _ext, struct Other _other);
@@ -0,0 +1,20 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <time.h>
+
+typedef void (*Callback)(void *user_data);
+
+struct Foo {
+  int32_t a;
+};
+
+struct Event {
+  struct timespec time;
+  Callback callback;
+  struct Foo* foo;
+  uint8_t* bytes;
+};
+
+void root(const struct Event *event, struct timespec *time);
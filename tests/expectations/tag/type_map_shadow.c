@@ -0,0 +1,16 @@
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+struct instant;
+
+/**
+ * The crate's own type, which a mapping of `other::Duration` doesn't replace.
+ */
+struct Duration {
+  uint64_t secs;
+  uint32_t nanos;
+};
+
+void root(struct Duration a, const struct instant *b);
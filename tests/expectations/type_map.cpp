@@ -0,0 +1,23 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+#include <time.h>
+
+typedef void (*Callback)(void *user_data);
+
+struct Foo {
+  int32_t a;
+};
+
+struct Event {
+  struct timespec time;
+  Callback callback;
+  Foo* foo;
+  uint8_t* bytes;
+};
+
+extern "C" {
+
+void root(const Event *event, struct timespec *time);
+
+} // extern "C"
@@ -0,0 +1,17 @@
+#include <cstdarg>
+#include <cstdint>
+#include <cstdlib>
+
+struct instant;
+
+/// The crate's own type, which a mapping of `other::Duration` doesn't replace.
+struct Duration {
+  uint64_t secs;
+  uint32_t nanos;
+};
+
+extern "C" {
+
+void root(Duration a, const struct instant *b);
+
+} // extern "C"
@@ -0,0 +1,30 @@
+#[bitfield(u32)]
+struct Flags {
+    /// Whether it's enabled.
+    enabled: bool,
+    #[bits(3)]
+    level: u8,
+    /// cbindgen:bitfield=12
+    offset: u16,
+    #[bits(16)]
+    id: u16,
+}
+
+#[bitfield(u8)]
+struct Mismatched {
+    #[bits(3)]
+    low: u8,
+    #[bits(3)]
+    high: u8,
+}
+
+/// Not bitfields, as each field has storage of its own.
+#[repr(C)]
+struct Unpacked {
+    /// cbindgen:bitfield=4
+    low: u8,
+    high: u8,
+}
+
+#[no_mangle]
+pub extern "C" fn root(a: Flags, b: *const Mismatched, c: Unpacked) {}
@@ -0,0 +1,35 @@
+pub const FOO: i32 = 10;
+pub const ZOM: f32 = 3.14;
+pub const MAX: u64 = 4294967296;
+
+/// cbindgen:const-style=enum
+pub const SMALL: u8 = 3;
+
+/// cbindgen:const-style=enum
+pub const LARGE: u32 = 70000;
+
+/// cbindgen:const-style=define
+pub const LIMIT: i16 = 7;
+
+/// cbindgen:const-style=static_const
+pub const RATIO: f64 = 0.5;
+
+#[repr(C)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl Point {
+    pub const ORIGIN: Point = Point { x: 0, y: 0 };
+    /// cbindgen:const-style=static_const
+    pub const DIMENSIONS: i32 = 2;
+    pub const MAX_X: i32 = 100;
+    /// cbindgen:const-style=enum
+    pub const AXES: u8 = 2;
+    /// cbindgen:const-style=define
+    pub const MAX_Y: i32 = 50;
+}
+
+#[no_mangle]
+pub extern "C" fn root(x: Point, y: [i32; FOO]) { }
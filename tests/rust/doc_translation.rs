@@ -0,0 +1,62 @@
+/// A point, made by [`make_point`].
+#[repr(C)]
+pub struct Point {
+    /// The horizontal coordinate, see [`Point::y`] for the other one.
+    x: i32,
+    y: i32,
+}
+
+/**
+ * A handle to a [`Point`].
+ *
+ * * It's never null.
+ * * It's owned by [the library](crate::Point).
+ */
+#[repr(C)]
+pub struct Handle {
+    point: *mut Point,
+}
+
+#[doc = "Shapes that can be drawn."]
+#[doc = ""]
+#[doc = "See [`Point`] and [the docs](https://example.com/shapes.html)."]
+#[repr(C)]
+pub enum Shape {
+    /// Draws a single [`Point`].
+    Dot,
+    Line,
+}
+
+/// Creates a [`Point`], see [`Handle`][handle] for owning one.
+///
+/// # Arguments
+///
+/// * `x` - The horizontal coordinate.
+///   Negative values are to the left.
+/// * `y` - The vertical coordinate.
+///
+/// # Returns
+///
+/// A new [`Point`], never a [`Missing`].
+///
+/// # Safety
+///
+/// Must be called on the main thread.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() {
+/// let point = make_point(1, 2);
+/// # }
+/// ```
+///
+/// [handle]: crate::Handle
+#[no_mangle]
+pub extern "C" fn make_point(x: i32, y: i32) -> Point {
+    Point { x, y }
+}
+
+/// Draws a [`Shape`] at every point of a [`Handle`].
+#[no_mangle]
+pub extern "C" fn draw(shape: Shape, handle: Handle) {}
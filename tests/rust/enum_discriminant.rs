@@ -0,0 +1,32 @@
+pub const BASE: i8 = 10;
+pub const SHIFT: u32 = 2 + 1;
+
+#[repr(i8)]
+enum Evaluated {
+    Shifted = 1 << SHIFT,
+    Added = BASE + 1,
+    Constant = BASE,
+    Byte = b'x' as i8,
+    Negated = -(BASE * 2),
+    Inverted = !0x0f,
+    Truncated = (300 as u8) as i8,
+    Qualified = crate::BASE - 1,
+}
+
+/// Evaluated in the repr type, so inverting doesn't make them negative.
+#[repr(u8)]
+enum Unsigned {
+    Low = !0x0f,
+    High = !0 - 1,
+    Masked = (!0x0f) >> 4,
+}
+
+/// Discriminants that refer to a constant defined elsewhere.
+#[repr(C)]
+enum External {
+    First = EXTERNAL_BASE + 1,
+    Second = (EXTERNAL_BASE as u32) << 2,
+}
+
+#[no_mangle]
+pub extern "C" fn root(e: Evaluated, u: Unsigned, x: External) {}
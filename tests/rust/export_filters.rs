@@ -0,0 +1,69 @@
+#[repr(C)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[no_mangle]
+pub extern "C" fn point_new(x: i32, y: i32) -> Point {
+    Point { x, y }
+}
+
+#[no_mangle]
+pub extern "C" fn internal_reset(p: *mut Point) {}
+
+#[no_mangle]
+pub extern "C" fn internal_dump(p: *const Point) {}
+
+#[repr(C)]
+pub struct Hidden1 {
+    x: i32,
+}
+
+#[no_mangle]
+pub extern "C" fn hidden_1(h: Hidden1) {}
+
+#[repr(C)]
+pub struct Hiddenish {
+    x: i32,
+}
+
+#[no_mangle]
+pub extern "C" fn hiddenish(h: Hiddenish) {}
+
+pub mod ffi {
+    #[repr(C)]
+    pub struct ExtraColor {
+        r: u8,
+        g: u8,
+        b: u8,
+    }
+
+    #[repr(C)]
+    pub struct ExtraSize {
+        w: u32,
+        h: u32,
+    }
+
+    pub mod private {
+        #[repr(C)]
+        pub struct Secret {
+            key: u64,
+        }
+
+        #[no_mangle]
+        pub extern "C" fn secret_get() -> Secret {
+            Secret { key: 0 }
+        }
+
+        #[repr(C)]
+        pub struct ExtraSecret {
+            key: u64,
+        }
+    }
+}
+
+mod ignored {
+    #[no_mangle]
+    pub extern "C" fn ignored_fn(x: i32) {}
+}
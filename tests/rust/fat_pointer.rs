@@ -0,0 +1,33 @@
+pub trait Visitor {
+    fn visit(&mut self, value: u32);
+}
+
+#[repr(C)]
+pub struct Point {
+    x: f32,
+    y: f32,
+}
+
+#[repr(C)]
+pub struct Path<'a> {
+    name: &'a str,
+    points: &'a [Point],
+}
+
+#[repr(C)]
+pub struct Buffer<T> {
+    data: *mut [T],
+}
+
+#[no_mangle]
+pub extern "C" fn root(
+    path: Path,
+    bytes: &[u8],
+    bytes_mut: &mut [u8],
+    buffer: Buffer<u16>,
+    nested: *const [&[u8]],
+    name: *mut str,
+    visitor: *mut dyn Visitor,
+    shared_visitor: &dyn Visitor,
+) {
+}
@@ -0,0 +1,57 @@
+/// A packet followed by its data.
+#[repr(C)]
+struct Packet {
+    len: u32,
+    data: [u8; 0],
+}
+
+#[repr(C)]
+struct Points {
+    count: usize,
+    points: [Point; 0],
+}
+
+#[repr(C)]
+struct Point {
+    x: f32,
+    y: f32,
+}
+
+/// A marker that isn't a flexible array member, since it's the only field.
+#[repr(C)]
+struct Marker {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct Misplaced {
+    data: [u8; 0],
+    len: u32,
+}
+
+/// Embedded by value, so its array can't be a flexible array member.
+#[repr(C)]
+struct Header {
+    len: u32,
+    tail: [u32; 0],
+}
+
+#[repr(C)]
+struct Message {
+    header: Header,
+    body: u32,
+}
+
+#[repr(C)]
+struct Frame {
+    len: u16,
+    data: [u8; 0],
+}
+
+#[repr(C)]
+struct Frames {
+    frames: [Frame; 2],
+}
+
+#[no_mangle]
+pub extern "C" fn root(a: *const Packet, b: *mut Points, c: *const Marker, d: Misplaced, e: Message, f: Frames) {}
@@ -0,0 +1,8 @@
+#[repr(C)]
+struct Packet {
+    len: u32,
+    data: [u8; 0],
+}
+
+#[no_mangle]
+pub extern "C" fn root(a: *const Packet) {}
@@ -0,0 +1,40 @@
+/// A tree node, which refers to its parent and to the list of its children.
+#[repr(C)]
+pub struct TreeNode {
+    parent: *mut TreeNode,
+    children: *mut ChildList,
+    value: i32,
+}
+
+#[repr(C)]
+pub struct ChildList {
+    node: *mut TreeNode,
+    next: *mut ChildList,
+}
+
+/// Contains a `Vertex` by value, which points back to the graph, so the
+/// graph has to be declared before the vertex.
+#[repr(C)]
+pub struct Graph {
+    root: Vertex,
+}
+
+#[repr(C)]
+pub struct Vertex {
+    graph: *const Graph,
+    value: i32,
+}
+
+#[repr(C)]
+pub union Payload {
+    value: i32,
+    cell: *mut Cell,
+}
+
+#[repr(C)]
+pub struct Cell {
+    payload: Payload,
+}
+
+#[no_mangle]
+pub extern "C" fn root(tree: *mut TreeNode, graph: Graph, cell: Cell) {}
@@ -0,0 +1,14 @@
+#[repr(C)]
+struct Buffer<T> {
+    ptr: *mut T,
+    len: usize,
+}
+
+#[repr(C)]
+struct Node {
+    children: Buffer<Node>,
+    value: i32,
+}
+
+#[no_mangle]
+pub extern "C" fn root(node: Node) {}
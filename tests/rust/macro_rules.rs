@@ -0,0 +1,65 @@
+macro_rules! c_struct {
+    ($(#[$attr:meta])* $name:ident { $($field:ident: $ty:ty),* $(,)? }) => {
+        $(#[$attr])*
+        #[repr(C)]
+        pub struct $name {
+            $(pub $field: $ty,)*
+        }
+    };
+}
+
+macro_rules! getter {
+    ($name:ident, $ty:ty, $value:expr) => {
+        #[no_mangle]
+        pub extern "C" fn $name() -> $ty {
+            $value
+        }
+    };
+}
+
+macro_rules! consts {
+    ($($name:ident = $value:expr;)+) => {
+        $(pub const $name: u32 = $value;)+
+    };
+}
+
+c_struct! {
+    /// A point.
+    Point { x: f32, y: f32 }
+}
+
+c_struct!(Rect { origin: Point, size: Point, });
+
+getter!(origin, Point, Point { x: 0.0, y: 0.0 });
+
+consts! {
+    ONE = 1;
+    TWO = 2;
+    FOUR = 1 << 2;
+}
+
+mod inner {
+    c_struct!(Inner { rect: Rect });
+}
+
+macro_rules! with_getter {
+    ($name:ident: $ty:ident) => {
+        c_struct!($ty { value: i32 });
+        getter!($name, $ty, $ty { value: 0 });
+    };
+}
+
+with_getter!(wrapped: Wrapped);
+
+// Invoked before it's defined, as with `#[macro_export]`.
+later!(Later);
+
+#[macro_export]
+macro_rules! later {
+    ($name:ident) => {
+        c_struct!($name { inner: inner::Inner });
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn root(r: Rect, l: Later) {}
@@ -0,0 +1,49 @@
+#[repr(C)]
+pub struct Counter {
+    value: i32,
+}
+
+impl Counter {
+    pub const MAX: i32 = 100;
+}
+
+/// Opaque, with member functions of its own.
+pub struct Registry {
+    counters: Vec<Counter>,
+}
+
+#[no_mangle]
+pub extern "C" fn mylib_counter_increment(counter: *mut Counter, by: i32) {}
+
+/// The value of the counter.
+#[no_mangle]
+pub extern "C" fn mylib_counter_get(counter: *const Counter) -> i32 {
+    0
+}
+
+/// cbindgen:method=false
+#[no_mangle]
+pub extern "C" fn mylib_counter_reset(counter: *mut Counter) {}
+
+/// cbindgen:method=is_maxed
+#[no_mangle]
+pub extern "C" fn counter_at_max(counter: &Counter) -> bool {
+    false
+}
+
+#[no_mangle]
+pub extern "C" fn mylib_registry_new() -> *mut Registry {
+    unimplemented!()
+}
+
+/// cbindgen:method
+#[no_mangle]
+pub extern "C" fn mylib_registry_add(registry: *mut Registry, counter: Counter) -> *mut Counter {
+    unimplemented!()
+}
+
+/// cbindgen:method
+#[no_mangle]
+pub extern "C" fn registry_len(registry: *const Registry) -> usize {
+    0
+}
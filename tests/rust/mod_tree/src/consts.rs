@@ -0,0 +1,6 @@
+use point::Point;
+
+impl Point {
+    pub const ORIGIN_X: i32 = 0;
+    pub const ORIGIN_Y: i32 = 0;
+}
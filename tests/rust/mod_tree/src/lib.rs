@@ -0,0 +1,17 @@
+mod point;
+mod consts;
+#[cfg(feature = "extra")]
+mod extra;
+
+mod nested {
+    pub mod leaf;
+}
+
+pub use point::Point;
+
+#[no_mangle]
+pub extern "C" fn move_point(point: *mut Point, delta: nested::leaf::Delta) {}
+
+#[cfg(feature = "extra")]
+#[no_mangle]
+pub extern "C" fn extra_point(extra: extra::Extra) {}
@@ -0,0 +1,5 @@
+#[repr(C)]
+pub struct Delta {
+    pub dx: i32,
+    pub dy: i32,
+}
@@ -0,0 +1,5 @@
+#[repr(C)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
@@ -0,0 +1,46 @@
+pub mod audio {
+    pub mod codec {
+        pub const MAX_CHANNELS: u8 = 8;
+
+        #[repr(C)]
+        pub struct Samples {
+            data: *const f32,
+            len: usize,
+        }
+    }
+
+    #[repr(C)]
+    pub struct Stream {
+        samples: codec::Samples,
+        channels: u8,
+    }
+}
+
+pub mod video {
+    pub mod audio {
+        #[repr(C)]
+        pub struct Track {
+            id: u32,
+        }
+    }
+
+    pub mod codec {
+        #[repr(C)]
+        pub struct Picture {
+            pixels: *const u8,
+            sound: *const ::audio::codec::Samples,
+            track: super::audio::Track,
+        }
+    }
+}
+
+#[repr(C)]
+pub struct Packet {
+    stream: audio::Stream,
+    picture: video::codec::Picture,
+}
+
+#[no_mangle]
+pub extern "C" fn decode(packet: *const Packet, out: *mut video::codec::Picture) -> audio::Stream {
+    unimplemented!()
+}
@@ -0,0 +1,39 @@
+pub mod legacy {
+    #[repr(C)]
+    pub struct Handle {
+        id: u64,
+    }
+
+    #[no_mangle]
+    pub extern "C" fn legacy_open() -> Handle {
+        unimplemented!()
+    }
+}
+
+pub mod net {
+    pub mod tcp {
+        #[repr(C)]
+        pub struct Socket {
+            handle: ::legacy::Handle,
+            port: u16,
+        }
+
+        #[no_mangle]
+        pub static mut SOCKET_COUNT: u32 = 0;
+
+        #[no_mangle]
+        pub extern "C" fn socket_open(port: u16) -> Socket {
+            unimplemented!()
+        }
+    }
+
+    #[repr(C)]
+    pub struct Address {
+        socket: *mut tcp::Socket,
+    }
+
+    #[no_mangle]
+    pub extern "C" fn address_of(socket: *mut tcp::Socket) -> Address {
+        unimplemented!()
+    }
+}
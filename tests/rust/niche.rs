@@ -0,0 +1,35 @@
+use std::cell::{Cell, UnsafeCell};
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::num::{NonZeroI64, NonZeroU32, NonZeroU64};
+use std::num::Wrapping;
+
+struct Opaque;
+
+#[repr(C)]
+pub struct Foo {
+    a: NonZeroU32,
+    b: Option<NonZeroU64>,
+    c: NonZeroI64,
+    d: Box<Opaque>,
+    e: Option<Box<Opaque>>,
+    f: Option<&'static Opaque>,
+    g: Option<extern "C" fn(i32) -> i32>,
+    h: ManuallyDrop<u8>,
+    i: MaybeUninit<u16>,
+    j: Cell<u32>,
+    k: UnsafeCell<*mut Opaque>,
+    l: Wrapping<i8>,
+    m: *const Cell<f32>,
+    n: Option<ManuallyDrop<Box<Opaque>>>,
+}
+
+#[repr(C)]
+pub struct Bar {
+    // `Cell` and friends hide the niche of the type they wrap.
+    a: *const Option<Cell<NonZeroU32>>,
+}
+
+#[no_mangle]
+pub extern "C" fn root(foo: Foo, bar: *mut Bar, count: Option<NonZeroU32>) -> Option<Box<Opaque>> {
+    None
+}
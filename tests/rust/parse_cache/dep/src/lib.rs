@@ -0,0 +1,4 @@
+#[repr(C)]
+pub struct ExtType {
+    pub data: u32,
+}
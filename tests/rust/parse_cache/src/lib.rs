@@ -0,0 +1,9 @@
+#[cfg(feature = "ext")]
+extern crate dep;
+
+mod other;
+
+#[cfg(feature = "ext")]
+#[no_mangle]
+pub extern "C" fn consume_ext(_ext: dep::ExtType, _other: other::Other) {
+}
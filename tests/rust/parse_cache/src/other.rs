@@ -0,0 +1,4 @@
+#[repr(C)]
+pub struct Other {
+    pub value: u8,
+}
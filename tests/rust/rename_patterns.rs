@@ -0,0 +1,35 @@
+#[repr(C)]
+pub struct PointFfi {
+    x: i32,
+    y: i32,
+}
+
+#[repr(C)]
+pub struct Handle {
+    id: u64,
+}
+
+#[repr(u8)]
+pub enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+pub const ORIGIN: PointFfi = PointFfi { x: 0, y: 0 };
+pub const MAX_POINTS: u32 = 64;
+#[allow(non_upper_case_globals)]
+pub const DefaultSize: u32 = 8;
+
+#[no_mangle]
+pub static mut point_count: u32 = 0;
+
+#[no_mangle]
+pub extern "C" fn make_point(x: i32, y: i32, color: Color) -> PointFfi {
+    PointFfi { x, y }
+}
+
+#[no_mangle]
+pub extern "C" fn open_handle(id: u64) -> *mut Handle {
+    std::ptr::null_mut()
+}
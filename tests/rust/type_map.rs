@@ -0,0 +1,20 @@
+#[repr(C)]
+pub struct Handle<T> {
+    ptr: *mut T,
+}
+
+#[repr(C)]
+pub struct Foo {
+    a: i32,
+}
+
+#[repr(C)]
+pub struct Event {
+    time: libc::timespec,
+    callback: Callback,
+    foo: Handle<Foo>,
+    bytes: Handle<u8>,
+}
+
+#[no_mangle]
+pub extern "C" fn root(event: *const Event, time: *mut libc::timespec) {}
@@ -0,0 +1,15 @@
+/// The crate's own type, which a mapping of `other::Duration` doesn't replace.
+#[repr(C)]
+pub struct Duration {
+    secs: u64,
+    nanos: u32,
+}
+
+/// Mapped by its path in the crate.
+#[repr(C)]
+pub struct Instant {
+    ticks: u64,
+}
+
+#[no_mangle]
+pub extern "C" fn root(a: Duration, b: *const Instant) {}